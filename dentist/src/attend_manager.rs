@@ -1,14 +1,34 @@
 use std::io;
+use std::rc::Rc;
 use std::thread;
 use std::time;
 
-use common::io_handler::IOHandler;
-use common::json_handler::JsonHandler;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use common::clock::{Clock, SystemClock};
+use common::database::Database;
+use common::documents::DocumentPrinter;
+use common::i18n;
+use common::io_handler::{stop_at_input_end, IOHandler, Menu};
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
 use common::service_sheet::SheetWithPriority;
 
+#[derive(Clone)]
+enum Document {
+    AttendanceDeclaration,
+    Prescription,
+}
+
 pub struct AttendManager<R, W> {
     io_handler: IOHandler<R, W>,
-    queue_path: String,
+    queues: QueueRouter,
+    // the queue this station calls pacients from
+    queue_name: String,
+    attendance_history: Database,
+    output_format: OutputFormat,
+    document_printer: DocumentPrinter,
+    clock: Rc<dyn Clock>,
 }
 
 impl<R, W> AttendManager<R, W>
@@ -16,47 +36,130 @@ where
     R: io::BufRead,
     W: io::Write,
 {
-    pub fn new(io_handler: IOHandler<R, W>, queue_path: String) -> Self {
+    pub fn new(
+        io_handler: IOHandler<R, W>,
+        queues: QueueRouter,
+        queue_name: String,
+        attendance_history: Database,
+        output_format: OutputFormat,
+        document_printer: DocumentPrinter,
+    ) -> Self {
         Self {
             io_handler,
-            queue_path,
+            queues,
+            queue_name,
+            attendance_history,
+            output_format,
+            document_printer,
+            clock: Rc::new(SystemClock),
         }
     }
 
-    pub fn start(&mut self) -> ! {
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn io_handler(&self) -> &IOHandler<R, W> {
+        &self.io_handler
+    }
+
+    // runs until the input ends
+    pub fn start(&mut self) -> Result<()> {
+        stop_at_input_end(self.serve())
+    }
+
+    fn serve(&mut self) -> Result<()> {
         loop {
             self.io_handler
-                .write("\nPressione [Enter] para chamar o próximo paciente\n")
-                .unwrap();
-            let _ = self.io_handler.read_line().unwrap();
-
-            match self.call_next_pacient() {
-                Some(sheet) => {
-                    self.io_handler.write(sheet).unwrap();
-                    self.io_handler.write("\nAtendendo paciente...\n").unwrap();
-                    let dur = time::Duration::from_secs(3);
-                    thread::sleep(dur);
-                    self.io_handler.write("Atendimento finalizado\n").unwrap();
-                }
-                None => {
-                    self.io_handler
-                        .write("Não existem fichas no momento na fila.\n")
-                        .unwrap();
-                }
+                .write(i18n::message("dentist.press_enter"))?;
+            let _ = self.io_handler.read_line()?;
+
+            let result = self.attend_next_pacient();
+            self.io_handler.recover(result)?;
+        }
+    }
+
+    fn attend_next_pacient(&mut self) -> Result<()> {
+        match self.call_next_pacient()? {
+            Some(mut sheet) => {
+                self.io_handler.write(self.output_format.render(&sheet))?;
+                sheet.lifecycle_mut().mark_started(self.now());
+                self.io_handler.write(i18n::message("dentist.attending"))?;
+                let dur = time::Duration::from_secs(3);
+                thread::sleep(dur);
+                self.io_handler.write(i18n::message("dentist.finished"))?;
+                sheet.lifecycle_mut().mark_finished(self.now());
+                self.attendance_history.insert(sheet.clone())?;
+                self.issue_documents(&sheet)?;
+            }
+            None => {
+                self.io_handler
+                    .write(i18n::message("dentist.queue_empty"))?;
             }
         }
+        Ok(())
     }
 
-    fn call_next_pacient(&self) -> Option<SheetWithPriority> {
-        let mut sheets: Vec<SheetWithPriority> =
-            JsonHandler::read_from_json(&self.queue_path).unwrap();
+    fn call_next_pacient(&mut self) -> Result<Option<SheetWithPriority>> {
+        let result = self.queues.dequeue(&self.queue_name)?;
 
-        if sheets.is_empty() {
-            return None;
+        Ok(result.map(|mut sheet| {
+            sheet.lifecycle_mut().mark_called(self.now());
+            sheet
+        }))
+    }
+
+    fn issue_documents(&mut self, sheet: &SheetWithPriority) -> Result<()> {
+        let menu = Menu::new(i18n::message("dentist.documents_menu"))
+            .option(
+                i18n::message("dentist.attendance_declaration"),
+                Document::AttendanceDeclaration,
+            )
+            .option(
+                i18n::message("dentist.prescription"),
+                Document::Prescription,
+            )
+            .back(i18n::message("dentist.no_document"));
+
+        while let Some(document) = self.io_handler.choose(&menu)? {
+            let path = match document {
+                Document::AttendanceDeclaration => self
+                    .document_printer
+                    .attendance_declaration(sheet.service_sheet(), self.now())?,
+                Document::Prescription => {
+                    let medications = self.get_medications_input()?;
+                    self.document_printer.prescription(
+                        sheet.service_sheet().pacient_account(),
+                        &medications,
+                        self.now(),
+                    )?
+                }
+            };
+
+            self.io_handler
+                .write(i18n::text("dentist.document_printed", &[("path", &path)]))?;
+        }
+        Ok(())
+    }
+
+    fn now(&self) -> DateTime<Local> {
+        self.clock.local_now()
+    }
+
+    fn get_medications_input(&mut self) -> Result<Vec<String>> {
+        self.io_handler
+            .write(i18n::message("dentist.ask_medications"))?;
+
+        let mut medications = Vec::new();
+        loop {
+            let medication = self.io_handler.read_line()?;
+            if medication.trim().is_empty() {
+                break;
+            }
+            medications.push(medication.trim().to_string());
         }
 
-        let result = sheets.remove(0);
-        JsonHandler::save_as_json(&self.queue_path, &sheets).unwrap();
-        Some(result)
+        Ok(medications)
     }
 }
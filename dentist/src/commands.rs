@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use anyhow::Result;
+use common::clock::Clock;
+use common::command::{CommandLine, CommandStatus};
+use common::database::Database;
+use common::i18n;
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+
+// runs a subcommand without the station loop: records go to stdout, messages
+// to stderr
+pub fn run<W: Write>(
+    command: &CommandLine,
+    queues: &mut QueueRouter,
+    queue_name: &str,
+    attendance_history: &Database,
+    clock: &dyn Clock,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    match command.words().as_slice() {
+        ["call-next"] => call_next(
+            queues,
+            queue_name,
+            attendance_history,
+            clock,
+            output_format,
+            stdout,
+        ),
+        _ => {
+            eprintln!("{}", i18n::message("cli.dentist_usage"));
+            Ok(CommandStatus::Usage)
+        }
+    }
+}
+
+// the called sheet goes to the attendance history right away, since no
+// station is left holding it
+fn call_next<W: Write>(
+    queues: &mut QueueRouter,
+    queue_name: &str,
+    attendance_history: &Database,
+    clock: &dyn Clock,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    let mut sheet = match queues.dequeue(queue_name)? {
+        Some(sheet) => sheet,
+        None => {
+            eprintln!("{}", i18n::message("dentist.queue_empty").trim());
+            return Ok(CommandStatus::NotFound);
+        }
+    };
+
+    sheet.lifecycle_mut().mark_called(clock.local_now());
+    attendance_history.insert(sheet.clone())?;
+    write!(stdout, "{}", output_format.render(&sheet))?;
+
+    Ok(CommandStatus::Done)
+}
@@ -1 +1,2 @@
 pub mod attend_manager;
+pub mod commands;
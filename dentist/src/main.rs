@@ -1,16 +1,86 @@
 use std::env;
+use std::io;
+use std::process::ExitCode;
 
 use anyhow::Result;
-use common::io_handler::{DefaultIOHandler, IOHandler};
+use common::command::CommandLine;
+use common::database::Database;
+use common::dequeue_policy::parse_policy;
+use common::documents::DocumentPrinter;
+use common::i18n::{self, Locale};
+use common::priority_queue::QueueSettings;
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::shutdown;
+use common::transcript::Session;
 use dentist::attend_manager::AttendManager;
+use dentist::commands;
 use dotenv::dotenv;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     dotenv().ok();
+    i18n::set_locale(Locale::from_config(env::var("LOCALE").ok().as_deref())?);
+    shutdown::handle_interrupts()?;
 
-    let dentist_queue_file_path = env::var("DENTIST_QUEUE_FILE_PATH")?;
-    let io_handler = IOHandler::default();
+    let command = CommandLine::parse(env::args().skip(1));
+    let output_format = OutputFormat::from_args(env::args().skip(1))?;
+    let mut session = Session::from_args(env::args().skip(1))?.start()?;
+    let queue_settings = QueueSettings::from_config(
+        env::var("PRIORITY_CATEGORIES").ok().as_deref(),
+        env::var("PRIORITY_AGING_MINUTES").ok().as_deref(),
+    )?
+    .with_policy(parse_policy(
+        env::var("DENTIST_DEQUEUE_POLICY").ok().as_deref(),
+    )?)
+    .with_clock(session.clock());
+    let mut queues = QueueRouter::from_config(
+        &QueueRouter::queues_config(
+            env::var("DENTIST_QUEUES").ok().as_deref(),
+            env::var("DENTIST_QUEUE_FILE_PATH").ok().as_deref(),
+        )?,
+        env::var("DENTIST_QUEUE_ROUTES").ok().as_deref(),
+        &queue_settings,
+    )?
+    .with_files(|path| session.data_file(path))?;
+    let queue_name = match env::var("DENTIST_STATION_QUEUE") {
+        Ok(name) if !name.trim().is_empty() => name.trim().to_string(),
+        _ => queues.names()[0].to_string(),
+    };
+    queues.queue(&queue_name)?;
 
-    let mut servecing = AttendManager::new(io_handler, dentist_queue_file_path);
-    servecing.start();
+    let document_printer = DocumentPrinter::new(
+        session.data_dir(env::var("DOCUMENTS_DIR")?)?,
+        env::var("DOCUMENT_TEMPLATES_DIR").ok(),
+    )?
+    .with_clock(session.clock());
+
+    let attendance_history =
+        Database::new(session.data_file(env::var("ATTENDANCE_HISTORY_DATABASE")?)?)?;
+
+    if !command.is_interactive() {
+        return Ok(commands::run(
+            &command,
+            &mut queues,
+            &queue_name,
+            &attendance_history,
+            session.clock().as_ref(),
+            output_format,
+            &mut io::stdout(),
+        )?
+        .into());
+    }
+
+    let mut servecing = AttendManager::new(
+        session.io_handler()?,
+        queues,
+        queue_name,
+        attendance_history,
+        output_format,
+        document_printer,
+    )
+    .with_clock(session.clock());
+    let result = servecing.start();
+    result.and(session.finish(servecing.io_handler()))?;
+
+    Ok(shutdown::exit_code())
 }
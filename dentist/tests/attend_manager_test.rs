@@ -0,0 +1,44 @@
+use anyhow::Result;
+use common::database::Database;
+use common::documents::DocumentPrinter;
+use common::priority_queue::QueueSettings;
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::transcript::Session;
+use dentist::attend_manager::AttendManager;
+
+// a recorded attendance: the pacient the receptionist sent is called and
+// leaves with an attendance declaration
+#[test]
+fn recorded_session_test() -> Result<()> {
+    let mut session = Session::Replay(format!(
+        "{}/tests/sessions/attend_manager.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .start()?;
+
+    // the settings of the .env the session was recorded with
+    let queue_settings = QueueSettings::from_config(None, Some("30"))?.with_clock(session.clock());
+    let queues = QueueRouter::from_config(
+        "geral=dentist_queue.json,ortodontia=orthodontics_queue.json,\
+         endodontia=endodontics_queue.json,limpeza=cleaning_queue.json",
+        Some("reason:aparelho=ortodontia,reason:canal=endodontia,procedure:Cleaning=limpeza"),
+        &queue_settings,
+    )?
+    .with_files(|path| session.data_file(path))?;
+    let document_printer = DocumentPrinter::new(session.data_dir("documents".to_string())?, None)?
+        .with_clock(session.clock());
+
+    let mut manager = AttendManager::new(
+        session.io_handler()?,
+        queues,
+        "geral".to_string(),
+        Database::new(session.data_file("attendance_history.json.db".to_string())?)?,
+        OutputFormat::Text,
+        document_printer,
+    )
+    .with_clock(session.clock());
+    manager.start()?;
+
+    session.finish(manager.io_handler())
+}
@@ -0,0 +1,56 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+
+    // the same time in the local timezone, as records keep it
+    fn local_now(&self) -> DateTime<Local> {
+        Local
+            .from_local_datetime(&self.now())
+            .earliest()
+            .unwrap_or_else(Local::now)
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn local_now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+// a clock that only moves when told to, clones share the same time
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Rc<Cell<NaiveDateTime>>,
+}
+
+impl ManualClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self {
+            now: Rc::new(Cell::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> NaiveDateTime {
+        self.now.get()
+    }
+}
@@ -0,0 +1,94 @@
+use std::process::ExitCode;
+
+// options followed by a value, which isn't taken for a subcommand word
+const VALUE_OPTIONS: &[&str] = &[
+    "--output",
+    "--record",
+    "--replay",
+    "--date",
+    "--priority",
+    "--pin",
+];
+
+// the subcommand words and options a station was started with, such as
+// `appointments list --date 05-03-2024`; with no words the station runs its
+// interactive menus
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandLine {
+    words: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl CommandLine {
+    pub fn parse<I>(args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut command = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                command.words.push(arg);
+            } else if let Some((name, value)) = arg.split_once('=') {
+                command
+                    .options
+                    .push((name.to_string(), Some(value.to_string())));
+            } else if VALUE_OPTIONS.contains(&arg.as_str()) {
+                let value = args.next();
+                command.options.push((arg, value));
+            } else {
+                command.options.push((arg, None));
+            }
+        }
+
+        command
+    }
+
+    pub fn words(&self) -> Vec<&str> {
+        self.words.iter().map(String::as_str).collect()
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    // the last value given to `name`, as in `--date 05-03-2024`
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+}
+
+// how a subcommand ended; failures are errors, which exit with 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    Done,
+    Usage,
+    NotFound,
+    Refused,
+}
+
+impl CommandStatus {
+    pub fn code(&self) -> u8 {
+        match self {
+            CommandStatus::Done => 0,
+            CommandStatus::Usage => 2,
+            CommandStatus::NotFound => 3,
+            CommandStatus::Refused => 4,
+        }
+    }
+}
+
+impl From<CommandStatus> for ExitCode {
+    fn from(status: CommandStatus) -> Self {
+        ExitCode::from(status.code())
+    }
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+
+// a CPF whose check digits are right, kept as its 11 digits; it is written
+// with the mask, as in 123.456.789-09, and saved and used as a key without it
+//
+// records saved before CPFs were checked may hold one that isn't valid; it is
+// read as a legacy CPF, kept and shown as it was saved, so the rest of the
+// file can still be read and the record fixed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(from = "String", into = "String")]
+pub struct Cpf {
+    digits: String,
+    legacy: bool,
+}
+
+impl Cpf {
+    // dots, dashes and spaces are dropped, the rest must be the 11 digits
+    pub fn parse(input: &str) -> Result<Self, InvalidCpf> {
+        let digits: String = input
+            .chars()
+            .filter(|char| !matches!(char, '.' | '-' | ' '))
+            .collect();
+
+        if digits.len() != 11 || !digits.chars().all(|char| char.is_ascii_digit()) {
+            return Err(InvalidCpf);
+        }
+
+        let numbers: Vec<u32> = digits
+            .chars()
+            .filter_map(|char| char.to_digit(10))
+            .collect();
+        // numbers like 111.111.111-11 have the right check digits but were
+        // never given to anyone
+        if numbers.iter().all(|number| *number == numbers[0]) {
+            return Err(InvalidCpf);
+        }
+        if check_digit(&numbers[..9]) != numbers[9] || check_digit(&numbers[..10]) != numbers[10] {
+            return Err(InvalidCpf);
+        }
+
+        Ok(Self {
+            digits,
+            legacy: false,
+        })
+    }
+
+    // a saved value that isn't a valid CPF, kept as it is
+    pub fn legacy(value: &str) -> Self {
+        Self {
+            digits: value.trim().to_string(),
+            legacy: true,
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    // the value a legacy CPF was saved with
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    pub fn masked(&self) -> String {
+        if self.legacy {
+            return self.digits.clone();
+        }
+
+        format!(
+            "{}.{}.{}-{}",
+            &self.digits[..3],
+            &self.digits[3..6],
+            &self.digits[6..9],
+            &self.digits[9..]
+        )
+    }
+}
+
+// each digit is weighted from the count of digits plus one down to 2
+fn check_digit(numbers: &[u32]) -> u32 {
+    let weight = numbers.len() as u32 + 1;
+    let sum: u32 = numbers
+        .iter()
+        .enumerate()
+        .map(|(idx, number)| number * (weight - idx as u32))
+        .sum();
+
+    match sum * 10 % 11 {
+        10 => 0,
+        digit => digit,
+    }
+}
+
+impl Display for Cpf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.masked())
+    }
+}
+
+impl FromStr for Cpf {
+    type Err = InvalidCpf;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+// how saved CPFs are read, the ones that aren't valid as legacy CPFs
+impl From<String> for Cpf {
+    fn from(input: String) -> Self {
+        Self::parse(&input).unwrap_or_else(|_| Self::legacy(&input))
+    }
+}
+
+impl From<Cpf> for String {
+    fn from(cpf: Cpf) -> Self {
+        cpf.digits
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidCpf;
+
+impl Display for InvalidCpf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", i18n::message("prompt.invalid_cpf"))
+    }
+}
+
+impl Error for InvalidCpf {}
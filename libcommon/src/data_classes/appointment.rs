@@ -1,38 +1,64 @@
-use std::fmt::Display;
+use std::cmp::Reverse;
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+use crate::cpf::Cpf;
 use crate::database::GetKeyAttribute;
+use crate::i18n;
+use crate::priority_queue::Priority;
+use crate::render::{Field, Render};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Appointment {
-    pub cpf: String,
+    pub cpf: Cpf,
     pub date: String,
 }
 
 impl Appointment {
-    pub fn new(cpf: String, date: String) -> Self {
+    pub fn new(cpf: Cpf, date: String) -> Self {
         Self { cpf, date }
     }
 
-    pub fn cpf(&self) -> &str {
+    pub fn cpf(&self) -> &Cpf {
         &self.cpf
     }
 
     pub fn date(&self) -> &str {
         &self.date
     }
+
+    // `None` when the date can't be read
+    pub fn day(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.date.trim(), "%d-%m-%Y").ok()
+    }
+
+    // booked for today or a later day
+    pub fn is_upcoming(&self, today: NaiveDate) -> bool {
+        self.day().is_some_and(|day| day >= today)
+    }
 }
 
 impl GetKeyAttribute for Appointment {
     fn get_key_attribute(&self) -> String {
-        self.cpf.to_string()
+        self.cpf.digits().to_string()
+    }
+}
+
+// the earliest appointment comes first, the ones with an unreadable date last
+impl Priority for Appointment {
+    type Key = Option<Reverse<NaiveDate>>;
+
+    fn priority(&self) -> Self::Key {
+        self.day().map(Reverse)
     }
 }
 
-impl Display for Appointment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "CPF: {}", self.cpf)?;
-        writeln!(f, "Data marcada: {}", self.date)
+impl Render for Appointment {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::new("cpf", i18n::message("field.cpf"), &self.cpf),
+            Field::new("date", i18n::message("field.appointment_date"), &self.date),
+        ]
     }
 }
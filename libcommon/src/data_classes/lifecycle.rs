@@ -0,0 +1,96 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::datetime_parsing::{parse_datetime_from_default_fmt, parse_default_fmt_to_datetime};
+use crate::i18n;
+use crate::render::{Field, Render};
+
+// when something entered a queue, was called, and was attended
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lifecycle {
+    #[serde(default)]
+    issued_at: Option<String>,
+    #[serde(default)]
+    called_at: Option<String>,
+    #[serde(default)]
+    started_at: Option<String>,
+    #[serde(default)]
+    finished_at: Option<String>,
+}
+
+impl Lifecycle {
+    pub fn issued_at(&self) -> Option<&str> {
+        self.issued_at.as_deref()
+    }
+
+    pub fn called_at(&self) -> Option<&str> {
+        self.called_at.as_deref()
+    }
+
+    pub fn started_at(&self) -> Option<&str> {
+        self.started_at.as_deref()
+    }
+
+    pub fn finished_at(&self) -> Option<&str> {
+        self.finished_at.as_deref()
+    }
+
+    pub fn mark_issued(&mut self, datetime: DateTime<Local>) {
+        self.issued_at = Some(parse_datetime_from_default_fmt(datetime));
+    }
+
+    pub fn mark_called(&mut self, datetime: DateTime<Local>) {
+        self.called_at = Some(parse_datetime_from_default_fmt(datetime));
+    }
+
+    pub fn mark_started(&mut self, datetime: DateTime<Local>) {
+        self.started_at = Some(parse_datetime_from_default_fmt(datetime));
+    }
+
+    pub fn mark_finished(&mut self, datetime: DateTime<Local>) {
+        self.finished_at = Some(parse_datetime_from_default_fmt(datetime));
+    }
+
+    // from being issued until being called
+    pub fn waited_minutes(&self) -> Option<i64> {
+        minutes_between(self.issued_at()?, self.called_at()?)
+    }
+
+    // from the start until the end of the attendance
+    pub fn service_minutes(&self) -> Option<i64> {
+        minutes_between(self.started_at()?, self.finished_at()?)
+    }
+}
+
+fn minutes_between(start: &str, end: &str) -> Option<i64> {
+    let start = parse_default_fmt_to_datetime(start)?;
+    let end = parse_default_fmt_to_datetime(end)?;
+    Some((end - start).num_minutes().max(0))
+}
+
+impl Render for Lifecycle {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::optional(
+                "issued_at",
+                i18n::message("field.issued_at"),
+                self.issued_at(),
+            ),
+            Field::optional(
+                "called_at",
+                i18n::message("field.called_at"),
+                self.called_at(),
+            ),
+            Field::optional(
+                "started_at",
+                i18n::message("field.started_at"),
+                self.started_at(),
+            ),
+            Field::optional(
+                "finished_at",
+                i18n::message("field.finished_at"),
+                self.finished_at(),
+            ),
+        ]
+    }
+}
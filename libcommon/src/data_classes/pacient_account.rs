@@ -1,13 +1,16 @@
-use std::fmt::Display;
-
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 
+use crate::cpf::Cpf;
 use crate::database::{Database, GetKeyAttribute};
 use crate::datetime_parsing::parse_datetime_from_default_fmt;
+use crate::form::{Form, FormField};
+use crate::i18n;
+use crate::io_handler::Prompt;
+use crate::render::{Field, Render};
 use crate::service_sheet::ServiceSheet;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Address {
     street: String,
     neighborhood: String,
@@ -34,12 +37,48 @@ impl Address {
     pub fn city(&self) -> &str {
         &self.city
     }
+
+    pub fn form<'a>() -> Form<'a, Address> {
+        Form::new()
+            .field(FormField::new(
+                "street",
+                i18n::message("field.street"),
+                |address: &Address| address.street.clone(),
+                |address, street| address.street = street,
+            ))
+            .field(FormField::new(
+                "neighborhood",
+                i18n::message("field.neighborhood"),
+                |address: &Address| address.neighborhood.clone(),
+                |address, neighborhood| address.neighborhood = neighborhood,
+            ))
+            .field(FormField::new(
+                "city",
+                i18n::message("field.city"),
+                |address: &Address| address.city.clone(),
+                |address, city| address.city = city,
+            ))
+    }
+}
+
+impl Render for Address {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::new("street", i18n::message("field.street"), &self.street),
+            Field::new(
+                "neighborhood",
+                i18n::message("field.neighborhood"),
+                &self.neighborhood,
+            ),
+            Field::new("city", i18n::message("field.city"), &self.city),
+        ]
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Pacient {
     name: String,
-    cpf: String,
+    cpf: Cpf,
     phone_number: String,
     date_of_birth: String,
     address: Address,
@@ -49,7 +88,7 @@ pub struct Pacient {
 impl Pacient {
     pub fn new(
         name: String,
-        cpf: String,
+        cpf: Cpf,
         phone_number: String,
         date_of_birth: String,
         address: Address,
@@ -65,11 +104,60 @@ impl Pacient {
         }
     }
 
+    // an account with only its key, for its form to fill in the rest
+    pub fn empty(cpf: Cpf, datetime_of_creation: DateTime<Local>) -> Self {
+        Self::new(
+            String::new(),
+            cpf,
+            String::new(),
+            String::new(),
+            Address::default(),
+            datetime_of_creation,
+        )
+    }
+
+    // the cpf is the key of the account, so it is given before the form is
+    // filled in and can't be changed afterwards; no one is born after `today`
+    pub fn form<'a>(today: NaiveDate) -> Form<'a, Pacient> {
+        Form::new()
+            .field(FormField::new(
+                "name",
+                i18n::message("field.name"),
+                |pacient: &Pacient| pacient.name.clone(),
+                |pacient, name| pacient.name = name,
+            ))
+            .field(FormField::read_only(
+                "cpf",
+                i18n::message("field.cpf"),
+                |pacient: &Pacient| pacient.cpf.to_string(),
+            ))
+            .field(FormField::new(
+                "phone_number",
+                i18n::message("field.phone_number"),
+                |pacient: &Pacient| pacient.phone_number.clone(),
+                |pacient, phone_number| pacient.phone_number = phone_number,
+            ))
+            .field(
+                FormField::new(
+                    "date_of_birth",
+                    i18n::message("field.date_of_birth"),
+                    |pacient: &Pacient| pacient.date_of_birth.clone(),
+                    |pacient, date_of_birth| pacient.date_of_birth = date_of_birth,
+                )
+                .parse_with(move |input| parse_date_of_birth(input, today)),
+            )
+            .nest(
+                Address::form(),
+                |pacient| &pacient.address,
+                |pacient| &mut pacient.address,
+            )
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub fn cpf(&self) -> &str {
+    pub fn cpf(&self) -> &Cpf {
         &self.cpf
     }
 
@@ -126,20 +214,38 @@ impl Pacient {
     }
 }
 
+fn parse_date_of_birth(input: &str, today: NaiveDate) -> Result<String, String> {
+    let date = Prompt::date("").parse(input)?;
+    if date > today {
+        return Err(i18n::message("form.birth_in_future").to_string());
+    }
+
+    Ok(date.format("%d-%m-%Y").to_string())
+}
+
 impl GetKeyAttribute for Pacient {
     fn get_key_attribute(&self) -> String {
-        self.cpf.to_string()
+        self.cpf.digits().to_string()
     }
 }
 
-impl Display for Pacient {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "Nome: {}", &self.name)?;
-        writeln!(f, "CPF: {}", &self.cpf)?;
-        writeln!(f, "Contato: {}", &self.phone_number)?;
-        writeln!(f, "Data de nascimento: {}", &self.date_of_birth)?;
-        writeln!(f, "Rua: {}", &self.street())?;
-        writeln!(f, "Bairro: {}", &self.neighborhood())?;
-        writeln!(f, "Cidade: {}", &self.city())
+impl Render for Pacient {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![
+            Field::new("name", i18n::message("field.name"), &self.name),
+            Field::new("cpf", i18n::message("field.cpf"), &self.cpf),
+            Field::new(
+                "phone_number",
+                i18n::message("field.phone_number"),
+                &self.phone_number,
+            ),
+            Field::new(
+                "date_of_birth",
+                i18n::message("field.date_of_birth"),
+                &self.date_of_birth,
+            ),
+        ];
+        fields.extend(self.address.fields());
+        fields
     }
 }
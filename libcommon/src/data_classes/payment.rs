@@ -0,0 +1,135 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::cpf::Cpf;
+use crate::database::GetKeyAttribute;
+use crate::datetime_parsing::parse_datetime_from_default_fmt;
+use crate::i18n;
+use crate::render::{Field, Render};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Procedure {
+    RoutineCheckup,
+    Filling,
+    Cleaning,
+}
+
+impl Procedure {
+    pub const ALL: &'static [Procedure] = &[
+        Procedure::RoutineCheckup,
+        Procedure::Filling,
+        Procedure::Cleaning,
+    ];
+
+    pub fn price(&self) -> usize {
+        match self {
+            Procedure::RoutineCheckup => 200,
+            Procedure::Filling => 100,
+            Procedure::Cleaning => 50,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|procedure| format!("{:?}", procedure).eq_ignore_ascii_case(value.trim()))
+            .copied()
+    }
+
+    pub fn label(&self) -> &'static str {
+        i18n::message(match self {
+            Procedure::RoutineCheckup => "procedure.routine_checkup",
+            Procedure::Filling => "procedure.filling",
+            Procedure::Cleaning => "procedure.cleaning",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaymentMethod {
+    Pix,
+    Card,
+    Cash,
+}
+
+impl PaymentMethod {
+    pub const ALL: &'static [PaymentMethod] =
+        &[PaymentMethod::Pix, PaymentMethod::Card, PaymentMethod::Cash];
+
+    pub fn label(&self) -> &'static str {
+        i18n::message(match self {
+            PaymentMethod::Pix => "payment_method.pix",
+            PaymentMethod::Card => "payment_method.card",
+            PaymentMethod::Cash => "payment_method.cash",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Payment {
+    cpf: Cpf,
+    procedure: Procedure,
+    method: PaymentMethod,
+    amount: usize,
+    date: String,
+}
+
+impl Payment {
+    pub fn new(
+        cpf: Cpf,
+        procedure: Procedure,
+        method: PaymentMethod,
+        datetime: DateTime<Local>,
+    ) -> Self {
+        Self {
+            cpf,
+            procedure,
+            method,
+            amount: procedure.price(),
+            date: parse_datetime_from_default_fmt(datetime),
+        }
+    }
+
+    pub fn cpf(&self) -> &Cpf {
+        &self.cpf
+    }
+
+    pub fn procedure(&self) -> Procedure {
+        self.procedure
+    }
+
+    pub fn method(&self) -> PaymentMethod {
+        self.method
+    }
+
+    pub fn amount(&self) -> usize {
+        self.amount
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+}
+
+impl GetKeyAttribute for Payment {
+    fn get_key_attribute(&self) -> String {
+        self.cpf.digits().to_string()
+    }
+}
+
+impl Render for Payment {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::new("cpf", i18n::message("field.cpf"), &self.cpf),
+            Field::new(
+                "procedure",
+                i18n::message("field.procedure"),
+                self.procedure.label(),
+            ),
+            Field::new("method", i18n::message("field.method"), self.method.label()),
+            Field::number("amount", i18n::message("field.amount"), self.amount)
+                .shown_as(format!("R${}", self.amount)),
+            Field::new("date", i18n::message("field.date"), &self.date),
+        ]
+    }
+}
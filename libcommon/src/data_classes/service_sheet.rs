@@ -1,18 +1,24 @@
-use std::fmt::Display;
-
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
 use crate::database::GetKeyAttribute;
-use crate::datetime_parsing::parse_datetime_from_default_fmt;
+use crate::datetime_parsing::{parse_datetime_from_default_fmt, parse_default_fmt_to_datetime};
+use crate::i18n;
+use crate::lifecycle::Lifecycle;
 use crate::pacient_account::Pacient;
+use crate::payment::Procedure;
 use crate::priority_queue::{Priority, TicketPriority};
+use crate::render::{Field, Render};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ServiceSheet {
     pacient: Pacient,
     reason: String,
     date: String,
+    #[serde(default)]
+    dentist: Option<String>,
+    #[serde(default)]
+    procedure: Option<Procedure>,
 }
 
 impl ServiceSheet {
@@ -21,13 +27,31 @@ impl ServiceSheet {
             pacient,
             reason,
             date: parse_datetime_from_default_fmt(datetime),
+            dentist: None,
+            procedure: None,
         }
     }
 
+    // the dentist the pacient asked for, if any
+    pub fn with_dentist(mut self, dentist: Option<String>) -> Self {
+        self.dentist = dentist;
+        self
+    }
+
+    // the procedure the pacient came for, when already known
+    pub fn with_procedure(mut self, procedure: Option<Procedure>) -> Self {
+        self.procedure = procedure;
+        self
+    }
+
     pub fn pacient(&self) -> &str {
         self.pacient.name()
     }
 
+    pub fn pacient_account(&self) -> &Pacient {
+        &self.pacient
+    }
+
     pub fn reason(&self) -> &str {
         self.reason.as_str()
     }
@@ -35,18 +59,58 @@ impl ServiceSheet {
     pub fn date(&self) -> &str {
         self.date.as_str()
     }
+
+    pub fn dentist(&self) -> Option<&str> {
+        self.dentist.as_deref()
+    }
+
+    pub fn procedure(&self) -> Option<Procedure> {
+        self.procedure
+    }
+}
+
+impl Render for ServiceSheet {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![
+            Field::new(
+                "pacient",
+                i18n::message("field.pacient"),
+                self.pacient.name(),
+            ),
+            Field::new("cpf", i18n::message("field.cpf"), self.pacient.cpf()),
+            Field::new("reason", i18n::message("field.reason"), &self.reason),
+            Field::new("date", i18n::message("field.date"), &self.date),
+        ];
+        if let Some(dentist) = &self.dentist {
+            fields.push(Field::new(
+                "dentist",
+                i18n::message("field.dentist"),
+                dentist,
+            ));
+        }
+        if let Some(procedure) = self.procedure {
+            fields.push(Field::new(
+                "procedure",
+                i18n::message("field.procedure"),
+                procedure.label(),
+            ));
+        }
+        fields
+    }
 }
 
 impl GetKeyAttribute for ServiceSheet {
     fn get_key_attribute(&self) -> String {
-        self.pacient.cpf().to_string()
+        self.pacient.cpf().digits().to_string()
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SheetWithPriority {
     service_sheet: ServiceSheet,
     priority: TicketPriority,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
 }
 
 impl SheetWithPriority {
@@ -54,6 +118,7 @@ impl SheetWithPriority {
         Self {
             service_sheet,
             priority,
+            lifecycle: Lifecycle::default(),
         }
     }
 
@@ -64,17 +129,47 @@ impl SheetWithPriority {
     pub fn priority(&self) -> TicketPriority {
         self.priority
     }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
+}
+
+impl GetKeyAttribute for SheetWithPriority {
+    fn get_key_attribute(&self) -> String {
+        self.service_sheet.pacient.cpf().digits().to_string()
+    }
 }
 
 impl Priority for SheetWithPriority {
+    type Key = TicketPriority;
+
     fn priority(&self) -> TicketPriority {
         self.priority
     }
+
+    // pacients wait for the dentist from the moment their sheet is filled
+    fn enqueued_at(&self) -> Option<NaiveDateTime> {
+        let issued_at = self
+            .lifecycle
+            .issued_at()
+            .unwrap_or(self.service_sheet.date());
+        parse_default_fmt_to_datetime(issued_at)
+    }
 }
 
-impl Display for SheetWithPriority {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.service_sheet.pacient)?;
-        write!(f, "Razão: {}", self.service_sheet.reason)
+impl Render for SheetWithPriority {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = self.service_sheet.pacient.fields();
+        fields.push(Field::new(
+            "reason",
+            i18n::message("field.reason"),
+            &self.service_sheet.reason,
+        ));
+        fields
     }
 }
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::database::GetKeyAttribute;
+use crate::i18n;
+use crate::lifecycle::Lifecycle;
+use crate::priority_queue::{Priority, PriorityQueueTicket, TicketPriority};
+use crate::render::{Field, Render};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TicketOutcome {
+    #[default]
+    Attended,
+    NoShow,
+    Cancelled,
+    // called and put back in the queue; the ticket gets another record once
+    // it leaves the queue for good
+    Requeued,
+}
+
+impl TicketOutcome {
+    pub fn label(&self) -> &'static str {
+        i18n::message(match self {
+            TicketOutcome::Attended => "outcome.attended",
+            TicketOutcome::NoShow => "outcome.no_show",
+            TicketOutcome::Cancelled => "outcome.cancelled",
+            TicketOutcome::Requeued => "outcome.requeued",
+        })
+    }
+
+    // whether the ticket left the queue for good, rather than being put back
+    pub fn is_final(&self) -> bool {
+        *self != TicketOutcome::Requeued
+    }
+}
+
+// a ticket that has left the pacient queue
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TicketRecord {
+    code: usize,
+    priority: TicketPriority,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+    #[serde(default)]
+    recalls: usize,
+    #[serde(default)]
+    outcome: TicketOutcome,
+}
+
+impl TicketRecord {
+    pub fn new(ticket: &PriorityQueueTicket) -> Self {
+        Self {
+            code: ticket.code(),
+            priority: ticket.priority(),
+            lifecycle: ticket.lifecycle().clone(),
+            recalls: ticket.recalls(),
+            outcome: TicketOutcome::Attended,
+        }
+    }
+
+    pub fn with_outcome(mut self, outcome: TicketOutcome) -> Self {
+        self.outcome = outcome;
+        self
+    }
+
+    pub fn code(&self) -> usize {
+        self.code
+    }
+
+    pub fn priority(&self) -> TicketPriority {
+        self.priority
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn issued_at(&self) -> Option<&str> {
+        self.lifecycle.issued_at()
+    }
+
+    pub fn called_at(&self) -> Option<&str> {
+        self.lifecycle.called_at()
+    }
+
+    pub fn recalls(&self) -> usize {
+        self.recalls
+    }
+
+    pub fn outcome(&self) -> TicketOutcome {
+        self.outcome
+    }
+}
+
+impl GetKeyAttribute for TicketRecord {
+    fn get_key_attribute(&self) -> String {
+        self.code.to_string()
+    }
+}
+
+impl Render for TicketRecord {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![
+            Field::new(
+                "code",
+                i18n::message("field.code"),
+                self.priority.format_code(self.code),
+            ),
+            Field::new(
+                "priority",
+                i18n::message("field.priority"),
+                self.priority.label(),
+            ),
+        ];
+        fields.extend(self.lifecycle.fields());
+        fields.push(Field::number(
+            "recalls",
+            i18n::message("field.recalls"),
+            self.recalls,
+        ));
+        fields.push(Field::new(
+            "outcome",
+            i18n::message("field.outcome"),
+            self.outcome.label(),
+        ));
+        fields
+    }
+}
@@ -2,9 +2,10 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use serde::{de, Serialize};
 
+use crate::i18n;
 use crate::json_handler::JsonHandler;
 
 pub trait GetKeyAttribute {
@@ -16,13 +17,14 @@ pub struct Database {
 }
 
 impl Database {
-    pub fn new(path: String) -> Self {
+    pub fn new(path: String) -> Result<Self> {
         if !Path::new(&path).exists() {
-            let mut file = File::create(&path).unwrap();
-            file.write_all("[]".as_bytes()).unwrap();
+            File::create(&path)
+                .and_then(|mut file| file.write_all("[]".as_bytes()))
+                .with_context(|| i18n::text("database.create_failed", &[("path", &path)]))?;
         }
 
-        Self { path }
+        Ok(Self { path })
     }
 
     pub fn insert<T>(&self, value: T) -> Result<()>
@@ -48,7 +50,7 @@ impl Database {
             return Ok(element);
         }
 
-        Err(anyhow!("Element not found"))
+        Err(not_found(key))
     }
 
     pub fn query_vec<T>(&self, key: &str) -> Result<Vec<T>>
@@ -66,7 +68,7 @@ impl Database {
             return Ok(result);
         }
 
-        Err(anyhow!("Element not found"))
+        Err(not_found(key))
     }
 
     pub fn query_all<T>(&self) -> Result<Vec<T>>
@@ -111,6 +113,10 @@ impl Database {
             return Ok(removed);
         }
 
-        Err(anyhow!("Element not found"))
+        Err(not_found(key))
     }
 }
+
+fn not_found(key: &str) -> anyhow::Error {
+    anyhow!(i18n::text("database.not_found", &[("key", &key)]))
+}
@@ -1,5 +1,11 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDateTime};
+
+const DEFAULT_FMT: &str = "%H:%M %d-%m-%Y";
 
 pub fn parse_datetime_from_default_fmt(datetime: DateTime<Local>) -> String {
-    datetime.format("%H:%M %d-%m-%Y").to_string()
+    datetime.format(DEFAULT_FMT).to_string()
+}
+
+pub fn parse_default_fmt_to_datetime(datetime: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(datetime.trim(), DEFAULT_FMT).ok()
 }
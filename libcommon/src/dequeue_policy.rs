@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::priority_queue::TicketPriority;
+
+// the keys a policy chooses between are ticket categories unless said otherwise
+pub trait DequeuePolicy<K = TicketPriority> {
+    // `waiting` holds the categories that have someone waiting, from the
+    // highest to the lowest priority; returns the position of the one to serve
+    fn select(&mut self, waiting: &[K]) -> usize;
+
+    // what the policy is and where it stands, saved along with the queue
+    fn state(&self) -> PolicyState;
+
+    // carries on from a saved state, as long as it was saved by the same
+    // policy with the same settings
+    fn restore(&mut self, state: &PolicyState);
+
+    fn clone_box(&self) -> Box<dyn DequeuePolicy<K>>;
+}
+
+impl<K> Clone for Box<dyn DequeuePolicy<K>> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PolicyState {
+    Strict,
+    Ratio {
+        priority: usize,
+        normal: usize,
+        turn: usize,
+    },
+    Weighted {
+        weights: Vec<(TicketPriority, u32)>,
+        current: Vec<(TicketPriority, i64)>,
+    },
+}
+
+impl PolicyState {
+    // the policy that saved this state, right where it was
+    pub fn policy(&self) -> Box<dyn DequeuePolicy> {
+        let mut policy: Box<dyn DequeuePolicy> = match self {
+            PolicyState::Strict => Box::new(StrictPriority),
+            PolicyState::Ratio {
+                priority, normal, ..
+            } => Box::new(RatioInterleaving::new(*priority, *normal)),
+            PolicyState::Weighted { weights, .. } => Box::new(WeightedFair::new(weights.clone())),
+        };
+        policy.restore(self);
+        policy
+    }
+}
+
+// reads a policy such as "strict", "ratio:2:1" or "weighted:Emergency=8,Normal=1"
+pub fn parse_policy(config: Option<&str>) -> Result<Box<dyn DequeuePolicy>> {
+    let config = match config.map(str::trim) {
+        Some(config) if !config.is_empty() => config,
+        _ => return Ok(Box::new(StrictPriority)),
+    };
+
+    let (name, args) = config.split_once(':').unwrap_or((config, ""));
+    match name.trim().to_lowercase().as_str() {
+        "strict" => Ok(Box::new(StrictPriority)),
+        "ratio" => {
+            let counts: Vec<usize> = args
+                .split(':')
+                .map(|count| count.trim().parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| anyhow!("invalid ratio: {}", args))?;
+            match counts[..] {
+                [priority, normal] if priority + normal > 0 => {
+                    Ok(Box::new(RatioInterleaving::new(priority, normal)))
+                }
+                _ => Err(anyhow!("invalid ratio: {}", args)),
+            }
+        }
+        "weighted" => {
+            let mut weights = Vec::new();
+            for weight in args.split(',').filter(|weight| !weight.trim().is_empty()) {
+                let (category, value) = weight
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("invalid weight: {}", weight))?;
+                let priority = TicketPriority::parse(category)
+                    .ok_or_else(|| anyhow!("unknown priority category: {}", category.trim()))?;
+                let value: u32 = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("invalid weight: {}", weight))?;
+                weights.push((priority, value));
+            }
+            Ok(Box::new(WeightedFair::new(weights)))
+        }
+        _ => Err(anyhow!("unknown dequeue policy: {}", name)),
+    }
+}
+
+// always serves the highest category waiting
+#[derive(Clone)]
+pub struct StrictPriority;
+
+impl DequeuePolicy for StrictPriority {
+    fn select(&mut self, _waiting: &[TicketPriority]) -> usize {
+        0
+    }
+
+    fn state(&self) -> PolicyState {
+        PolicyState::Strict
+    }
+
+    fn restore(&mut self, _state: &PolicyState) {}
+
+    fn clone_box(&self) -> Box<dyn DequeuePolicy> {
+        Box::new(self.clone())
+    }
+}
+
+// serves `priority` preferential tickets, then `normal` ones, and starts over;
+// when the expected group has nobody waiting the other one is served instead
+#[derive(Clone)]
+pub struct RatioInterleaving {
+    priority: usize,
+    normal: usize,
+    turn: usize,
+}
+
+impl RatioInterleaving {
+    pub fn new(priority: usize, normal: usize) -> Self {
+        Self {
+            priority,
+            normal,
+            turn: 0,
+        }
+    }
+}
+
+impl DequeuePolicy for RatioInterleaving {
+    fn select(&mut self, waiting: &[TicketPriority]) -> usize {
+        let wants_preferential = self.turn < self.priority;
+
+        match waiting
+            .iter()
+            .position(|priority| priority.is_preferential() == wants_preferential)
+        {
+            Some(idx) => {
+                self.turn = (self.turn + 1) % (self.priority + self.normal);
+                idx
+            }
+            None => 0,
+        }
+    }
+
+    fn state(&self) -> PolicyState {
+        PolicyState::Ratio {
+            priority: self.priority,
+            normal: self.normal,
+            turn: self.turn,
+        }
+    }
+
+    fn restore(&mut self, state: &PolicyState) {
+        match state {
+            PolicyState::Ratio {
+                priority,
+                normal,
+                turn,
+            } if *priority == self.priority && *normal == self.normal => {
+                self.turn = *turn % (self.priority + self.normal).max(1);
+            }
+            _ => {}
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn DequeuePolicy> {
+        Box::new(self.clone())
+    }
+}
+
+// smooth weighted round robin between the categories waiting, categories
+// without a weight count as 1
+#[derive(Clone)]
+pub struct WeightedFair {
+    weights: Vec<(TicketPriority, u32)>,
+    current: Vec<(TicketPriority, i64)>,
+}
+
+impl WeightedFair {
+    pub fn new(weights: Vec<(TicketPriority, u32)>) -> Self {
+        Self {
+            weights,
+            current: Vec::new(),
+        }
+    }
+
+    fn weight(&self, priority: TicketPriority) -> i64 {
+        self.weights
+            .iter()
+            .find(|(category, _)| *category == priority)
+            .map(|(_, weight)| *weight as i64)
+            .unwrap_or(1)
+    }
+}
+
+impl DequeuePolicy for WeightedFair {
+    fn select(&mut self, waiting: &[TicketPriority]) -> usize {
+        // categories nobody is waiting on lose what they had accumulated
+        self.current
+            .retain(|(priority, _)| waiting.contains(priority));
+
+        let total: i64 = waiting.iter().map(|priority| self.weight(*priority)).sum();
+        let mut selected = 0;
+        let mut selected_current = i64::MIN;
+
+        for (idx, priority) in waiting.iter().enumerate() {
+            let weight = self.weight(*priority);
+            let current = match self
+                .current
+                .iter_mut()
+                .find(|(category, _)| category == priority)
+            {
+                Some((_, current)) => {
+                    *current += weight;
+                    *current
+                }
+                None => {
+                    self.current.push((*priority, weight));
+                    weight
+                }
+            };
+
+            // ties go to the higher category
+            if current > selected_current {
+                selected = idx;
+                selected_current = current;
+            }
+        }
+
+        if let Some((_, current)) = self
+            .current
+            .iter_mut()
+            .find(|(category, _)| *category == waiting[selected])
+        {
+            *current -= total;
+        }
+
+        selected
+    }
+
+    fn state(&self) -> PolicyState {
+        PolicyState::Weighted {
+            weights: self.weights.clone(),
+            current: self.current.clone(),
+        }
+    }
+
+    fn restore(&mut self, state: &PolicyState) {
+        match state {
+            PolicyState::Weighted { weights, current } if *weights == self.weights => {
+                self.current = current.clone();
+            }
+            _ => {}
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn DequeuePolicy> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,267 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, SystemClock};
+use crate::datetime_parsing::parse_datetime_from_default_fmt;
+use crate::i18n;
+use crate::pacient_account::Pacient;
+use crate::payment::Payment;
+use crate::pdf_handler::PdfHandler;
+use crate::priority_queue::{Priority, PriorityQueueTicket};
+use crate::service_sheet::ServiceSheet;
+
+const CLINIC_NAME: &str = "SOS Dentes";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentKind {
+    Receipt,
+    QueueTicket,
+    AttendanceDeclaration,
+    Prescription,
+}
+
+impl DocumentKind {
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            DocumentKind::Receipt => "recibo",
+            DocumentKind::QueueTicket => "senha",
+            DocumentKind::AttendanceDeclaration => "declaracao",
+            DocumentKind::Prescription => "receita",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentTemplate {
+    title: String,
+    lines: Vec<String>,
+}
+
+impl DocumentTemplate {
+    pub fn new(title: String, lines: Vec<String>) -> Self {
+        Self { title, lines }
+    }
+
+    pub fn default_for(kind: DocumentKind) -> Self {
+        let (title, lines) = match kind {
+            DocumentKind::Receipt => ("document.receipt.title", "document.receipt.lines"),
+            DocumentKind::QueueTicket => {
+                ("document.queue_ticket.title", "document.queue_ticket.lines")
+            }
+            DocumentKind::AttendanceDeclaration => (
+                "document.attendance_declaration.title",
+                "document.attendance_declaration.lines",
+            ),
+            DocumentKind::Prescription => {
+                ("document.prescription.title", "document.prescription.lines")
+            }
+        };
+
+        Self::new(
+            i18n::message(title).to_string(),
+            i18n::message(lines)
+                .split('\n')
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    // templates can be customized by placing `<file stem>.json` in the templates directory
+    pub fn load(templates_dir: Option<&str>, kind: DocumentKind) -> Result<Self> {
+        if let Some(dir) = templates_dir {
+            let path = Path::new(dir).join(format!("{}.json", kind.file_stem()));
+            if path.exists() {
+                let reader = BufReader::new(File::open(path)?);
+                return Ok(serde_json::from_reader(reader)?);
+            }
+        }
+
+        Ok(Self::default_for(kind))
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn lines(&self) -> &Vec<String> {
+        &self.lines
+    }
+
+    pub fn fill(&self, kind: DocumentKind, values: &[(&str, String)]) -> Document {
+        let replace = |text: &str| {
+            i18n::fill_placeholders(text, |name| {
+                values
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.clone())
+            })
+        };
+
+        let lines = self
+            .lines
+            .iter()
+            .flat_map(|line| {
+                // values spanning multiple lines, such as medications, become separate lines
+                replace(line)
+                    .split('\n')
+                    .map(str::to_string)
+                    .collect::<Vec<String>>()
+            })
+            .collect();
+
+        Document {
+            kind,
+            title: replace(&self.title),
+            lines,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    kind: DocumentKind,
+    title: String,
+    lines: Vec<String>,
+}
+
+impl Document {
+    pub fn kind(&self) -> DocumentKind {
+        self.kind
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn lines(&self) -> &Vec<String> {
+        &self.lines
+    }
+}
+
+pub struct DocumentPrinter {
+    output_dir: String,
+    templates_dir: Option<String>,
+    // names the files after when they were printed
+    clock: Rc<dyn Clock>,
+}
+
+impl DocumentPrinter {
+    pub fn new(output_dir: String, templates_dir: Option<String>) -> Result<Self> {
+        fs::create_dir_all(&output_dir)?;
+
+        Ok(Self {
+            output_dir,
+            templates_dir,
+            clock: Rc::new(SystemClock),
+        })
+    }
+
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn receipt(&self, pacient: &Pacient, payment: &Payment) -> Result<String> {
+        let values = [
+            ("clinic", CLINIC_NAME.to_string()),
+            ("name", pacient.name().to_string()),
+            ("cpf", payment.cpf().to_string()),
+            ("amount", payment.amount().to_string()),
+            ("procedure", payment.procedure().label().to_string()),
+            ("method", payment.method().label().to_string()),
+            ("date", payment.date().to_string()),
+        ];
+
+        self.print(DocumentKind::Receipt, payment.cpf().digits(), &values)
+    }
+
+    pub fn queue_ticket(
+        &self,
+        ticket: &PriorityQueueTicket,
+        estimated_wait: &str,
+        datetime: DateTime<Local>,
+    ) -> Result<String> {
+        let values = [
+            ("clinic", CLINIC_NAME.to_string()),
+            ("code", ticket.display_code()),
+            ("priority", ticket.priority().label().to_string()),
+            ("date", parse_datetime_from_default_fmt(datetime)),
+            ("wait", estimated_wait.to_string()),
+            ("pin", ticket.pin().unwrap_or_default().to_string()),
+        ];
+
+        self.print(DocumentKind::QueueTicket, &ticket.display_code(), &values)
+    }
+
+    pub fn attendance_declaration(
+        &self,
+        sheet: &ServiceSheet,
+        datetime: DateTime<Local>,
+    ) -> Result<String> {
+        let pacient = sheet.pacient_account();
+        let values = [
+            ("clinic", CLINIC_NAME.to_string()),
+            ("name", pacient.name().to_string()),
+            ("cpf", pacient.cpf().to_string()),
+            ("reason", sheet.reason().to_string()),
+            ("date", parse_datetime_from_default_fmt(datetime)),
+        ];
+
+        self.print(
+            DocumentKind::AttendanceDeclaration,
+            pacient.cpf().digits(),
+            &values,
+        )
+    }
+
+    pub fn prescription(
+        &self,
+        pacient: &Pacient,
+        medications: &[String],
+        datetime: DateTime<Local>,
+    ) -> Result<String> {
+        let values = [
+            ("clinic", CLINIC_NAME.to_string()),
+            ("name", pacient.name().to_string()),
+            ("cpf", pacient.cpf().to_string()),
+            ("medications", medications.join("\n")),
+            ("date", parse_datetime_from_default_fmt(datetime)),
+        ];
+
+        self.print(DocumentKind::Prescription, pacient.cpf().digits(), &values)
+    }
+
+    fn print(&self, kind: DocumentKind, id: &str, values: &[(&str, String)]) -> Result<String> {
+        let template = DocumentTemplate::load(self.templates_dir.as_deref(), kind)?;
+        let document = template.fill(kind, values);
+
+        let stem = format!(
+            "{}-{}-{}",
+            kind.file_stem(),
+            id,
+            self.clock.now().format("%Y%m%d%H%M%S")
+        );
+        // a second document for the same pacient within the same second gets
+        // a counter instead of replacing the first one
+        let path = (1..)
+            .map(|count| match count {
+                1 => format!("{}.pdf", stem),
+                _ => format!("{}-{}.pdf", stem, count),
+            })
+            .map(|file_name| Path::new(&self.output_dir).join(file_name))
+            .find(|path| !path.exists())
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        PdfHandler::save_as_pdf(&path, &document)?;
+
+        Ok(path)
+    }
+}
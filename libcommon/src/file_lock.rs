@@ -0,0 +1,92 @@
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+
+use crate::shutdown;
+
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+// a lock this old was left behind by a station that crashed while holding it
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+// tells apart the stale locks one process moves aside
+static BROKEN_LOCKS: AtomicUsize = AtomicUsize::new(0);
+
+// held while a file shared by the stations is read, changed and saved, so no
+// station saves over what another one has just saved; it is the file
+// `<path>.lock`, removed when the lock is dropped
+pub struct FileLock {
+    path: String,
+}
+
+impl FileLock {
+    pub fn acquire(path: &str) -> Result<Self> {
+        let path = format!("{}.lock", path);
+        let started = SystemTime::now();
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => {
+                    shutdown::hold_lock(&path);
+                    return Ok(Self { path });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if Self::is_stale(&path) {
+                        Self::break_stale(&path)?;
+                        continue;
+                    }
+                    if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err(anyhow!("timed out waiting for {}", path));
+                    }
+                    thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    // the lock is moved aside under a name no other station uses rather than
+    // removed, so of two stations that find it stale at once only one takes
+    // it; should it be a lock another station has just taken, it is put back
+    fn break_stale(path: &str) -> Result<()> {
+        let aside = format!(
+            "{}.stale-{}-{}",
+            path,
+            process::id(),
+            BROKEN_LOCKS.fetch_add(1, Ordering::Relaxed)
+        );
+
+        match fs::rename(path, &aside) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+
+        if !Self::is_stale(&aside) {
+            // unlike a rename, a link never replaces a lock taken meanwhile
+            let _ = fs::hard_link(&aside, path);
+        }
+        fs::remove_file(&aside)?;
+
+        Ok(())
+    }
+
+    fn is_stale(path: &str) -> bool {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > STALE_LOCK_AGE)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        shutdown::release_lock(&self.path);
+    }
+}
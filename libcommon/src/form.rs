@@ -0,0 +1,259 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+
+use crate::i18n;
+use crate::io_handler::{IOHandler, Menu, Prompt};
+
+type Getter<'a, T> = Box<dyn Fn(&T) -> String + 'a>;
+type Setter<'a, T> = Box<dyn Fn(&mut T, String) + 'a>;
+type Parser<'a> = Box<dyn Fn(&str) -> Result<String, String> + 'a>;
+
+// one field of a form: how its answer is checked and where it is kept; the
+// parser gets the trimmed, non-empty input and returns the value to keep
+pub struct FormField<'a, T> {
+    key: &'static str,
+    label: &'static str,
+    required: bool,
+    editable: bool,
+    get: Getter<'a, T>,
+    set: Setter<'a, T>,
+    parser: Parser<'a>,
+}
+
+impl<'a, T: 'a> FormField<'a, T> {
+    pub fn new<G, S>(key: &'static str, label: &'static str, get: G, set: S) -> Self
+    where
+        G: Fn(&T) -> String + 'a,
+        S: Fn(&mut T, String) + 'a,
+    {
+        Self {
+            key,
+            label,
+            required: true,
+            editable: true,
+            get: Box::new(get),
+            set: Box::new(set),
+            parser: Box::new(|input| Ok(input.to_string())),
+        }
+    }
+
+    // given when the record is made, before its form is filled in, and
+    // never changed afterwards, so it is only shown
+    pub fn read_only<G>(key: &'static str, label: &'static str, get: G) -> Self
+    where
+        G: Fn(&T) -> String + 'a,
+    {
+        Self {
+            editable: false,
+            ..Self::new(key, label, get, |_, _| {})
+        }
+    }
+
+    // an empty answer leaves the field empty
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub fn parse_with<F>(mut self, parser: F) -> Self
+    where
+        F: Fn(&str) -> Result<String, String> + 'a,
+    {
+        self.parser = Box::new(parser);
+        self
+    }
+
+    // checked after the answer is parsed
+    pub fn validate<F>(self, check: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + 'a,
+    {
+        let parser = self.parser;
+        Self {
+            parser: Box::new(move |input| {
+                let value = parser(input)?;
+                check(&value)?;
+                Ok(value)
+            }),
+            ..self
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.key
+    }
+
+    pub fn label(&self) -> &str {
+        self.label
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    pub fn is_editable(&self) -> bool {
+        self.editable
+    }
+
+    pub fn value(&self, item: &T) -> String {
+        (self.get)(item)
+    }
+
+    pub fn set(&self, item: &mut T, value: String) {
+        (self.set)(item, value)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            if self.required {
+                Err(i18n::message("prompt.required").to_string())
+            } else {
+                Ok(String::new())
+            }
+        } else {
+            (self.parser)(input)
+        }
+    }
+
+    pub fn prompt(&self) -> Prompt<'_, String> {
+        Prompt::new(format!("{}: ", self.label), |input| self.parse(input))
+    }
+
+    // the field of a part of a bigger record, such as the address of a pacient
+    fn within<P>(self, part: fn(&P) -> &T, part_mut: fn(&mut P) -> &mut T) -> FormField<'a, P>
+    where
+        P: 'a,
+    {
+        let get = self.get;
+        let set = self.set;
+        FormField {
+            key: self.key,
+            label: self.label,
+            required: self.required,
+            editable: self.editable,
+            get: Box::new(move |item| get(part(item))),
+            set: Box::new(move |item, value| set(part_mut(item), value)),
+            parser: self.parser,
+        }
+    }
+}
+
+// asks for a record field by field and lets the operator correct any of them
+// before it is saved
+pub struct Form<'a, T> {
+    fields: Vec<FormField<'a, T>>,
+}
+
+impl<'a, T: 'a> Default for Form<'a, T> {
+    fn default() -> Self {
+        Self { fields: vec![] }
+    }
+}
+
+impl<'a, T: 'a> Form<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, field: FormField<'a, T>) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    // takes in the fields of the form of one of the record's parts
+    pub fn nest<P>(
+        mut self,
+        form: Form<'a, P>,
+        part: fn(&T) -> &P,
+        part_mut: fn(&mut T) -> &mut P,
+    ) -> Self
+    where
+        P: 'a,
+    {
+        self.fields.extend(
+            form.fields
+                .into_iter()
+                .map(|field| field.within(part, part_mut)),
+        );
+        self
+    }
+
+    // adds a check to the field with the key, for what the record can't
+    // tell on its own, like whether the key is already taken
+    pub fn validate<F>(mut self, key: &str, check: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + 'a,
+    {
+        if let Some(index) = self.fields.iter().position(|field| field.key == key) {
+            let field = self.fields.remove(index);
+            self.fields.insert(index, field.validate(check));
+        }
+        self
+    }
+
+    pub fn fields(&self) -> &[FormField<'a, T>] {
+        &self.fields
+    }
+
+    pub fn summary(&self, item: &T) -> String {
+        self.fields
+            .iter()
+            .map(|field| format!("{}: {}\n", field.label, field.value(item)))
+            .collect()
+    }
+
+    // asks every editable field, then shows the record for review
+    pub fn create<R: BufRead, W: Write>(
+        &self,
+        io_handler: &mut IOHandler<R, W>,
+        item: &mut T,
+    ) -> Result<()> {
+        io_handler.write(i18n::message("prompt.cancel_hint"))?;
+        for field in self.fields.iter().filter(|field| field.editable) {
+            let value = io_handler.ask(&field.prompt().cancellable())?;
+            field.set(item, value);
+        }
+
+        self.edit(io_handler, item)?;
+        Ok(())
+    }
+
+    // shows the record and lets its editable fields be corrected, `true`
+    // when any of them changed
+    pub fn edit<R: BufRead, W: Write>(
+        &self,
+        io_handler: &mut IOHandler<R, W>,
+        item: &mut T,
+    ) -> Result<bool> {
+        let mut changed = false;
+
+        loop {
+            io_handler.write(i18n::message("form.review"))?;
+            io_handler.write(self.summary(item))?;
+
+            if !io_handler.ask(&Prompt::yes_no(i18n::message("form.any_wrong")))? {
+                return Ok(changed);
+            }
+
+            let menu = self
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| field.editable)
+                .fold(
+                    Menu::new(i18n::message("form.choose_field")),
+                    |menu, (index, field)| menu.option(field.label, index),
+                )
+                .back(i18n::message("form.back"));
+
+            if let Some(index) = io_handler.choose(&menu)? {
+                let field = &self.fields[index];
+                let value = io_handler.ask(&field.prompt())?;
+                changed |= value != field.value(item);
+                field.set(item, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+// the language every station speaks, chosen once at startup
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::PtBr);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    PtBr,
+    EnUs,
+}
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[Locale::PtBr, Locale::EnUs];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::PtBr => "pt-BR",
+            Locale::EnUs => "en-US",
+        }
+    }
+
+    // "pt-BR", "pt_br" and "PT-br" all name the same locale
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim().replace('_', "-");
+        Self::ALL
+            .iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(&value))
+            .copied()
+            .ok_or_else(|| anyhow!("unknown locale: {}", value))
+    }
+
+    // reads the LOCALE setting, pt-BR when it is left empty
+    pub fn from_config(config: Option<&str>) -> Result<Self> {
+        match config {
+            Some(config) if !config.trim().is_empty() => Self::parse(config),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    // the catalogs are bundled with the binaries, so no locale file has to be
+    // shipped along with them
+    pub fn source(&self) -> &'static str {
+        match self {
+            Locale::PtBr => include_str!("../locales/pt-BR.json"),
+            Locale::EnUs => include_str!("../locales/en-US.json"),
+        }
+    }
+
+    pub fn catalog(&self) -> &'static Catalog {
+        static PT_BR: OnceLock<Catalog> = OnceLock::new();
+        static EN_US: OnceLock<Catalog> = OnceLock::new();
+
+        let catalog = match self {
+            Locale::PtBr => &PT_BR,
+            Locale::EnUs => &EN_US,
+        };
+        catalog.get_or_init(|| {
+            Catalog::parse(self.source())
+                .unwrap_or_else(|err| panic!("invalid {} catalog: {}", self.code(), err))
+        })
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Message {
+    Text(String),
+    Plural { one: String, other: String },
+}
+
+impl Message {
+    pub fn forms(&self) -> Vec<&str> {
+        match self {
+            Message::Text(text) => vec![text],
+            Message::Plural { one, other } => vec![one, other],
+        }
+    }
+}
+
+// messages by key, where "{name}" marks a value filled in when the message is
+// shown and plural messages have a "one" and an "other" form
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    messages: HashMap<String, Message>,
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(Self {
+            messages: serde_json::from_str(source)?,
+        })
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.messages.keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Message> {
+        self.messages.get(key)
+    }
+
+    // the message as it is written in the catalog, the key itself when it is
+    // missing so the station still shows something
+    pub fn message<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.messages.get(key) {
+            Some(Message::Text(text)) => text,
+            Some(Message::Plural { other, .. }) => other,
+            None => key,
+        }
+    }
+
+    pub fn text(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        interpolate(self.message(key), args)
+    }
+
+    // `count` picks the form and fills in "{count}"
+    pub fn plural(&self, key: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+        let message = match self.messages.get(key) {
+            Some(Message::Plural { one, .. }) if count == 1 => one.as_str(),
+            _ => self.message(key),
+        };
+
+        let mut args = args.to_vec();
+        args.push(("count", &count));
+        interpolate(message, &args)
+    }
+}
+
+fn interpolate(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    fill_placeholders(message, |name| {
+        args.iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+// replaces each "{name}" in one pass, so a value that happens to contain
+// another placeholder, like a pacient typing "{cpf}", is kept as typed;
+// unknown names are left as they are
+pub(crate) fn fill_placeholders<F>(text: &str, value: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+
+        match placeholder
+            .find('}')
+            .and_then(|end| Some((end, value(&placeholder[1..end])?)))
+        {
+            Some((end, value)) => {
+                filled.push_str(&value);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &placeholder[1..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap_or_else(|err| err.into_inner()) = locale;
+}
+
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap_or_else(|err| err.into_inner())
+}
+
+pub fn catalog() -> &'static Catalog {
+    locale().catalog()
+}
+
+pub fn message(key: &'static str) -> &'static str {
+    catalog().message(key)
+}
+
+pub fn text(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    catalog().text(key, args)
+}
+
+pub fn plural(key: &str, count: usize, args: &[(&str, &dyn Display)]) -> String {
+    catalog().plural(key, count, args)
+}
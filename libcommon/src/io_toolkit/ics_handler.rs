@@ -0,0 +1,373 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+
+use crate::appointment::Appointment;
+use crate::cpf::Cpf;
+use crate::i18n;
+use crate::pacient_account::Pacient;
+
+const PRODUCT_ID: &str = "-//SOS Dentes//Agenda de Consultas//PT";
+const UID_DOMAIN: &str = "sosdentes";
+const CPF_PROPERTY: &str = "X-SOSDENTES-CPF";
+const APPOINTMENT_DATE_FMT: &str = "%d-%m-%Y";
+const ICS_DATE_FMT: &str = "%Y%m%d";
+// RFC 5545 asks content lines to be folded at 75 octets
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConflictKind {
+    // the same appointment is already scheduled
+    Duplicate,
+    // the pacient already has an appointment on another date
+    AlreadyScheduled,
+    // the event does not describe a valid appointment
+    Invalid,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImportConflict {
+    uid: String,
+    kind: ConflictKind,
+    incoming: Option<Appointment>,
+    existing: Option<Appointment>,
+}
+
+impl ImportConflict {
+    pub fn uid(&self) -> &str {
+        &self.uid
+    }
+
+    pub fn kind(&self) -> ConflictKind {
+        self.kind
+    }
+
+    pub fn incoming(&self) -> Option<&Appointment> {
+        self.incoming.as_ref()
+    }
+
+    pub fn existing(&self) -> Option<&Appointment> {
+        self.existing.as_ref()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    imported: Vec<Appointment>,
+    conflicts: Vec<ImportConflict>,
+}
+
+impl ImportReport {
+    pub fn imported(&self) -> &Vec<Appointment> {
+        &self.imported
+    }
+
+    pub fn conflicts(&self) -> &Vec<ImportConflict> {
+        &self.conflicts
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    calendar: String,
+    exported: usize,
+    skipped: Vec<Appointment>,
+}
+
+impl ExportReport {
+    pub fn calendar(&self) -> &str {
+        &self.calendar
+    }
+
+    pub fn exported(&self) -> usize {
+        self.exported
+    }
+
+    // appointments whose date couldn't be read, such as the free text ones
+    // booked before dates were checked
+    pub fn skipped(&self) -> &Vec<Appointment> {
+        &self.skipped
+    }
+}
+
+pub struct IcsHandler;
+
+impl IcsHandler {
+    pub fn save_as_ics(
+        path: &str,
+        appointments: &[Appointment],
+        accounts: &[Pacient],
+        datetime: DateTime<Local>,
+    ) -> Result<ExportReport> {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+
+        let report = Self::export(appointments, accounts, datetime);
+        let mut file = File::create(path)?;
+        file.write_all(report.calendar.as_bytes())?;
+
+        Ok(report)
+    }
+
+    pub fn read_from_ics(path: &str, existing: &[Appointment]) -> Result<ImportReport> {
+        let content = fs::read_to_string(path)?;
+        Self::import(&content, existing)
+    }
+
+    pub fn export(
+        appointments: &[Appointment],
+        accounts: &[Pacient],
+        datetime: DateTime<Local>,
+    ) -> ExportReport {
+        let stamp = datetime.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ");
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODUCT_ID),
+            "CALSCALE:GREGORIAN".to_string(),
+        ];
+        let mut skipped = vec![];
+
+        for appointment in appointments {
+            let Ok(date) = parse_appointment_date(appointment.date()) else {
+                skipped.push(appointment.clone());
+                continue;
+            };
+            let name = accounts
+                .iter()
+                .find(|account| account.cpf() == appointment.cpf())
+                .map_or(appointment.cpf().to_string(), |account| {
+                    account.name().to_string()
+                });
+
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}", event_uid(appointment.cpf(), date)));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("DTSTART;VALUE=DATE:{}", date.format(ICS_DATE_FMT)));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                (date + Duration::days(1)).format(ICS_DATE_FMT)
+            ));
+            lines.push(format!(
+                "SUMMARY:{}",
+                escape_text(&i18n::text("calendar.summary", &[("name", &name)]))
+            ));
+            lines.push(format!(
+                "DESCRIPTION:{}",
+                escape_text(&i18n::text(
+                    "calendar.description",
+                    &[("name", &name), ("cpf", &appointment.cpf())]
+                ))
+            ));
+            lines.push(format!(
+                "{}:{}",
+                CPF_PROPERTY,
+                escape_text(appointment.cpf().digits())
+            ));
+            lines.push("END:VEVENT".to_string());
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+
+        ExportReport {
+            calendar: lines
+                .iter()
+                .map(|line| fold_line(line))
+                .collect::<Vec<String>>()
+                .join(""),
+            exported: appointments.len() - skipped.len(),
+            skipped,
+        }
+    }
+
+    pub fn import(content: &str, existing: &[Appointment]) -> Result<ImportReport> {
+        let lines = unfold_lines(content);
+        if lines.first().map(String::as_str) != Some("BEGIN:VCALENDAR") {
+            return Err(anyhow!(i18n::message("calendar.not_icalendar")));
+        }
+
+        let mut report = ImportReport::default();
+        let mut event: Option<Vec<(String, String)>> = None;
+
+        for line in lines {
+            let (name, value) = split_content_line(&line);
+
+            if name == "BEGIN" && value == "VEVENT" {
+                event = Some(Vec::new());
+            } else if name == "END" && value == "VEVENT" {
+                if let Some(properties) = event.take() {
+                    report_event(&mut report, &properties, existing);
+                }
+            } else if let Some(properties) = event.as_mut() {
+                properties.push((name, value));
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+fn report_event(
+    report: &mut ImportReport,
+    properties: &[(String, String)],
+    existing: &[Appointment],
+) {
+    let property = |key: &str| {
+        properties
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| unescape_text(value))
+    };
+
+    let uid = property("UID").unwrap_or_default();
+    let cpf = property(CPF_PROPERTY)
+        .or_else(|| cpf_from_uid(&uid))
+        .and_then(|cpf| Cpf::parse(&cpf).ok());
+    let date = property("DTSTART").and_then(|value| parse_ics_date(&value));
+
+    let incoming = match (cpf, date) {
+        (Some(cpf), Some(date)) => {
+            Appointment::new(cpf, date.format(APPOINTMENT_DATE_FMT).to_string())
+        }
+        _ => {
+            report.conflicts.push(ImportConflict {
+                uid,
+                kind: ConflictKind::Invalid,
+                incoming: None,
+                existing: None,
+            });
+            return;
+        }
+    };
+
+    let scheduled = existing
+        .iter()
+        .chain(report.imported.iter())
+        .find(|appointment| appointment.cpf() == incoming.cpf())
+        .cloned();
+
+    match scheduled {
+        Some(scheduled) => {
+            let kind = if same_date(&scheduled, &incoming) {
+                ConflictKind::Duplicate
+            } else {
+                ConflictKind::AlreadyScheduled
+            };
+
+            report.conflicts.push(ImportConflict {
+                uid,
+                kind,
+                incoming: Some(incoming),
+                existing: Some(scheduled),
+            });
+        }
+        None => report.imported.push(incoming),
+    }
+}
+
+fn same_date(lhs: &Appointment, rhs: &Appointment) -> bool {
+    match (
+        parse_appointment_date(lhs.date()),
+        parse_appointment_date(rhs.date()),
+    ) {
+        (Ok(lhs), Ok(rhs)) => lhs == rhs,
+        _ => lhs.date().trim() == rhs.date().trim(),
+    }
+}
+
+fn event_uid(cpf: &Cpf, date: NaiveDate) -> String {
+    format!(
+        "{}-{}@{}",
+        cpf.digits(),
+        date.format(ICS_DATE_FMT),
+        UID_DOMAIN
+    )
+}
+
+fn cpf_from_uid(uid: &str) -> Option<String> {
+    let (local_part, _) = uid.split_once('@')?;
+    let (cpf, _) = local_part.rsplit_once('-')?;
+    Some(cpf.to_string())
+}
+
+fn parse_appointment_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), APPOINTMENT_DATE_FMT)
+        .map_err(|_| anyhow!("Invalid appointment date: {}", date))
+}
+
+fn parse_ics_date(value: &str) -> Option<NaiveDate> {
+    // both DATE and DATE-TIME values start with the date itself
+    NaiveDate::parse_from_str(value.get(..8)?, ICS_DATE_FMT).ok()
+}
+
+fn split_content_line(line: &str) -> (String, String) {
+    let (head, value) = line.split_once(':').unwrap_or((line, ""));
+    // parameters such as `;VALUE=DATE` are not needed to rebuild appointments
+    let name = head.split(';').next().unwrap_or(head);
+    (name.to_ascii_uppercase(), value.to_string())
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // the leading space of a continuation line counts as an octet
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
@@ -1,11 +1,69 @@
-use std::fmt::Display;
+use std::error::Error;
+use std::fmt::{self, Display};
 use std::io;
 
 use anyhow::Result;
+use chrono::{Local, NaiveDate};
+
+use crate::cpf::Cpf;
+use crate::i18n;
+use crate::shutdown;
+use crate::transcript::Transcript;
+
+const DATE_FMT: &str = "%d-%m-%Y";
+// typed instead of an answer to give up a cancellable question, in any of
+// the supported languages
+const CANCEL_INPUTS: &[&str] = &["cancelar", "cancel"];
+
+// reading past the end of the input, such as a closed stdin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEnded;
+
+impl Display for InputEnded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "input ended")
+    }
+}
+
+impl Error for InputEnded {}
+
+// the operator gave up a cancellable question, and so the whole operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+// the station was told to stop by Ctrl-C while waiting for input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
+
+impl Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "interrupted")
+    }
+}
+
+impl Error for Interrupted {}
+
+// the station stopping because its input ended, or because it was
+// interrupted, isn't a failure
+pub fn stop_at_input_end(result: Result<()>) -> Result<()> {
+    match result {
+        Err(err) if err.is::<InputEnded>() || err.is::<Interrupted>() => Ok(()),
+        result => result,
+    }
+}
 
 pub struct IOHandler<R, W> {
     reader: R,
     writer: W,
+    transcript: Option<Transcript>,
 }
 
 pub trait DefaultIOHandler {
@@ -18,24 +76,297 @@ where
     W: io::Write,
 {
     pub fn new(reader: R, writer: W) -> Self {
-        Self { reader, writer }
+        Self {
+            reader,
+            writer,
+            transcript: None,
+        }
+    }
+
+    // keeps a transcript of everything written and read from now on
+    pub fn recording(mut self) -> Self {
+        self.transcript = Some(Transcript::new());
+        self
+    }
+
+    pub fn transcript(&self) -> Option<&Transcript> {
+        self.transcript.as_ref()
     }
 
     pub fn read_line(&mut self) -> Result<String> {
+        if shutdown::interrupted() {
+            return Err(Interrupted.into());
+        }
+
         let mut result = String::new();
-        self.reader.read_line(&mut result)?;
+        if self.reader.read_line(&mut result)? == 0 {
+            if shutdown::interrupted() {
+                return Err(Interrupted.into());
+            }
+            return Err(InputEnded.into());
+        }
+
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_input(
+                result.trim_end_matches(['\r', '\n']).to_string(),
+                Local::now(),
+            );
+        }
         Ok(result)
     }
 
     pub fn write<T: Display>(&mut self, msg: T) -> Result<()> {
+        let msg = msg.to_string();
         write!(self.writer, "{}", msg)?;
         self.writer.flush()?;
+
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_output(msg, Local::now());
+        }
         Ok(())
     }
 
     pub fn writer(&self) -> &W {
         &self.writer
     }
+
+    // asks until one of the options is picked, `None` is the back option
+    pub fn choose<T: Clone>(&mut self, menu: &Menu<T>) -> Result<Option<T>> {
+        loop {
+            self.write(menu.render())?;
+            let input = self.read_line()?;
+
+            match menu.parse(&input) {
+                Some(choice) => return Ok(choice),
+                None => self.write(i18n::message("menu.invalid_option"))?,
+            }
+        }
+    }
+
+    // asks until the answer is valid, a cancellable prompt also stops with
+    // `Cancelled` when the operator gives up
+    pub fn ask<T>(&mut self, prompt: &Prompt<T>) -> Result<T> {
+        loop {
+            self.write(&prompt.message)?;
+            let input = self.read_line()?;
+
+            if prompt.is_cancel(&input) {
+                return Err(Cancelled.into());
+            }
+            match prompt.parse(&input) {
+                Ok(value) => return Ok(value),
+                Err(err) => self.write(format!("{}\n", err))?,
+            }
+        }
+    }
+
+    // shows what went wrong so the operator can try again, the end of the
+    // input and an interruption are passed on so the station stops
+    pub fn recover(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Err(err) if err.is::<InputEnded>() || err.is::<Interrupted>() => Err(err),
+            Err(err) if err.is::<Cancelled>() => {
+                self.write(i18n::message("station.operation_cancelled"))
+            }
+            Err(err) => self.write(i18n::text("station.operation_failed", &[("error", &err)])),
+            ok => ok,
+        }
+    }
+}
+
+// options listed as "[1] label" and the back option as "[0] label"
+pub struct Menu<T> {
+    message: String,
+    options: Vec<(String, T)>,
+    back: Option<String>,
+}
+
+impl<T: Clone> Menu<T> {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Self {
+            message: message.into(),
+            options: Vec::new(),
+            back: None,
+        }
+    }
+
+    pub fn option<S: Into<String>>(mut self, label: S, value: T) -> Self {
+        self.options.push((label.into(), value));
+        self
+    }
+
+    pub fn back<S: Into<String>>(mut self, label: S) -> Self {
+        self.back = Some(label.into());
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut menu = String::from("\n");
+        for (idx, (label, _)) in self.options.iter().enumerate() {
+            menu.push_str(&format!("[{}] {}\n", idx + 1, label));
+        }
+        if let Some(label) = &self.back {
+            menu.push_str(&format!("[0] {}\n", label));
+        }
+        menu.push('\n');
+        menu.push_str(&self.message);
+        menu
+    }
+
+    // `None` when the input matches no option
+    pub fn parse(&self, input: &str) -> Option<Option<T>> {
+        match input.trim().parse::<usize>().ok()? {
+            0 if self.back.is_some() => Some(None),
+            0 => None,
+            option => self
+                .options
+                .get(option - 1)
+                .map(|(_, value)| Some(value.clone())),
+        }
+    }
+}
+
+type Parser<'a, T> = Box<dyn Fn(&str) -> Result<T, String> + 'a>;
+
+// a question whose answer is parsed, and checked, before being accepted;
+// the parser gets the trimmed input and explains what is wrong with it
+pub struct Prompt<'a, T> {
+    message: String,
+    parser: Parser<'a, T>,
+    cancellable: bool,
+}
+
+impl<'a, T: 'a> Prompt<'a, T> {
+    pub fn new<S, F>(message: S, parser: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&str) -> Result<T, String> + 'a,
+    {
+        Self {
+            message: message.into(),
+            parser: Box::new(parser),
+            cancellable: false,
+        }
+    }
+
+    // lets the operator give up by typing "cancelar" instead of an answer
+    pub fn cancellable(mut self) -> Self {
+        self.cancellable = true;
+        self
+    }
+
+    pub fn is_cancel(&self, input: &str) -> bool {
+        self.cancellable
+            && CANCEL_INPUTS
+                .iter()
+                .any(|cancel| cancel.eq_ignore_ascii_case(input.trim()))
+    }
+
+    pub fn validate<F>(self, check: F) -> Self
+    where
+        F: Fn(&T) -> Result<(), String> + 'a,
+    {
+        let parser = self.parser;
+        Self {
+            parser: Box::new(move |input| {
+                let value = parser(input)?;
+                check(&value)?;
+                Ok(value)
+            }),
+            ..self
+        }
+    }
+
+    // the same answer `ask` would accept, for front-ends that read their
+    // input some other way
+    pub fn parse(&self, input: &str) -> Result<T, String> {
+        (self.parser)(input.trim())
+    }
+
+    // what an empty answer stands for
+    pub fn or_default(self, default: T) -> Self
+    where
+        T: Clone,
+    {
+        let parser = self.parser;
+        Self {
+            parser: Box::new(move |input| {
+                if input.is_empty() {
+                    Ok(default.clone())
+                } else {
+                    parser(input)
+                }
+            }),
+            ..self
+        }
+    }
+}
+
+impl<'a> Prompt<'a, String> {
+    pub fn text<S: Into<String>>(message: S) -> Self {
+        Self::new(message, |input| {
+            if input.is_empty() {
+                Err(i18n::message("prompt.required").to_string())
+            } else {
+                Ok(input.to_string())
+            }
+        })
+    }
+}
+
+impl<'a> Prompt<'a, Cpf> {
+    // with or without the mask, as long as the check digits are right
+    pub fn cpf<S: Into<String>>(message: S) -> Self {
+        Self::new(message, |input| {
+            Cpf::parse(input).map_err(|err| err.to_string())
+        })
+    }
+}
+
+impl<'a> Prompt<'a, Option<String>> {
+    pub fn optional_text<S: Into<String>>(message: S) -> Self {
+        Self::new(message, |input| {
+            Ok(Some(input.to_string()).filter(|input| !input.is_empty()))
+        })
+    }
+}
+
+impl<'a> Prompt<'a, usize> {
+    pub fn number<S: Into<String>>(message: S) -> Self {
+        Self::new(message, |input| {
+            input
+                .parse()
+                .map_err(|_| i18n::message("prompt.invalid_number").to_string())
+        })
+    }
+}
+
+impl<'a> Prompt<'a, NaiveDate> {
+    // dates are written as dd-mm-aaaa
+    pub fn date<S: Into<String>>(message: S) -> Self {
+        Self::new(message, |input| {
+            NaiveDate::parse_from_str(input, DATE_FMT)
+                .map_err(|_| i18n::message("prompt.invalid_date").to_string())
+        })
+    }
+}
+
+impl<'a> Prompt<'a, bool> {
+    // listed as a "[1] Sim / [2] Não" menu, like the other questions, the
+    // answer can also be written out in any of the supported languages
+    pub fn yes_no<S: Into<String>>(message: S) -> Self {
+        let message = format!(
+            "\n[1] {}\n[2] {}\n\n{}",
+            i18n::message("prompt.yes"),
+            i18n::message("prompt.no"),
+            message.into()
+        );
+        Self::new(message, |input| match input.to_lowercase().as_str() {
+            "1" | "s" | "sim" | "y" | "yes" => Ok(true),
+            "2" | "n" | "não" | "nao" | "no" => Ok(false),
+            _ => Err(i18n::message("prompt.yes_no_hint").to_string()),
+        })
+    }
 }
 
 impl DefaultIOHandler for IOHandler<io::StdinLock<'static>, io::Stdout> {
@@ -43,6 +374,7 @@ impl DefaultIOHandler for IOHandler<io::StdinLock<'static>, io::Stdout> {
         Self {
             reader: io::stdin().lock(),
             writer: io::stdout(),
+            transcript: None,
         }
     }
 }
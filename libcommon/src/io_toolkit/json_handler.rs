@@ -1,6 +1,6 @@
 use std::fs::{self, File};
 use std::io::{BufReader, Write};
-use std::path::Path;
+use std::process;
 
 use anyhow::Result;
 use serde::{de, Serialize};
@@ -8,22 +8,31 @@ use serde::{de, Serialize};
 pub struct JsonHandler;
 
 impl JsonHandler {
-    pub fn save_as_json<T>(path: &str, buff: &Vec<T>) -> Result<()>
+    pub fn save_as_json<T>(path: &str, buff: &T) -> Result<()>
     where
-        T: Serialize,
+        T: Serialize + ?Sized,
     {
-        if Path::new(path).exists() {
-            fs::remove_file(path)?;
-        }
-
         let serialized = serde_json::to_string_pretty(buff)?;
-        let mut file = File::create(path)?;
+
+        // written aside and renamed so a crash never leaves a half written file
+        let tmp_path = format!("{}.{}.tmp", path, process::id());
+        let mut file = File::create(&tmp_path)?;
         file.write_all(serialized.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
 
         Ok(())
     }
 
     pub fn read_from_json<T>(path: &str) -> Result<Vec<T>>
+    where
+        T: de::DeserializeOwned,
+    {
+        Self::read_document(path)
+    }
+
+    // for files holding something other than a list, such as a queue
+    pub fn read_document<T>(path: &str) -> Result<T>
     where
         T: de::DeserializeOwned,
     {
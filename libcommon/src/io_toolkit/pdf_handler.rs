@@ -0,0 +1,663 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::documents::{Document, DocumentKind};
+
+// embedded so documents render the same on any machine, with no network
+// access; each document only carries the glyphs it uses, so a ticket stays
+// small
+const FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+const FONT_NAME: &str = "DejaVuSansMono";
+const FIRST_CHAR: u8 = 32;
+const LAST_CHAR: u8 = 255;
+// the tables a PDF reader uses from an embedded TrueType font, in the order
+// of their tags; glyph names, kerning and the like are left out
+const SUBSET_TABLES: [&[u8; 4]; 11] = [
+    b"OS/2", b"cmap", b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp",
+    b"prep",
+];
+
+struct PageLayout {
+    width: f32,
+    height: f32,
+    margin: f32,
+    title_size: f32,
+    body_size: f32,
+    centered: bool,
+}
+
+impl PageLayout {
+    fn for_kind(kind: DocumentKind) -> Self {
+        match kind {
+            // 80mm thermal printer roll
+            DocumentKind::QueueTicket => Self {
+                width: 226.0,
+                height: 340.0,
+                margin: 16.0,
+                title_size: 14.0,
+                body_size: 10.0,
+                centered: true,
+            },
+            // A4
+            _ => Self {
+                width: 595.0,
+                height: 842.0,
+                margin: 56.0,
+                title_size: 16.0,
+                body_size: 11.0,
+                centered: false,
+            },
+        }
+    }
+}
+
+struct FontMetrics {
+    // every glyph has the same advance since the embedded font is monospaced
+    advance: i32,
+    bbox: [i32; 4],
+    ascent: i32,
+    descent: i32,
+    cap_height: i32,
+}
+
+impl FontMetrics {
+    fn parse(font: &[u8]) -> Result<Self> {
+        let head = table(font, b"head")?;
+        let hhea = table(font, b"hhea")?;
+        let hmtx = table(font, b"hmtx")?;
+
+        let units_per_em = read_u16(head, 18)? as i32;
+        let scale = |value: i32| value * 1000 / units_per_em;
+
+        let bbox = [
+            scale(read_i16(head, 36)? as i32),
+            scale(read_i16(head, 38)? as i32),
+            scale(read_i16(head, 40)? as i32),
+            scale(read_i16(head, 42)? as i32),
+        ];
+        let ascent = scale(read_i16(hhea, 4)? as i32);
+        let descent = scale(read_i16(hhea, 6)? as i32);
+
+        // the cap height is only present from version 2 of the OS/2 table onwards
+        let cap_height = match table(font, b"OS/2") {
+            Ok(os2) if read_u16(os2, 0)? >= 2 => scale(read_i16(os2, 88)? as i32),
+            _ => ascent,
+        };
+
+        Ok(Self {
+            advance: scale(read_u16(hmtx, 0)? as i32),
+            bbox,
+            ascent,
+            descent,
+            cap_height,
+        })
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars().count() as f32 * self.advance as f32 * size / 1000.0
+    }
+
+    fn chars_per_line(&self, width: f32, size: f32) -> usize {
+        ((width * 1000.0) / (self.advance as f32 * size))
+            .floor()
+            .max(1.0) as usize
+    }
+}
+
+pub struct PdfHandler;
+
+impl PdfHandler {
+    pub fn save_as_pdf(path: &str, document: &Document) -> Result<()> {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+
+        let pdf = Self::to_pdf(document)?;
+        let mut file = File::create(path)?;
+        file.write_all(&pdf)?;
+
+        Ok(())
+    }
+
+    pub fn to_pdf(document: &Document) -> Result<Vec<u8>> {
+        let metrics = FontMetrics::parse(FONT)?;
+        let layout = PageLayout::for_kind(document.kind());
+        let pages = layout_pages(document, &layout, &metrics);
+
+        // characters WinAnsiEncoding lacks are printed as a question mark
+        let chars: BTreeSet<char> = document
+            .title()
+            .chars()
+            .chain(document.lines().iter().flat_map(|line| line.chars()))
+            .map(|c| if win_ansi(c).is_some() { c } else { '?' })
+            .collect();
+        let font = subset_font(FONT, &chars)?;
+        // a subset is named after a tag of six capital letters
+        let font_name = format!("{}+{}", subset_tag(&chars), FONT_NAME);
+
+        let mut writer = PdfWriter::new();
+
+        // objects 1 to 5 are fixed, each page then takes a page object and a content stream
+        let page_refs: Vec<String> = (0..pages.len())
+            .map(|idx| format!("{} 0 R", 6 + idx * 2))
+            .collect();
+
+        writer.object(b"<< /Type /Catalog /Pages 2 0 R >>");
+        writer.object(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_refs.join(" "),
+                pages.len()
+            )
+            .as_bytes(),
+        );
+
+        let widths = vec![metrics.advance.to_string(); (LAST_CHAR - FIRST_CHAR + 1) as usize];
+        writer.object(
+            format!(
+                "<< /Type /Font /Subtype /TrueType /BaseFont /{} /FirstChar {} /LastChar {} \
+                /Widths [{}] /Encoding /WinAnsiEncoding /FontDescriptor 4 0 R >>",
+                font_name,
+                FIRST_CHAR,
+                LAST_CHAR,
+                widths.join(" ")
+            )
+            .as_bytes(),
+        );
+        writer.object(
+            format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 33 /FontBBox [{} {} {} {}] \
+                /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80 \
+                /FontFile2 5 0 R >>",
+                font_name,
+                metrics.bbox[0],
+                metrics.bbox[1],
+                metrics.bbox[2],
+                metrics.bbox[3],
+                metrics.ascent,
+                metrics.descent,
+                metrics.cap_height
+            )
+            .as_bytes(),
+        );
+        writer.font_file(&font);
+
+        for (idx, content) in pages.iter().enumerate() {
+            writer.object(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                    /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                    layout.width,
+                    layout.height,
+                    7 + idx * 2
+                )
+                .as_bytes(),
+            );
+            writer.stream(content);
+        }
+
+        Ok(writer.finish())
+    }
+}
+
+fn layout_pages(document: &Document, layout: &PageLayout, metrics: &FontMetrics) -> Vec<Vec<u8>> {
+    let usable_width = layout.width - 2.0 * layout.margin;
+
+    let mut lines: Vec<(String, f32, bool)> = Vec::new();
+    for line in wrap(
+        document.title(),
+        metrics.chars_per_line(usable_width, layout.title_size),
+    ) {
+        lines.push((line, layout.title_size, true));
+    }
+    lines.push((String::new(), layout.body_size, false));
+    for line in document.lines() {
+        for wrapped in wrap(line, metrics.chars_per_line(usable_width, layout.body_size)) {
+            lines.push((wrapped, layout.body_size, layout.centered));
+        }
+    }
+
+    let mut pages = Vec::new();
+    let mut content = Vec::new();
+    let mut y = layout.height - layout.margin;
+
+    for (text, size, centered) in lines {
+        let line_height = size * 1.4;
+        if y - line_height < layout.margin {
+            pages.push(content);
+            content = Vec::new();
+            y = layout.height - layout.margin;
+        }
+        y -= line_height;
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let x = if centered {
+            (layout.width - metrics.text_width(&text, size)) / 2.0
+        } else {
+            layout.margin
+        };
+
+        content.extend(format!("BT /F1 {} Tf {:.2} {:.2} Td (", size, x, y).as_bytes());
+        content.extend(encode_text(&text));
+        content.extend(b") Tj ET\n");
+    }
+    pages.push(content);
+
+    pages
+}
+
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    if text.trim().is_empty() {
+        return vec![String::new()];
+    }
+
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        // words longer than a whole line are broken wherever they hit the margin
+        while word.len() > max_chars {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+
+        let word: String = word.into_iter().collect();
+        if current.is_empty() {
+            current = word;
+        } else if current.chars().count() + 1 + word.chars().count() <= max_chars {
+            current.push(' ');
+            current.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut current, word));
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+// the code of a character in WinAnsiEncoding
+fn win_ansi(c: char) -> Option<u8> {
+    Some(match c {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        _ => return None,
+    })
+}
+
+// converts to WinAnsiEncoding and escapes the result for a PDF literal string
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(text.len());
+
+    for c in text.chars() {
+        let byte = win_ansi(c).unwrap_or(b'?');
+
+        match byte {
+            b'(' | b')' | b'\\' => encoded.extend([b'\\', byte]),
+            0x20..=0x7e => encoded.push(byte),
+            _ => encoded.extend(format!("\\{:03o}", byte).as_bytes()),
+        }
+    }
+
+    encoded
+}
+
+struct PdfWriter {
+    buffer: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        let mut buffer = b"%PDF-1.4\n".to_vec();
+        // binary comment so transfer tools don't treat the file as text
+        buffer.extend([b'%', 0xe2, 0xe3, 0xcf, 0xd3, b'\n']);
+
+        Self {
+            buffer,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn begin_object(&mut self) {
+        self.offsets.push(self.buffer.len());
+        self.buffer
+            .extend(format!("{} 0 obj\n", self.offsets.len()).as_bytes());
+    }
+
+    fn object(&mut self, dictionary: &[u8]) {
+        self.begin_object();
+        self.buffer.extend(dictionary);
+        self.buffer.extend(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, data: &[u8]) {
+        self.begin_object();
+        self.buffer
+            .extend(format!("<< /Length {} >>\nstream\n", data.len()).as_bytes());
+        self.end_stream(data);
+    }
+
+    // a TrueType font also gives its length before any filter
+    fn font_file(&mut self, font: &[u8]) {
+        self.begin_object();
+        self.buffer.extend(
+            format!(
+                "<< /Length {} /Length1 {} >>\nstream\n",
+                font.len(),
+                font.len()
+            )
+            .as_bytes(),
+        );
+        self.end_stream(font);
+    }
+
+    fn end_stream(&mut self, data: &[u8]) {
+        self.buffer.extend(data);
+        self.buffer.extend(b"\nendstream\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref_offset = self.buffer.len();
+
+        self.buffer
+            .extend(format!("xref\n0 {}\n", self.offsets.len() + 1).as_bytes());
+        self.buffer.extend(b"0000000000 65535 f \n");
+        for offset in &self.offsets {
+            self.buffer
+                .extend(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+
+        self.buffer.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                self.offsets.len() + 1,
+                xref_offset
+            )
+            .as_bytes(),
+        );
+
+        self.buffer
+    }
+}
+
+// a copy of the font holding only the glyphs of `chars`, and of the glyphs
+// they are built from, along with the tables in SUBSET_TABLES; glyphs keep
+// their numbers, so the ones left out are written empty
+fn subset_font(font: &[u8], chars: &BTreeSet<char>) -> Result<Vec<u8>> {
+    let glyf = table(font, b"glyf")?;
+    let num_glyphs = read_u16(table(font, b"maxp")?, 4)?;
+
+    // .notdef is the glyph a reader shows for anything missing
+    let mut kept = BTreeSet::from([0]);
+    let mut pending: Vec<u16> = chars
+        .iter()
+        .map(|&c| glyph_index(font, c))
+        .collect::<Result<_>>()?;
+    while let Some(glyph) = pending.pop() {
+        if kept.insert(glyph) {
+            pending.extend(components(&glyf[glyph_range(font, glyph)?])?);
+        }
+    }
+
+    let mut glyphs = Vec::new();
+    let mut offsets = Vec::new();
+    for glyph in 0..num_glyphs {
+        offsets.push(glyphs.len());
+        if kept.contains(&glyph) {
+            glyphs.extend(&glyf[glyph_range(font, glyph)?]);
+            // the short offsets count in pairs of bytes
+            glyphs.resize(glyphs.len() + glyphs.len() % 2, 0);
+        }
+    }
+    offsets.push(glyphs.len());
+
+    let short_offsets = glyphs.len() <= 2 * u16::MAX as usize;
+    let loca: Vec<u8> = offsets
+        .iter()
+        .flat_map(|&offset| {
+            if short_offsets {
+                ((offset / 2) as u16).to_be_bytes().to_vec()
+            } else {
+                (offset as u32).to_be_bytes().to_vec()
+            }
+        })
+        .collect();
+
+    let mut head = table(font, b"head")?.to_vec();
+    // the checksum of the whole file, which no PDF reader checks
+    head[8..12].fill(0);
+    head[50..52].copy_from_slice(&(!short_offsets as i16).to_be_bytes());
+
+    let tables: Vec<(&[u8; 4], Vec<u8>)> = SUBSET_TABLES
+        .iter()
+        .map(|&tag| {
+            Ok(match tag {
+                b"glyf" => (tag, std::mem::take(&mut glyphs)),
+                b"loca" => (tag, loca.clone()),
+                b"head" => (tag, head.clone()),
+                _ => (tag, table(font, tag)?.to_vec()),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(write_font(&tables))
+}
+
+// the glyphs a composite glyph is made of
+fn components(glyph: &[u8]) -> Result<Vec<u16>> {
+    const ARGS_ARE_WORDS: u16 = 0x0001;
+    const HAS_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const HAS_XY_SCALE: u16 = 0x0040;
+    const HAS_TWO_BY_TWO: u16 = 0x0080;
+
+    // simple glyphs, and empty ones, have no components
+    if glyph.is_empty() || read_i16(glyph, 0)? >= 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut components = Vec::new();
+    let mut offset = 10;
+    loop {
+        let flags = read_u16(glyph, offset)?;
+        components.push(read_u16(glyph, offset + 2)?);
+
+        offset += 4 + if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & HAS_SCALE != 0 {
+            offset += 2;
+        } else if flags & HAS_XY_SCALE != 0 {
+            offset += 4;
+        } else if flags & HAS_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(components);
+        }
+    }
+}
+
+// the glyph of a character in the Windows Unicode cmap, 0 when the font lacks it
+fn glyph_index(font: &[u8], c: char) -> Result<u16> {
+    let cmap = table(font, b"cmap")?;
+    let code = c as u32;
+    if code > 0xffff {
+        return Ok(0);
+    }
+    let code = code as u16;
+
+    let num_subtables = read_u16(cmap, 2)? as usize;
+    let subtable = (0..num_subtables)
+        .map(|idx| 4 + idx * 8)
+        .find(|&record| {
+            read_u16(cmap, record).ok() == Some(3) && read_u16(cmap, record + 2).ok() == Some(1)
+        })
+        .ok_or_else(|| anyhow!("Font has no Unicode cmap"))?;
+    let cmap = &cmap[read_u32(cmap, subtable + 4)? as usize..];
+    if read_u16(cmap, 0)? != 4 {
+        return Err(anyhow!("Unsupported cmap format"));
+    }
+
+    let seg_count_x2 = read_u16(cmap, 6)? as usize;
+    let end_codes = 14;
+    let start_codes = end_codes + seg_count_x2 + 2;
+    let id_deltas = start_codes + seg_count_x2;
+    let id_range_offsets = id_deltas + seg_count_x2;
+
+    for segment in (0..seg_count_x2).step_by(2) {
+        if read_u16(cmap, end_codes + segment)? < code {
+            continue;
+        }
+        let start = read_u16(cmap, start_codes + segment)?;
+        if start > code {
+            return Ok(0);
+        }
+
+        let delta = read_u16(cmap, id_deltas + segment)?;
+        let range_offset = read_u16(cmap, id_range_offsets + segment)? as usize;
+        if range_offset == 0 {
+            return Ok(code.wrapping_add(delta));
+        }
+
+        let glyph = read_u16(
+            cmap,
+            id_range_offsets + segment + range_offset + 2 * (code - start) as usize,
+        )?;
+        return Ok(if glyph == 0 {
+            0
+        } else {
+            glyph.wrapping_add(delta)
+        });
+    }
+
+    Ok(0)
+}
+
+// where a glyph is in the glyf table
+fn glyph_range(font: &[u8], glyph: u16) -> Result<Range<usize>> {
+    let loca = table(font, b"loca")?;
+    let glyph = glyph as usize;
+
+    Ok(if read_i16(table(font, b"head")?, 50)? == 0 {
+        read_u16(loca, glyph * 2)? as usize * 2..read_u16(loca, glyph * 2 + 2)? as usize * 2
+    } else {
+        read_u32(loca, glyph * 4)? as usize..read_u32(loca, glyph * 4 + 4)? as usize
+    })
+}
+
+fn write_font(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let entry_selector = 15 - num_tables.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+
+    let mut font = Vec::new();
+    font.extend(0x0001_0000u32.to_be_bytes());
+    for value in [
+        num_tables,
+        search_range,
+        entry_selector,
+        num_tables * 16 - search_range,
+    ] {
+        font.extend(value.to_be_bytes());
+    }
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in tables {
+        font.extend(tag.as_slice());
+        font.extend(checksum(data).to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, data) in tables {
+        font.extend(data);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    font
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+// six capital letters that change with the characters of the subset
+fn subset_tag(chars: &BTreeSet<char>) -> String {
+    let mut hash = chars.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &c| {
+        (hash ^ c as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
+
+fn table<'a>(font: &'a [u8], tag: &[u8; 4]) -> Result<&'a [u8]> {
+    let num_tables = read_u16(font, 4)? as usize;
+
+    for idx in 0..num_tables {
+        let record = 12 + idx * 16;
+        if font.get(record..record + 4) == Some(tag.as_slice()) {
+            let offset = read_u32(font, record + 8)? as usize;
+            let length = read_u32(font, record + 12)? as usize;
+            return font
+                .get(offset..offset + length)
+                .ok_or_else(|| anyhow!("Truncated font"));
+        }
+    }
+
+    Err(anyhow!(
+        "Font table not found: {}",
+        String::from_utf8_lossy(tag)
+    ))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Truncated font"))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Result<i16> {
+    Ok(read_u16(data, offset)? as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("Truncated font"))
+}
@@ -0,0 +1,482 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, Cursor, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, ManualClock, SystemClock};
+use crate::io_handler::IOHandler;
+use crate::json_handler::JsonHandler;
+use crate::shutdown::InterruptibleStdin;
+
+const DATETIME_FMT: &str = "%H:%M:%S %d-%m-%Y";
+
+// tells apart the data directories of the sessions one process replays
+static REPLAYS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Output { at: String, text: String },
+    Input { at: String, text: String },
+}
+
+impl Entry {
+    pub fn at(&self) -> &str {
+        match self {
+            Entry::Output { at, .. } | Entry::Input { at, .. } => at,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Entry::Output { text, .. } | Entry::Input { text, .. } => text,
+        }
+    }
+
+    pub fn time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(self.at(), DATETIME_FMT).ok()
+    }
+}
+
+// everything a station showed and every line typed into it, in order, along
+// with the data files as they were when the session started and the seed of
+// whatever the station drew at random
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transcript {
+    entries: Vec<Entry>,
+    // the contents of each file, `None` for the ones that didn't exist yet
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    files: BTreeMap<String, Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        JsonHandler::read_document(path)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        JsonHandler::save_as_json(path, self)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn files(&self) -> &BTreeMap<String, Option<String>> {
+        &self.files
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn record_output(&mut self, text: String, at: DateTime<Local>) {
+        // consecutive writes are kept together, as they were seen
+        if let Some(Entry::Output { text: last, .. }) = self.entries.last_mut() {
+            last.push_str(&text);
+            return;
+        }
+
+        self.entries.push(Entry::Output {
+            at: at.format(DATETIME_FMT).to_string(),
+            text,
+        });
+    }
+
+    pub fn record_input(&mut self, text: String, at: DateTime<Local>) {
+        self.entries.push(Entry::Input {
+            at: at.format(DATETIME_FMT).to_string(),
+            text,
+        });
+    }
+
+    pub fn inputs(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry, Entry::Input { .. }))
+            .map(Entry::text)
+            .collect()
+    }
+
+    // the typed lines, as a replayed station reads them
+    pub fn input(&self) -> String {
+        self.inputs()
+            .iter()
+            .map(|input| format!("{}\n", input))
+            .collect()
+    }
+
+    // what was shown before the first input and after each of them
+    pub fn outputs(&self) -> Vec<String> {
+        let mut outputs = vec![String::new()];
+        for entry in &self.entries {
+            match entry {
+                Entry::Output { text, .. } => {
+                    if let Some(output) = outputs.last_mut() {
+                        output.push_str(text);
+                    }
+                }
+                Entry::Input { .. } => outputs.push(String::new()),
+            }
+        }
+        outputs
+    }
+
+    // the times entries were recorded at aren't compared
+    pub fn compare(&self, replayed: &Transcript) -> Result<(), Mismatch> {
+        let expected = self.outputs();
+        let found = replayed.outputs();
+        let inputs = self.inputs();
+
+        for step in 0..expected.len().max(found.len()) {
+            let expected = expected.get(step).cloned().unwrap_or_default();
+            let found = found.get(step).cloned().unwrap_or_default();
+            if expected != found {
+                return Err(Mismatch {
+                    step,
+                    input: step
+                        .checked_sub(1)
+                        .and_then(|idx| inputs.get(idx))
+                        .map(|input| input.to_string()),
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// the first output of a replay that differs from the recorded one; `step` is
+// how many lines had been typed before it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub step: usize,
+    pub input: Option<String>,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.input {
+            Some(input) => write!(
+                f,
+                "the output after input {} ({:?}) differs",
+                self.step, input
+            )?,
+            None => write!(f, "the output before the first input differs")?,
+        }
+        write!(
+            f,
+            "\n--- recorded\n{}\n--- replayed\n{}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl Error for Mismatch {}
+
+pub type SessionIOHandler = IOHandler<Box<dyn io::BufRead>, Box<dyn io::Write>>;
+
+// a seed for whatever a station draws at random, from the randomly keyed
+// hasher of the standard library
+pub fn random_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+// how a station runs: live, recording its session to a file or replaying a
+// recorded one and checking it shows the same output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Session {
+    #[default]
+    Live,
+    Record(String),
+    Replay(String),
+}
+
+impl Session {
+    // reads `--record <path>` or `--replay <path>` from the command line arguments
+    pub fn from_args<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut session = Session::default();
+
+        while let Some(arg) = args.next() {
+            if arg == "--record" || arg == "--replay" {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("missing transcript path for {}", arg))?;
+                session = match arg.as_str() {
+                    "--record" => Session::Record(path),
+                    _ => Session::Replay(path),
+                };
+            }
+        }
+
+        Ok(session)
+    }
+
+    // a replay works on copies of the recorded data files, in a directory of
+    // its own, and its clock shows the time each input was typed at
+    pub fn start(&self) -> Result<SessionRun> {
+        let mut run = SessionRun {
+            session: self.clone(),
+            recorded: None,
+            files: BTreeMap::new(),
+            seed: random_seed(),
+            data_dir: None,
+            moved: Vec::new(),
+            clock: None,
+        };
+
+        if let Session::Replay(path) = self {
+            let recorded = Transcript::load(path)?;
+            let data_dir = std::env::temp_dir().join(format!(
+                "sos-dentes-replay-{}-{}",
+                process::id(),
+                REPLAYS.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&data_dir)?;
+
+            let started_at = recorded
+                .entries()
+                .first()
+                .and_then(Entry::time)
+                .unwrap_or_else(|| SystemClock.now());
+            run.seed = recorded.seed().unwrap_or_default();
+            run.clock = Some(ManualClock::new(started_at));
+            run.data_dir = Some(data_dir);
+            run.recorded = Some(recorded);
+        }
+
+        Ok(run)
+    }
+}
+
+// a session being run; the station takes its data paths, clock, seed and
+// input from it
+pub struct SessionRun {
+    session: Session,
+    recorded: Option<Transcript>,
+    // the data files as they were when the recording started
+    files: BTreeMap<String, Option<String>>,
+    seed: u64,
+    data_dir: Option<PathBuf>,
+    // the paths given and where a replay keeps them instead
+    moved: Vec<(String, String)>,
+    clock: Option<ManualClock>,
+}
+
+impl SessionRun {
+    // the path a data file is read from and written to: a recording keeps
+    // what it holds now, a replay puts back what it held then
+    pub fn data_file(&mut self, path: String) -> Result<String> {
+        match self.session {
+            Session::Live => Ok(path),
+            Session::Record(_) => {
+                let content = match fs::read_to_string(&path) {
+                    Ok(content) => Some(content),
+                    Err(err) if err.kind() == ErrorKind::NotFound => None,
+                    Err(err) => return Err(err.into()),
+                };
+                self.files.insert(path.clone(), content);
+                Ok(path)
+            }
+            Session::Replay(_) => {
+                let moved = self.move_path(&path)?;
+                let recorded = self
+                    .recorded
+                    .as_ref()
+                    .and_then(|recorded| recorded.files().get(&path).cloned())
+                    .flatten();
+                if let Some(content) = recorded {
+                    if let Some(parent) = Path::new(&moved).parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&moved, content)?;
+                }
+                Ok(moved)
+            }
+        }
+    }
+
+    // a directory the station only writes to, such as the one documents are
+    // printed to, which a replay keeps to itself
+    pub fn data_dir(&mut self, path: String) -> Result<String> {
+        match self.session {
+            Session::Replay(_) => self.move_path(&path),
+            _ => Ok(path),
+        }
+    }
+
+    pub fn clock(&self) -> Rc<dyn Clock> {
+        match &self.clock {
+            Some(clock) => Rc::new(clock.clone()),
+            None => Rc::new(SystemClock),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // a replayed station reads the recorded input and shows nothing, its
+    // output is only compared
+    pub fn io_handler(&self) -> Result<SessionIOHandler> {
+        let (reader, writer): (Box<dyn io::BufRead>, Box<dyn io::Write>) =
+            match (&self.recorded, &self.clock) {
+                (Some(recorded), Some(clock)) => (
+                    Box::new(ReplayInput::new(recorded, clock.clone())),
+                    Box::new(io::sink()),
+                ),
+                _ => (Box::new(InterruptibleStdin::new()), Box::new(io::stdout())),
+            };
+
+        let io_handler = IOHandler::new(reader, writer);
+        match self.session {
+            Session::Live => Ok(io_handler),
+            _ => Ok(io_handler.recording()),
+        }
+    }
+
+    // saves the recorded session, or checks the replayed one; the paths of
+    // the replay are shown as the ones recorded before comparing
+    pub fn finish<R, W>(mut self, io_handler: &IOHandler<R, W>) -> Result<()>
+    where
+        R: io::BufRead,
+        W: io::Write,
+    {
+        let transcript = match io_handler.transcript() {
+            Some(transcript) => transcript,
+            None => return Ok(()),
+        };
+
+        match (&self.session, self.recorded.take()) {
+            (Session::Record(path), _) => Transcript {
+                entries: transcript.entries.clone(),
+                files: std::mem::take(&mut self.files),
+                seed: Some(self.seed),
+            }
+            .save(path),
+            (Session::Replay(_), Some(recorded)) => {
+                let mut moved = std::mem::take(&mut self.moved);
+                moved.sort_by_key(|(_, moved)| std::cmp::Reverse(moved.len()));
+                let mut replayed = transcript.clone();
+                for entry in replayed.entries.iter_mut() {
+                    if let Entry::Output { text, .. } = entry {
+                        for (path, moved) in moved.iter() {
+                            *text = text.replace(moved.as_str(), path);
+                        }
+                    }
+                }
+                Ok(recorded.compare(&replayed)?)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn move_path(&mut self, path: &str) -> Result<String> {
+        let data_dir = self
+            .data_dir
+            .as_ref()
+            .ok_or_else(|| anyhow!("only a replay moves its data"))?;
+        // absolute paths and the ones going up are kept inside as well
+        let relative: PathBuf = Path::new(path)
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        let moved = data_dir.join(relative).to_string_lossy().to_string();
+
+        self.moved.push((path.to_string(), moved.clone()));
+        Ok(moved)
+    }
+}
+
+// the copies a replay worked on are gone once it ends, however it ends
+impl Drop for SessionRun {
+    fn drop(&mut self) {
+        if let Some(data_dir) = &self.data_dir {
+            let _ = fs::remove_dir_all(data_dir);
+        }
+    }
+}
+
+// feeds a replayed station the recorded lines, setting its clock to the time
+// each of them was typed at as it is read
+struct ReplayInput {
+    lines: VecDeque<(String, Option<NaiveDateTime>)>,
+    current: Cursor<Vec<u8>>,
+    clock: ManualClock,
+}
+
+impl ReplayInput {
+    fn new(recorded: &Transcript, clock: ManualClock) -> Self {
+        let lines = recorded
+            .entries()
+            .iter()
+            .filter(|entry| matches!(entry, Entry::Input { .. }))
+            .map(|entry| (format!("{}\n", entry.text()), entry.time()))
+            .collect();
+
+        Self {
+            lines,
+            current: Cursor::new(Vec::new()),
+            clock,
+        }
+    }
+}
+
+impl Read for ReplayInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.fill_buf()?.read(buf)?;
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for ReplayInput {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.current.position() as usize >= self.current.get_ref().len() {
+            if let Some((line, typed_at)) = self.lines.pop_front() {
+                if let Some(typed_at) = typed_at {
+                    self.clock.set(typed_at);
+                }
+                self.current = Cursor::new(line.into_bytes());
+            }
+        }
+        self.current.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.current.consume(amount);
+    }
+}
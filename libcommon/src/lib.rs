@@ -1,22 +1,49 @@
 mod io_toolkit {
+    pub mod ics_handler;
     pub mod io_handler;
     pub mod json_handler;
+    pub mod pdf_handler;
+    pub mod transcript;
 }
 
+pub use io_toolkit::ics_handler;
 pub use io_toolkit::io_handler;
 pub use io_toolkit::json_handler;
+pub use io_toolkit::pdf_handler;
+pub use io_toolkit::transcript;
 
 mod data_classes {
     pub mod appointment;
+    pub mod lifecycle;
     pub mod pacient_account;
+    pub mod payment;
     pub mod service_sheet;
+    pub mod ticket_record;
 }
 
 pub use data_classes::appointment;
+pub use data_classes::lifecycle;
 pub use data_classes::pacient_account;
+pub use data_classes::payment;
 pub use data_classes::service_sheet;
+pub use data_classes::ticket_record;
 
 mod datetime_parsing;
 
+pub mod clock;
+pub mod command;
+pub mod cpf;
 pub mod database;
+pub mod dequeue_policy;
+pub mod documents;
+pub mod file_lock;
+pub mod form;
+pub mod i18n;
 pub mod priority_queue;
+pub mod queue_router;
+pub mod render;
+pub mod report;
+pub mod shutdown;
+pub mod ticket_limits;
+pub mod ticket_sequence;
+pub mod wait_estimator;
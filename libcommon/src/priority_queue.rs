@@ -1,83 +1,687 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
 use std::convert::From;
+use std::rc::Rc;
 
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDateTime};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 
+use crate::clock::{Clock, SystemClock};
+use crate::datetime_parsing::parse_default_fmt_to_datetime;
+use crate::dequeue_policy::{DequeuePolicy, PolicyState, StrictPriority};
+use crate::i18n;
+use crate::lifecycle::Lifecycle;
+use crate::render::{Field, Render};
+
 pub trait Priority {
-    fn priority(&self) -> TicketPriority;
+    // greater keys are served first
+    type Key: Ord + Clone;
+
+    fn priority(&self) -> Self::Key;
+
+    // when the element started waiting, if it keeps track of it
+    fn enqueued_at(&self) -> Option<NaiveDateTime> {
+        None
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+// declared from the lowest to the highest priority so the derived ordering ranks them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TicketPriority {
     Normal,
+    Scheduled,
+    // generic priority from the two-level queue files, kept so they still load
     High,
+    Disability,
+    Pregnant,
+    Elderly60,
+    Elderly80,
+    Emergency,
+}
+
+impl TicketPriority {
+    // from the highest to the lowest priority
+    pub const ALL: &'static [TicketPriority] = &[
+        TicketPriority::Emergency,
+        TicketPriority::Elderly80,
+        TicketPriority::Elderly60,
+        TicketPriority::Pregnant,
+        TicketPriority::Disability,
+        TicketPriority::High,
+        TicketPriority::Scheduled,
+        TicketPriority::Normal,
+    ];
+
+    pub const DEFAULT_CATEGORIES: &'static [TicketPriority] = &[
+        TicketPriority::Emergency,
+        TicketPriority::Elderly80,
+        TicketPriority::Elderly60,
+        TicketPriority::Pregnant,
+        TicketPriority::Disability,
+        TicketPriority::Scheduled,
+        TicketPriority::Normal,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        i18n::message(match self {
+            TicketPriority::Normal => "priority.normal",
+            TicketPriority::Scheduled => "priority.scheduled",
+            TicketPriority::High => "priority.high",
+            TicketPriority::Disability => "priority.disability",
+            TicketPriority::Pregnant => "priority.pregnant",
+            TicketPriority::Elderly60 => "priority.elderly60",
+            TicketPriority::Elderly80 => "priority.elderly80",
+            TicketPriority::Emergency => "priority.emergency",
+        })
+    }
+
+    // the categories entitled to preferential service, as opposed to walk-ins
+    // and scheduled pacients
+    pub fn is_preferential(&self) -> bool {
+        *self > TicketPriority::Scheduled
+    }
+
+    // shown before the ticket number: E for emergencies, P for preferential
+    // service, A for scheduled pacients and N for walk-ins
+    pub fn prefix(&self) -> char {
+        match self {
+            TicketPriority::Emergency => 'E',
+            TicketPriority::Scheduled => 'A',
+            TicketPriority::Normal => 'N',
+            _ => 'P',
+        }
+    }
+
+    pub fn format_code(&self, code: usize) -> String {
+        format!("{}{:03}", self.prefix(), code)
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|priority| format!("{:?}", priority).eq_ignore_ascii_case(value.trim()))
+            .copied()
+    }
+
+    // reads a comma separated list such as "Emergency,Elderly80,Normal",
+    // from the highest to the lowest priority
+    pub fn categories(config: Option<&str>) -> Result<Vec<TicketPriority>> {
+        let config = match config {
+            Some(config) if !config.trim().is_empty() => config,
+            _ => return Ok(Self::DEFAULT_CATEGORIES.to_vec()),
+        };
+
+        let mut categories = Vec::new();
+        for name in config.split(',') {
+            let priority = Self::parse(name)
+                .ok_or_else(|| anyhow!("unknown priority category: {}", name.trim()))?;
+            if !categories.contains(&priority) {
+                categories.push(priority);
+            }
+        }
+
+        Ok(categories)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PriorityQueueTicket {
     code: usize,
     priority: TicketPriority,
+    #[serde(flatten)]
+    lifecycle: Lifecycle,
+    #[serde(default)]
+    recalls: usize,
+    // printed on the ticket and asked to cancel it, so only its holder can
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pin: Option<String>,
 }
 
 impl PriorityQueueTicket {
     pub fn new(code: usize, priority: TicketPriority) -> Self {
-        Self { code, priority }
+        Self {
+            code,
+            priority,
+            lifecycle: Lifecycle::default(),
+            recalls: 0,
+            pin: None,
+        }
     }
 
     pub fn code(&self) -> usize {
         self.code
     }
+
+    // what the pacient sees, such as P001 or N042
+    pub fn display_code(&self) -> String {
+        self.priority.format_code(self.code)
+    }
+
+    pub fn issued_at(&self) -> Option<&str> {
+        self.lifecycle.issued_at()
+    }
+
+    pub fn set_issued_at(&mut self, datetime: DateTime<Local>) {
+        self.lifecycle.mark_issued(datetime);
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn lifecycle_mut(&mut self) -> &mut Lifecycle {
+        &mut self.lifecycle
+    }
+
+    // times the ticket was called again because nobody came forward
+    pub fn recalls(&self) -> usize {
+        self.recalls
+    }
+
+    pub fn recall(&mut self, datetime: DateTime<Local>) {
+        self.recalls += 1;
+        self.lifecycle.mark_called(datetime);
+    }
+
+    pub fn pin(&self) -> Option<&str> {
+        self.pin.as_deref()
+    }
+
+    pub fn set_pin(&mut self, pin: String) {
+        self.pin = Some(pin);
+    }
+
+    // tickets issued before they had a PIN can't be cancelled by anyone
+    pub fn matches_pin(&self, pin: &str) -> bool {
+        self.pin.as_deref() == Some(pin.trim())
+    }
 }
 
 impl Priority for PriorityQueueTicket {
+    type Key = TicketPriority;
+
     fn priority(&self) -> TicketPriority {
         self.priority
     }
+
+    fn enqueued_at(&self) -> Option<NaiveDateTime> {
+        parse_default_fmt_to_datetime(self.lifecycle.issued_at()?)
+    }
 }
 
-// not implementing with binary heap because of lack of time
-pub struct PriorityQueue<T: Priority> {
-    high_priority_queue: Vec<T>,
-    normal_priority_queue: Vec<T>,
+impl Render for PriorityQueueTicket {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::new("code", i18n::message("field.code"), self.display_code()),
+            Field::new(
+                "priority",
+                i18n::message("field.priority"),
+                self.priority.label(),
+            ),
+        ]
+    }
 }
 
-impl<T> PriorityQueue<T>
-where
-    T: Priority,
-{
-    pub fn new() -> Self {
+#[derive(Clone)]
+pub struct QueueSettings {
+    categories: Vec<TicketPriority>,
+    aging_step: Option<Duration>,
+    clock: Rc<dyn Clock>,
+    policy: Box<dyn DequeuePolicy>,
+}
+
+impl QueueSettings {
+    pub fn new(categories: Vec<TicketPriority>) -> Self {
         Self {
-            high_priority_queue: Vec::new(),
-            normal_priority_queue: Vec::new(),
+            categories,
+            aging_step: None,
+            clock: Rc::new(SystemClock),
+            policy: Box::new(StrictPriority),
         }
     }
 
-    pub fn enqueue(&mut self, element: T) {
-        match element.priority() {
-            TicketPriority::Normal => {
-                self.normal_priority_queue.push(element);
+    // reads the PRIORITY_CATEGORIES and PRIORITY_AGING_MINUTES settings
+    pub fn from_config(categories: Option<&str>, aging_minutes: Option<&str>) -> Result<Self> {
+        let aging_step = match aging_minutes.map(str::trim) {
+            Some(minutes) if !minutes.is_empty() => {
+                let minutes: i64 = minutes
+                    .parse()
+                    .map_err(|_| anyhow!("invalid aging interval: {}", minutes))?;
+                if minutes <= 0 {
+                    return Err(anyhow!("aging interval must be positive: {}", minutes));
+                }
+                Some(Duration::minutes(minutes))
             }
-            TicketPriority::High => {
-                self.high_priority_queue.push(element);
+            _ => None,
+        };
+
+        Ok(Self::new(TicketPriority::categories(categories)?).with_aging(aging_step))
+    }
+
+    // every `step` waited raises an element one category
+    pub fn with_aging(mut self, step: Option<Duration>) -> Self {
+        self.aging_step = step;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // every queue built from these settings gets its own copy of the policy
+    pub fn with_policy(mut self, policy: Box<dyn DequeuePolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn categories(&self) -> &Vec<TicketPriority> {
+        &self.categories
+    }
+
+    pub fn aging_step(&self) -> Option<Duration> {
+        self.aging_step
+    }
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        Self::new(TicketPriority::DEFAULT_CATEGORIES.to_vec())
+    }
+}
+
+// version of the document queues are saved as, files holding a plain array
+// of elements come from before it existed
+const QUEUE_DOCUMENT_VERSION: u32 = 1;
+// seconds are kept so elements enqueued in the same minute keep their order
+const ENQUEUED_AT_FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Serialize, Deserialize)]
+struct QueueDocument<T> {
+    version: u32,
+    sequence: usize,
+    policy: PolicyState,
+    levels: Vec<LevelDocument<T>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LevelDocument<T> {
+    priority: TicketPriority,
+    elements: Vec<T>,
+    enqueued_at: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueueFile<T> {
+    Document(QueueDocument<T>),
+    Flat(Vec<T>),
+}
+
+// where a key is served: the configured categories in the order given, any
+// other key right above the first configured one it outranks, and keys in
+// the same place from the greatest to the smallest
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank<K>(Reverse<usize>, bool, K);
+
+impl<K: Ord + Clone> Rank<K> {
+    fn of(key: &K, categories: &[K]) -> Self {
+        match categories.iter().position(|category| category == key) {
+            Some(idx) => Rank(Reverse(idx), false, key.clone()),
+            None => {
+                let idx = categories
+                    .iter()
+                    .position(|category| category < key)
+                    .unwrap_or(categories.len());
+                Rank(Reverse(idx), true, key.clone())
             }
         }
     }
 
-    pub fn high_priority_queue(&self) -> &Vec<T> {
-        &self.high_priority_queue
+    fn key(&self) -> &K {
+        &self.2
+    }
+}
+
+struct Entry<T: Priority> {
+    rank: Rank<T::Key>,
+    enqueued_at: NaiveDateTime,
+    // tells apart elements that started waiting at the same time
+    arrival: usize,
+    element: T,
+}
+
+impl<T: Priority> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .cmp(&other.rank)
+            .then_with(|| other.enqueued_at.cmp(&self.enqueued_at))
+            .then_with(|| other.arrival.cmp(&self.arrival))
+    }
+}
+
+impl<T: Priority> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    pub fn normal_priority_queue(&self) -> &Vec<T> {
-        &self.normal_priority_queue
+impl<T: Priority> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
+}
+
+impl<T: Priority> Eq for Entry<T> {}
+
+// a binary heap serving the greatest key first and equal keys in the order
+// they started waiting; ticket queues also get categories, aging and a
+// dequeue policy from their `QueueSettings`
+pub struct PriorityQueue<T: Priority> {
+    heap: BinaryHeap<Entry<T>>,
+    // keys served in the order given rather than in their own order
+    categories: Vec<T::Key>,
+    // how many elements were ever enqueued
+    sequence: usize,
+    arrivals: usize,
+    aging_step: Option<Duration>,
+    clock: Rc<dyn Clock>,
+    // strict priority when there is none
+    policy: Option<Box<dyn DequeuePolicy<T::Key>>>,
+}
+
+impl<T: Priority> PriorityQueue<T> {
+    pub fn new() -> Self {
+        Self::with_categories(Vec::new())
+    }
+
+    pub fn with_categories(categories: Vec<T::Key>) -> Self {
+        let mut unique: Vec<T::Key> = Vec::new();
+        for category in categories {
+            if !unique.contains(&category) {
+                unique.push(category);
+            }
+        }
+
+        Self {
+            heap: BinaryHeap::new(),
+            categories: unique,
+            sequence: 0,
+            arrivals: 0,
+            aging_step: None,
+            clock: Rc::new(SystemClock),
+            policy: None,
+        }
+    }
+
+    // the configured categories along with the other keys waiting, from the
+    // first to the last served
+    pub fn categories(&self) -> Vec<T::Key> {
+        let mut ranks: Vec<Rank<T::Key>> = self
+            .categories
+            .iter()
+            .map(|category| Rank::of(category, &self.categories))
+            .chain(self.heap.iter().map(|entry| entry.rank.clone()))
+            .collect();
+        ranks.sort_by(|a, b| b.cmp(a));
+        ranks.dedup();
 
+        ranks.into_iter().map(|Rank(_, _, key)| key).collect()
+    }
+
+    pub fn sequence(&self) -> usize {
+        self.sequence
+    }
+
+    pub fn enqueue(&mut self, element: T) {
+        let enqueued_at = element.enqueued_at().unwrap_or_else(|| self.clock.now());
+        self.push(element, enqueued_at);
+        self.sequence += 1;
+    }
+
+    fn push(&mut self, element: T, enqueued_at: NaiveDateTime) {
+        self.heap.push(Entry {
+            rank: Rank::of(&element.priority(), &self.categories),
+            enqueued_at,
+            arrival: self.arrivals,
+            element,
+        });
+        self.arrivals += 1;
+    }
+
+    // puts an element back where it stood before being dequeued, going by
+    // when it was enqueued
+    pub fn requeue(&mut self, element: T) {
+        let enqueued_at = element.enqueued_at().unwrap_or_else(|| self.clock.now());
+        self.push(element, enqueued_at);
+    }
+
+    // the elements waiting with this key, in the order they started waiting
+    pub fn level(&self, key: T::Key) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.sorted()
+            .into_iter()
+            .filter(|entry| *entry.rank.key() == key)
+            .map(|entry| entry.element.clone())
+            .collect()
+    }
+
+    // the order elements would leave the queue in if nobody else arrived
     pub fn queue(&self) -> Vec<&T> {
-        let mut high_priority_tickets: Vec<&T> = self.high_priority_queue.iter().collect();
-        high_priority_tickets.extend(&self.normal_priority_queue);
-        high_priority_tickets
+        self.serving_order()
+            .into_iter()
+            .map(|entry| &entry.element)
+            .collect()
+    }
+
+    pub fn iter(&self) -> std::vec::IntoIter<&T> {
+        self.queue().into_iter()
+    }
+
+    pub fn into_vec(mut self) -> Vec<T> {
+        std::iter::from_fn(|| self.dequeue()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        if self.by_rank() {
+            return self.heap.peek().map(|entry| &entry.element);
+        }
+
+        let mut policy = self.policy.clone();
+        let (_, entry) = Self::next_in(&mut policy, &mut self.groups())?;
+
+        Some(&entry.element)
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        if self.by_rank() {
+            return self.heap.pop().map(|entry| entry.element);
+        }
+
+        // the policy takes its turn on a copy, kept once the element is out
+        let mut policy = self.policy.clone();
+        let (_, entry) = Self::next_in(&mut policy, &mut self.groups())?;
+        let arrival = entry.arrival;
+        self.policy = policy;
+
+        self.take(arrival)
+    }
+
+    // how many elements would be served before the first one matching
+    pub fn position_of<P: Fn(&T) -> bool>(&self, predicate: P) -> Option<usize> {
+        self.iter().position(predicate)
+    }
+
+    // takes out the first matching element in serving order, without giving
+    // the dequeue policy a turn
+    pub fn remove_by<P: Fn(&T) -> bool>(&mut self, predicate: P) -> Option<T> {
+        let arrival = self
+            .serving_order()
+            .into_iter()
+            .find(|entry| predicate(&entry.element))?
+            .arrival;
+
+        self.take(arrival)
+    }
+
+    // drops everything waiting but keeps the policy where it was
+    pub fn reload<I: IntoIterator<Item = T>>(&mut self, elements: I) {
+        self.heap.clear();
+        for element in elements {
+            let enqueued_at = element.enqueued_at().unwrap_or_else(|| self.clock.now());
+            self.push(element, enqueued_at);
+        }
+    }
+
+    // takes the elements, counter and policy state of a queue read from a
+    // file while keeping the settings of this one; the policy state is only
+    // taken when it was saved by the same policy
+    pub fn restore(&mut self, saved: PriorityQueue<T>) {
+        let policy_state = saved.policy_state();
+        self.heap.clear();
+        for entry in saved.heap.into_sorted_vec().into_iter().rev() {
+            self.push(entry.element, entry.enqueued_at);
+        }
+        self.sequence = saved.sequence;
+        if let Some(policy) = self.policy.as_mut() {
+            policy.restore(&policy_state);
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.high_priority_queue.is_empty() && self.normal_priority_queue.is_empty()
+        self.heap.is_empty()
+    }
+
+    // without aging and with strict priority the heap order is the serving order
+    fn by_rank(&self) -> bool {
+        self.aging_step.is_none() && self.policy_state() == PolicyState::Strict
+    }
+
+    fn policy_state(&self) -> PolicyState {
+        self.policy
+            .as_ref()
+            .map_or(PolicyState::Strict, |policy| policy.state())
+    }
+
+    fn take(&mut self, arrival: usize) -> Option<T> {
+        let mut entries = std::mem::take(&mut self.heap).into_vec();
+        let taken = entries
+            .iter()
+            .position(|entry| entry.arrival == arrival)
+            .map(|idx| entries.swap_remove(idx).element);
+        self.heap = BinaryHeap::from(entries);
+
+        taken
+    }
+
+    fn sorted(&self) -> Vec<&Entry<T>> {
+        let mut entries: Vec<&Entry<T>> = self.heap.iter().collect();
+        entries.sort_by(|a, b| b.cmp(a));
+        entries
+    }
+
+    fn serving_order(&self) -> Vec<&Entry<T>> {
+        if self.by_rank() {
+            return self.sorted();
+        }
+
+        let mut policy = self.policy.clone();
+        let mut groups = self.groups();
+        let mut order = Vec::new();
+
+        while let Some((group, entry)) = Self::next_in(&mut policy, &mut groups) {
+            groups[group].1.pop_front();
+            order.push(entry);
+        }
+
+        order
+    }
+
+    // elements grouped by the category they are currently served in, each
+    // group in serving order
+    fn groups(&self) -> Vec<(T::Key, VecDeque<&Entry<T>>)> {
+        let levels = self.categories();
+        let now = self.clock.now();
+        let mut groups: Vec<Vec<&Entry<T>>> = levels.iter().map(|_| Vec::new()).collect();
+
+        for entry in self.sorted() {
+            let level = levels
+                .iter()
+                .position(|key| key == entry.rank.key())
+                .unwrap_or_default();
+            let effective_level = match self.aging_step {
+                Some(step) => {
+                    let waited = (now - entry.enqueued_at).num_milliseconds().max(0);
+                    // a step under a millisecond raises nothing
+                    let raised = waited
+                        .checked_div(step.num_milliseconds())
+                        .unwrap_or_default()
+                        .max(0);
+                    level.saturating_sub(raised as usize)
+                }
+                None => level,
+            };
+            groups[effective_level].push(entry);
+        }
+
+        levels
+            .into_iter()
+            .zip(groups)
+            .map(|(key, mut group)| {
+                // aged elements join in arrival order, ties keep the category order
+                if self.aging_step.is_some() {
+                    group.sort_by_key(|entry| entry.enqueued_at);
+                }
+                (key, group.into())
+            })
+            .collect()
+    }
+
+    fn next_in<'a>(
+        policy: &mut Option<Box<dyn DequeuePolicy<T::Key>>>,
+        groups: &mut [(T::Key, VecDeque<&'a Entry<T>>)],
+    ) -> Option<(usize, &'a Entry<T>)> {
+        let waiting: Vec<usize> = groups
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, group))| !group.is_empty())
+            .map(|(idx, _)| idx)
+            .collect();
+        if waiting.is_empty() {
+            return None;
+        }
+
+        let selected = match policy {
+            Some(policy) => {
+                let categories: Vec<T::Key> =
+                    waiting.iter().map(|idx| groups[*idx].0.clone()).collect();
+                policy.select(&categories).min(waiting.len() - 1)
+            }
+            None => 0,
+        };
+        let group = waiting[selected];
+
+        Some((group, *groups[group].1.front()?))
+    }
+}
+
+// categories, aging and dequeue policies are only configured for tickets
+impl<T: Priority<Key = TicketPriority>> PriorityQueue<T> {
+    pub fn with_settings(settings: &QueueSettings) -> Self {
+        let mut queue = Self::with_categories(settings.categories.clone());
+        queue.aging_step = settings.aging_step;
+        queue.clock = Rc::clone(&settings.clock);
+        queue.policy = Some(settings.policy.clone());
+        queue
     }
 }
 
@@ -87,17 +691,108 @@ impl<T: Priority> Default for PriorityQueue<T> {
     }
 }
 
-impl<T: Priority> From<Vec<T>> for PriorityQueue<T> {
-    fn from(value: Vec<T>) -> Self {
-        let mut queue = PriorityQueue::new();
+impl<'a, T: Priority> IntoIterator for &'a PriorityQueue<T> {
+    type Item = &'a T;
+    type IntoIter = std::vec::IntoIter<&'a T>;
 
-        value
-            .into_iter()
-            .for_each(|element| match element.priority() {
-                TicketPriority::High => queue.high_priority_queue.push(element),
-                TicketPriority::Normal => queue.normal_priority_queue.push(element),
-            });
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
 
+impl<T: Priority> Extend<T> for PriorityQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|element| self.enqueue(element));
+    }
+}
+
+impl<T: Priority> FromIterator<T> for PriorityQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
         queue
     }
 }
+
+impl<T: Priority<Key = TicketPriority> + Serialize> Serialize for PriorityQueue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = self.sorted();
+        let levels = self
+            .categories()
+            .into_iter()
+            .map(|priority| {
+                let level: Vec<&&Entry<T>> = entries
+                    .iter()
+                    .filter(|entry| *entry.rank.key() == priority)
+                    .collect();
+                LevelDocument {
+                    priority,
+                    elements: level.iter().map(|entry| &entry.element).collect(),
+                    enqueued_at: level
+                        .iter()
+                        .map(|entry| entry.enqueued_at.format(ENQUEUED_AT_FMT).to_string())
+                        .collect(),
+                }
+            })
+            .collect();
+
+        QueueDocument {
+            version: QUEUE_DOCUMENT_VERSION,
+            sequence: self.sequence,
+            policy: self.policy_state(),
+            levels,
+        }
+        .serialize(serializer)
+    }
+}
+
+// the queue read is built with the default settings and the saved policy,
+// `restore` moves it into a queue with the settings of the station
+impl<'de, T> Deserialize<'de> for PriorityQueue<T>
+where
+    T: Priority<Key = TicketPriority> + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let document = match QueueFile::deserialize(deserializer)? {
+            QueueFile::Document(document) => document,
+            QueueFile::Flat(elements) => return Ok(PriorityQueue::from(elements)),
+        };
+
+        if document.version != QUEUE_DOCUMENT_VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported queue document version: {}",
+                document.version
+            )));
+        }
+
+        let settings =
+            QueueSettings::new(document.levels.iter().map(|level| level.priority).collect())
+                .with_policy(document.policy.policy());
+        let mut queue = PriorityQueue::with_settings(&settings);
+
+        for level in document.levels {
+            if level.elements.len() != level.enqueued_at.len() {
+                return Err(de::Error::custom(format!(
+                    "level {:?} has {} elements and {} arrival times",
+                    level.priority,
+                    level.elements.len(),
+                    level.enqueued_at.len()
+                )));
+            }
+            for (element, enqueued_at) in level.elements.into_iter().zip(level.enqueued_at) {
+                let enqueued_at = NaiveDateTime::parse_from_str(&enqueued_at, ENQUEUED_AT_FMT)
+                    .map_err(|_| de::Error::custom(format!("invalid arrival: {}", enqueued_at)))?;
+                queue.push(element, enqueued_at);
+            }
+        }
+        queue.sequence = document.sequence;
+
+        Ok(queue)
+    }
+}
+
+impl<T: Priority> From<Vec<T>> for PriorityQueue<T> {
+    fn from(value: Vec<T>) -> Self {
+        value.into_iter().collect()
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+
+use crate::cpf::Cpf;
+use crate::database::GetKeyAttribute;
+use crate::file_lock::FileLock;
+use crate::json_handler::JsonHandler;
+use crate::payment::Procedure;
+use crate::priority_queue::{PriorityQueue, QueueSettings};
+use crate::service_sheet::SheetWithPriority;
+use crate::shutdown::archive_queue;
+
+// the name of the one queue read from DENTIST_QUEUE_FILE_PATH
+pub const DEFAULT_QUEUE_NAME: &str = "geral";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteCriterion {
+    // a word found in the reason of the visit
+    Reason(String),
+    Dentist(String),
+    Procedure(Procedure),
+}
+
+impl RouteCriterion {
+    fn matches(&self, sheet: &SheetWithPriority) -> bool {
+        let service_sheet = sheet.service_sheet();
+        match self {
+            RouteCriterion::Reason(keyword) => service_sheet
+                .reason()
+                .to_lowercase()
+                .contains(&keyword.to_lowercase()),
+            RouteCriterion::Dentist(dentist) => service_sheet
+                .dentist()
+                .is_some_and(|requested| requested.trim().eq_ignore_ascii_case(dentist)),
+            RouteCriterion::Procedure(procedure) => service_sheet.procedure() == Some(*procedure),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoutingRule {
+    criterion: RouteCriterion,
+    queue: String,
+}
+
+impl RoutingRule {
+    pub fn new(criterion: RouteCriterion, queue: String) -> Self {
+        Self { criterion, queue }
+    }
+
+    // reads a rule such as "reason:aparelho=ortodontia", "dentist:Ana=ana"
+    // or "procedure:Cleaning=limpeza"
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (criterion, queue) = rule
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid routing rule: {}", rule.trim()))?;
+        let (kind, value) = criterion
+            .split_once(':')
+            .ok_or_else(|| anyhow!("invalid routing rule: {}", rule.trim()))?;
+        let value = value.trim();
+
+        let criterion = match kind.trim().to_lowercase().as_str() {
+            "reason" => RouteCriterion::Reason(value.to_string()),
+            "dentist" => RouteCriterion::Dentist(value.to_string()),
+            "procedure" => RouteCriterion::Procedure(
+                Procedure::parse(value).ok_or_else(|| anyhow!("unknown procedure: {}", value))?,
+            ),
+            kind => return Err(anyhow!("unknown routing criterion: {}", kind)),
+        };
+
+        if value.is_empty() || queue.trim().is_empty() {
+            return Err(anyhow!("invalid routing rule: {}", rule.trim()));
+        }
+
+        Ok(Self::new(criterion, queue.trim().to_string()))
+    }
+
+    pub fn criterion(&self) -> &RouteCriterion {
+        &self.criterion
+    }
+
+    pub fn queue(&self) -> &str {
+        &self.queue
+    }
+}
+
+struct NamedQueue {
+    name: String,
+    path: String,
+    queue: PriorityQueue<SheetWithPriority>,
+}
+
+// the dentist queues, each kept in its own file; sheets no rule routes go to
+// the first queue
+pub struct QueueRouter {
+    queues: Vec<NamedQueue>,
+    rules: Vec<RoutingRule>,
+}
+
+impl QueueRouter {
+    // `queues` holds the name and file of each queue
+    pub fn new(queues: Vec<(String, String)>, settings: &QueueSettings) -> Result<Self> {
+        if queues.is_empty() {
+            return Err(anyhow!("at least one queue is needed"));
+        }
+
+        let mut named_queues: Vec<NamedQueue> = Vec::new();
+        for (name, path) in queues {
+            if named_queues.iter().any(|queue| queue.name == name) {
+                return Err(anyhow!("duplicated queue: {}", name));
+            }
+            named_queues.push(NamedQueue {
+                name,
+                path,
+                queue: PriorityQueue::with_settings(settings),
+            });
+        }
+
+        Ok(Self {
+            queues: named_queues,
+            rules: Vec::new(),
+        })
+    }
+
+    // the DENTIST_QUEUES setting, or else a single queue at the
+    // DENTIST_QUEUE_FILE_PATH of the setups from before queues had names
+    pub fn queues_config(queues: Option<&str>, queue_file: Option<&str>) -> Result<String> {
+        match (queues.map(str::trim), queue_file.map(str::trim)) {
+            (Some(queues), _) if !queues.is_empty() => Ok(queues.to_string()),
+            (_, Some(path)) if !path.is_empty() => Ok(format!("{}={}", DEFAULT_QUEUE_NAME, path)),
+            _ => Err(anyhow!(
+                "neither DENTIST_QUEUES nor DENTIST_QUEUE_FILE_PATH is set"
+            )),
+        }
+    }
+
+    // reads the DENTIST_QUEUES and DENTIST_QUEUE_ROUTES settings, such as
+    // "geral=dentist_queue.json,limpeza=cleaning_queue.json" and
+    // "procedure:Cleaning=limpeza"
+    pub fn from_config(
+        queues: &str,
+        routes: Option<&str>,
+        settings: &QueueSettings,
+    ) -> Result<Self> {
+        let mut named_queues = Vec::new();
+        for queue in queues.split(',').filter(|queue| !queue.trim().is_empty()) {
+            let (name, path) = queue
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid queue: {}", queue.trim()))?;
+            named_queues.push((name.trim().to_string(), path.trim().to_string()));
+        }
+
+        let mut router = Self::new(named_queues, settings)?;
+        for rule in routes
+            .unwrap_or_default()
+            .split(',')
+            .filter(|rule| !rule.trim().is_empty())
+        {
+            router = router.with_rule(RoutingRule::parse(rule)?)?;
+        }
+
+        Ok(router)
+    }
+
+    // keeps each queue in the file `file` gives for the configured one, as a
+    // replayed session does
+    pub fn with_files<F>(mut self, mut file: F) -> Result<Self>
+    where
+        F: FnMut(String) -> Result<String>,
+    {
+        for queue in self.queues.iter_mut() {
+            queue.path = file(queue.path.clone())?;
+        }
+        Ok(self)
+    }
+
+    // rules are tried in the order they were added, the first match wins
+    pub fn with_rule(mut self, rule: RoutingRule) -> Result<Self> {
+        self.index_of(rule.queue())?;
+        self.rules.push(rule);
+        Ok(self)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.queues
+            .iter()
+            .map(|queue| queue.name.as_str())
+            .collect()
+    }
+
+    pub fn rules(&self) -> &[RoutingRule] {
+        &self.rules
+    }
+
+    // as it was last loaded or saved
+    pub fn queue(&self, name: &str) -> Result<&PriorityQueue<SheetWithPriority>> {
+        Ok(&self.queues[self.index_of(name)?].queue)
+    }
+
+    pub fn route(&self, sheet: &SheetWithPriority) -> &str {
+        self.rules
+            .iter()
+            .find(|rule| rule.criterion.matches(sheet))
+            .map(|rule| rule.queue())
+            .unwrap_or(&self.queues[0].name)
+    }
+
+    // returns the name of the queue the sheet went to
+    pub fn enqueue(&mut self, sheet: SheetWithPriority) -> Result<String> {
+        let name = self.route(&sheet).to_string();
+        let _lock = self.lock(&name)?;
+        let idx = self.load(&name)?;
+
+        self.queues[idx].queue.enqueue(sheet);
+        self.save(idx)?;
+
+        Ok(name)
+    }
+
+    pub fn dequeue(&mut self, name: &str) -> Result<Option<SheetWithPriority>> {
+        let _lock = self.lock(name)?;
+        let idx = self.load(name)?;
+
+        let sheet = self.queues[idx].queue.dequeue();
+        if sheet.is_some() {
+            self.save(idx)?;
+        }
+
+        Ok(sheet)
+    }
+
+    // moves the sheet of the pacient with `cpf` to another queue, keeping
+    // the time it has already waited; returns false when it isn't in `from`
+    pub fn transfer(&mut self, cpf: &Cpf, from: &str, to: &str) -> Result<bool> {
+        // both files are locked in the order the queues are configured, so
+        // two stations moving sheets opposite ways don't wait on each other
+        let mut locked = vec![self.index_of(from)?, self.index_of(to)?];
+        locked.sort_unstable();
+        locked.dedup();
+        let _locks = locked
+            .into_iter()
+            .map(|idx| FileLock::acquire(&self.queues[idx].path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let from_idx = self.load(from)?;
+        let to_idx = self.load(to)?;
+
+        let sheet = match self.queues[from_idx]
+            .queue
+            .remove_by(|sheet| sheet.get_key_attribute() == cpf.digits())
+        {
+            Some(sheet) => sheet,
+            None => return Ok(false),
+        };
+
+        self.queues[to_idx].queue.requeue(sheet);
+        self.save(from_idx)?;
+        self.save(to_idx)?;
+
+        Ok(true)
+    }
+
+    // the queue a pacient is waiting in, if any
+    pub fn find(&mut self, cpf: &Cpf) -> Result<Option<String>> {
+        for idx in 0..self.queues.len() {
+            let name = self.queues[idx].name.clone();
+            self.load(&name)?;
+            if self.queues[idx]
+                .queue
+                .position_of(|sheet| sheet.get_key_attribute() == cpf.digits())
+                .is_some()
+            {
+                return Ok(Some(name));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // reads every queue file again, for stations that show them
+    pub fn refresh(&mut self) -> Result<()> {
+        for idx in 0..self.queues.len() {
+            let name = self.queues[idx].name.clone();
+            self.load(&name)?;
+        }
+
+        Ok(())
+    }
+
+    // archives every queue file, the paths they were moved to are returned
+    pub fn archive(&mut self, at: DateTime<Local>) -> Result<Vec<String>> {
+        let mut archived = Vec::new();
+        for named_queue in self.queues.iter_mut() {
+            let _lock = FileLock::acquire(&named_queue.path)?;
+            archived.extend(archive_queue(&named_queue.path, at)?);
+            named_queue.queue.reload(Vec::new());
+        }
+
+        Ok(archived)
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize> {
+        self.queues
+            .iter()
+            .position(|queue| queue.name == name)
+            .ok_or_else(|| anyhow!("unknown queue: {}", name))
+    }
+
+    // held from reading a queue to saving it, while no other station may
+    // change its file
+    fn lock(&self, name: &str) -> Result<FileLock> {
+        FileLock::acquire(&self.queues[self.index_of(name)?].path)
+    }
+
+    // other stations write to the same files, so queues are read before
+    // every change
+    fn load(&mut self, name: &str) -> Result<usize> {
+        let idx = self.index_of(name)?;
+        let named_queue = &mut self.queues[idx];
+
+        if Path::new(&named_queue.path).exists() {
+            named_queue
+                .queue
+                .restore(JsonHandler::read_document(&named_queue.path)?);
+        } else {
+            named_queue.queue.reload(Vec::new());
+        }
+
+        Ok(idx)
+    }
+
+    fn save(&self, idx: usize) -> Result<()> {
+        let named_queue = &self.queues[idx];
+        JsonHandler::save_as_json(&named_queue.path, &named_queue.queue)
+    }
+}
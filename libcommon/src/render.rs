@@ -0,0 +1,238 @@
+use anyhow::{anyhow, Result};
+
+// what a field holds, for the formats that tell numbers and missing values
+// apart from text, such as JSON
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Number(serde_json::Number),
+    Missing,
+}
+
+pub struct Field {
+    key: &'static str,
+    label: &'static str,
+    value: String,
+    data: FieldValue,
+}
+
+impl Field {
+    pub fn new<T: ToString>(key: &'static str, label: &'static str, value: T) -> Self {
+        let value = value.to_string();
+        Self {
+            key,
+            label,
+            data: FieldValue::Text(value.clone()),
+            value,
+        }
+    }
+
+    pub fn number<N>(key: &'static str, label: &'static str, number: N) -> Self
+    where
+        N: Into<serde_json::Number>,
+    {
+        let number = number.into();
+        Self {
+            key,
+            label,
+            value: number.to_string(),
+            data: FieldValue::Number(number),
+        }
+    }
+
+    // shown as a dash, and as null in JSON, when there is no value
+    pub fn optional<T: ToString>(key: &'static str, label: &'static str, value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::new(key, label, value),
+            None => Self {
+                key,
+                label,
+                value: "-".to_string(),
+                data: FieldValue::Missing,
+            },
+        }
+    }
+
+    // the text shown in place of the value, like the currency of an amount
+    pub fn shown_as<T: ToString>(mut self, text: T) -> Self {
+        self.value = text.to_string();
+        self
+    }
+
+    pub fn key(&self) -> &str {
+        self.key
+    }
+
+    pub fn label(&self) -> &str {
+        self.label
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn data(&self) -> &FieldValue {
+        &self.data
+    }
+}
+
+pub trait Render {
+    fn fields(&self) -> Vec<Field>;
+}
+
+pub trait Renderer {
+    fn render(&self, fields: &[Field]) -> String;
+
+    fn render_all(&self, items: &[Vec<Field>]) -> String;
+}
+
+pub struct PlainTextRenderer;
+
+impl Renderer for PlainTextRenderer {
+    fn render(&self, fields: &[Field]) -> String {
+        fields
+            .iter()
+            .map(|field| format!("{}: {}\n", field.label, field.value))
+            .collect()
+    }
+
+    fn render_all(&self, items: &[Vec<Field>]) -> String {
+        items
+            .iter()
+            .map(|fields| self.render(fields))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+pub struct JsonRenderer;
+
+impl JsonRenderer {
+    fn object(fields: &[Field]) -> String {
+        // written by hand so the keys keep the order in which they were declared
+        let members: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                let value = match &field.data {
+                    FieldValue::Text(text) => serde_json::Value::from(text.as_str()),
+                    FieldValue::Number(number) => serde_json::Value::from(number.clone()),
+                    FieldValue::Missing => serde_json::Value::Null,
+                };
+                format!("{}:{}", serde_json::Value::from(field.key), value)
+            })
+            .collect();
+
+        format!("{{{}}}", members.join(","))
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, fields: &[Field]) -> String {
+        format!("{}\n", Self::object(fields))
+    }
+
+    fn render_all(&self, items: &[Vec<Field>]) -> String {
+        let objects: Vec<String> = items.iter().map(|fields| Self::object(fields)).collect();
+        format!("[{}]\n", objects.join(","))
+    }
+}
+
+pub struct HtmlRenderer;
+
+impl HtmlRenderer {
+    pub fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;")
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, fields: &[Field]) -> String {
+        let mut html = String::from("<dl>\n");
+
+        for field in fields {
+            html.push_str(&format!(
+                "  <dt class=\"{}\">{}</dt><dd>{}</dd>\n",
+                field.key,
+                Self::escape(field.label),
+                Self::escape(&field.value)
+            ));
+        }
+
+        html.push_str("</dl>\n");
+        html
+    }
+
+    fn render_all(&self, items: &[Vec<Field>]) -> String {
+        let mut html = String::from("<section>\n");
+        items
+            .iter()
+            .for_each(|fields| html.push_str(&self.render(fields)));
+        html.push_str("</section>\n");
+        html
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Html,
+}
+
+impl OutputFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(anyhow!("Unknown output format: {}", format)),
+        }
+    }
+
+    // reads `--output <format>`, `--output=<format>` or `--json` from the
+    // command line arguments
+    pub fn from_args<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut format = OutputFormat::default();
+
+        while let Some(arg) = args.next() {
+            if arg == "--output" {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing value for --output"))?;
+                format = Self::parse(&value)?;
+            } else if let Some(value) = arg.strip_prefix("--output=") {
+                format = Self::parse(value)?;
+            } else if arg == "--json" {
+                format = OutputFormat::Json;
+            }
+        }
+
+        Ok(format)
+    }
+
+    pub fn renderer(&self) -> &'static dyn Renderer {
+        match self {
+            OutputFormat::Text => &PlainTextRenderer,
+            OutputFormat::Json => &JsonRenderer,
+            OutputFormat::Html => &HtmlRenderer,
+        }
+    }
+
+    pub fn render<T: Render>(&self, value: &T) -> String {
+        self.renderer().render(&value.fields())
+    }
+
+    pub fn render_all<T: Render>(&self, values: &[T]) -> String {
+        let items: Vec<Vec<Field>> = values.iter().map(Render::fields).collect();
+        self.renderer().render_all(&items)
+    }
+}
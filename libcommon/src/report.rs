@@ -0,0 +1,411 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::appointment::Appointment;
+use crate::datetime_parsing::parse_default_fmt_to_datetime;
+use crate::i18n;
+use crate::payment::{Payment, Procedure};
+use crate::priority_queue::{Priority, PriorityQueueTicket, TicketPriority};
+use crate::render::HtmlRenderer;
+use crate::service_sheet::ServiceSheet;
+use crate::ticket_record::{TicketOutcome, TicketRecord};
+
+const APPOINTMENT_DATE_FMT: &str = "%d-%m-%Y";
+
+const STYLE: &str =
+    "body { font-family: sans-serif; margin: 2em auto; max-width: 48em; color: #222; }\n\
+    h1 { margin-bottom: 0; }\n\
+    .date { color: #666; margin-top: 0.2em; }\n\
+    table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }\n\
+    th, td { border: 1px solid #ccc; padding: 0.4em 0.6em; text-align: left; }\n\
+    th { background: #f0f0f0; }\n\
+    td.number, th.number { text-align: right; }\n\
+    tr.total td { font-weight: bold; }\n";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcedureRevenue {
+    procedure: Procedure,
+    count: usize,
+    total: usize,
+}
+
+impl ProcedureRevenue {
+    pub fn procedure(&self) -> Procedure {
+        self.procedure
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DailyReport {
+    date: NaiveDate,
+    tickets_by_priority: Vec<(TicketPriority, usize)>,
+    pacients_attended: usize,
+    average_wait_minutes: Option<i64>,
+    no_shows: usize,
+    cancelled_tickets: usize,
+    appointments_kept: Vec<Appointment>,
+    appointments_missed: Vec<Appointment>,
+    revenue_by_procedure: Vec<ProcedureRevenue>,
+}
+
+impl DailyReport {
+    pub fn build(
+        date: NaiveDate,
+        queue: &[PriorityQueueTicket],
+        ticket_history: &[TicketRecord],
+        service_sheets: &[ServiceSheet],
+        appointments: &[Appointment],
+        payments: &[Payment],
+    ) -> Self {
+        let is_on_date = |datetime: Option<&str>| {
+            datetime
+                .and_then(parse_default_fmt_to_datetime)
+                .is_some_and(|datetime| datetime.date() == date)
+        };
+
+        // a ticket put back in the queue is counted by the record of how it
+        // ended, or as waiting
+        let ticket_history: Vec<&TicketRecord> = ticket_history
+            .iter()
+            .filter(|record| record.outcome().is_final())
+            .collect();
+
+        // tickets still waiting were issued as well, they just weren't called yet
+        let issued: Vec<TicketPriority> = ticket_history
+            .iter()
+            .filter(|record| is_on_date(record.issued_at()))
+            .map(|record| record.priority())
+            .chain(
+                queue
+                    .iter()
+                    .filter(|ticket| is_on_date(ticket.issued_at()))
+                    .map(|ticket| ticket.priority()),
+            )
+            .collect();
+
+        let tickets_by_priority = TicketPriority::ALL
+            .iter()
+            .map(|priority| {
+                let count = issued.iter().filter(|issued| *issued == priority).count();
+                (*priority, count)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        let waits: Vec<i64> = ticket_history
+            .iter()
+            .filter(|record| is_on_date(record.called_at()))
+            .filter_map(|record| record.lifecycle().waited_minutes())
+            .collect();
+
+        let average_wait_minutes = if waits.is_empty() {
+            None
+        } else {
+            Some(waits.iter().sum::<i64>() / waits.len() as i64)
+        };
+
+        let count_outcome = |outcome: TicketOutcome| {
+            ticket_history
+                .iter()
+                .filter(|record| record.outcome() == outcome && is_on_date(record.issued_at()))
+                .count()
+        };
+
+        let sheets_of_the_day: Vec<&ServiceSheet> = service_sheets
+            .iter()
+            .filter(|sheet| is_on_date(Some(sheet.date())))
+            .collect();
+
+        let (appointments_kept, appointments_missed) = appointments
+            .iter()
+            .filter(|appointment| {
+                NaiveDate::parse_from_str(appointment.date().trim(), APPOINTMENT_DATE_FMT)
+                    .is_ok_and(|appointment_date| appointment_date == date)
+            })
+            .cloned()
+            .partition(|appointment| {
+                sheets_of_the_day
+                    .iter()
+                    .any(|sheet| sheet.pacient_account().cpf() == appointment.cpf())
+            });
+
+        let payments_of_the_day: Vec<&Payment> = payments
+            .iter()
+            .filter(|payment| is_on_date(Some(payment.date())))
+            .collect();
+
+        let revenue_by_procedure = Procedure::ALL
+            .iter()
+            .map(|procedure| {
+                let paid: Vec<&&Payment> = payments_of_the_day
+                    .iter()
+                    .filter(|payment| payment.procedure() == *procedure)
+                    .collect();
+
+                ProcedureRevenue {
+                    procedure: *procedure,
+                    count: paid.len(),
+                    total: paid.iter().map(|payment| payment.amount()).sum(),
+                }
+            })
+            .collect();
+
+        Self {
+            date,
+            tickets_by_priority,
+            pacients_attended: sheets_of_the_day.len(),
+            average_wait_minutes,
+            no_shows: count_outcome(TicketOutcome::NoShow),
+            cancelled_tickets: count_outcome(TicketOutcome::Cancelled),
+            appointments_kept,
+            appointments_missed,
+            revenue_by_procedure,
+        }
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn tickets_by_priority(&self) -> &Vec<(TicketPriority, usize)> {
+        &self.tickets_by_priority
+    }
+
+    pub fn tickets_issued(&self) -> usize {
+        self.tickets_by_priority
+            .iter()
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn pacients_attended(&self) -> usize {
+        self.pacients_attended
+    }
+
+    pub fn average_wait_minutes(&self) -> Option<i64> {
+        self.average_wait_minutes
+    }
+
+    pub fn no_shows(&self) -> usize {
+        self.no_shows
+    }
+
+    pub fn cancelled_tickets(&self) -> usize {
+        self.cancelled_tickets
+    }
+
+    pub fn appointments_kept(&self) -> &Vec<Appointment> {
+        &self.appointments_kept
+    }
+
+    pub fn appointments_missed(&self) -> &Vec<Appointment> {
+        &self.appointments_missed
+    }
+
+    pub fn revenue_by_procedure(&self) -> &Vec<ProcedureRevenue> {
+        &self.revenue_by_procedure
+    }
+
+    pub fn total_revenue(&self) -> usize {
+        self.revenue_by_procedure
+            .iter()
+            .map(|revenue| revenue.total)
+            .sum()
+    }
+
+    pub fn save_as_html(&self, path: &str) -> Result<()> {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+
+        let mut file = File::create(path)?;
+        file.write_all(self.to_html().as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn to_html(&self) -> String {
+        let date = self.date.format(APPOINTMENT_DATE_FMT).to_string();
+        let mut html = String::new();
+
+        html.push_str(&format!(
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n",
+            i18n::locale().code()
+        ));
+        html.push_str("<meta charset=\"utf-8\">\n");
+        html.push_str(&format!(
+            "<title>{}</title>\n",
+            HtmlRenderer::escape(&i18n::text("report.title", &[("date", &date)]))
+        ));
+        html.push_str(&format!("<style>\n{}</style>\n", STYLE));
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&heading(1, i18n::message("report.heading")));
+        html.push_str(&format!("<p class=\"date\">{}</p>\n", date));
+
+        html.push_str(&heading(2, i18n::message("report.tickets")));
+        let mut rows: Vec<Vec<String>> = self
+            .tickets_by_priority
+            .iter()
+            .map(|(priority, count)| vec![priority.label().to_string(), count.to_string()])
+            .collect();
+        let total = i18n::message("report.total").to_string();
+        rows.push(vec![total.clone(), self.tickets_issued().to_string()]);
+        html.push_str(&table(
+            &[
+                i18n::message("report.service"),
+                i18n::message("report.ticket_count"),
+            ],
+            1,
+            &rows,
+        ));
+
+        html.push_str(&heading(2, i18n::message("report.attendance")));
+        let average_wait = match self.average_wait_minutes {
+            Some(minutes) => i18n::text("report.minutes", &[("minutes", &minutes)]),
+            None => "-".to_string(),
+        };
+        let indicator =
+            |key: &'static str, value: String| vec![i18n::message(key).to_string(), value];
+        html.push_str(&table(
+            &[
+                i18n::message("report.indicator"),
+                i18n::message("report.value"),
+            ],
+            1,
+            &[
+                indicator(
+                    "report.pacients_attended",
+                    self.pacients_attended.to_string(),
+                ),
+                indicator("report.average_wait", average_wait),
+                indicator("report.no_shows", self.no_shows.to_string()),
+                indicator("report.cancelled", self.cancelled_tickets.to_string()),
+            ],
+        ));
+
+        html.push_str(&heading(2, i18n::message("report.appointments")));
+        let status = |appointment: &Appointment, key: &'static str| {
+            vec![
+                appointment.cpf().to_string(),
+                i18n::message(key).to_string(),
+            ]
+        };
+        let mut rows: Vec<Vec<String>> = self
+            .appointments_kept
+            .iter()
+            .map(|appointment| status(appointment, "report.kept"))
+            .chain(
+                self.appointments_missed
+                    .iter()
+                    .map(|appointment| status(appointment, "report.missed")),
+            )
+            .collect();
+        rows.push(vec![
+            total.clone(),
+            i18n::text(
+                "report.appointments_total",
+                &[
+                    ("kept", &self.appointments_kept.len()),
+                    ("missed", &self.appointments_missed.len()),
+                ],
+            ),
+        ]);
+        html.push_str(&table(
+            &[i18n::message("field.cpf"), i18n::message("report.status")],
+            2,
+            &rows,
+        ));
+
+        html.push_str(&heading(2, i18n::message("report.revenue")));
+        let mut rows: Vec<Vec<String>> = self
+            .revenue_by_procedure
+            .iter()
+            .map(|revenue| {
+                vec![
+                    revenue.procedure.label().to_string(),
+                    revenue.count.to_string(),
+                    format!("R${}", revenue.total),
+                ]
+            })
+            .collect();
+        rows.push(vec![
+            total,
+            self.revenue_by_procedure
+                .iter()
+                .map(|revenue| revenue.count)
+                .sum::<usize>()
+                .to_string(),
+            format!("R${}", self.total_revenue()),
+        ]);
+        html.push_str(&table(
+            &[
+                i18n::message("report.procedure"),
+                i18n::message("report.count"),
+                i18n::message("report.value"),
+            ],
+            1,
+            &rows,
+        ));
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+// columns from `numbers_from` onwards are aligned as numbers and the last row is the total
+fn heading(level: usize, text: &str) -> String {
+    format!("<h{level}>{}</h{level}>\n", HtmlRenderer::escape(text))
+}
+
+fn table(headers: &[&str], numbers_from: usize, rows: &[Vec<String>]) -> String {
+    let is_number = |idx: usize| idx >= numbers_from;
+    let mut html = String::from("<table>\n<tr>");
+
+    for (idx, header) in headers.iter().enumerate() {
+        let class = if is_number(idx) {
+            " class=\"number\""
+        } else {
+            ""
+        };
+        html.push_str(&format!(
+            "<th{}>{}</th>",
+            class,
+            HtmlRenderer::escape(header)
+        ));
+    }
+    html.push_str("</tr>\n");
+
+    for (row_idx, row) in rows.iter().enumerate() {
+        let class = if row_idx + 1 == rows.len() {
+            " class=\"total\""
+        } else {
+            ""
+        };
+        html.push_str(&format!("<tr{}>", class));
+
+        for (idx, cell) in row.iter().enumerate() {
+            let class = if is_number(idx) {
+                " class=\"number\""
+            } else {
+                ""
+            };
+            html.push_str(&format!("<td{}>{}</td>", class, HtmlRenderer::escape(cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n");
+    html
+}
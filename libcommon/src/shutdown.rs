@@ -0,0 +1,204 @@
+use std::fs;
+use std::io::{self, BufRead, Cursor, Read, Write};
+use std::path::Path;
+use std::process::{self, ExitCode};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+
+use crate::i18n;
+use crate::io_handler::{IOHandler, Menu, Prompt};
+
+// exit code of a process stopped by Ctrl-C
+pub const INTERRUPTED_EXIT: u8 = 130;
+
+// how often a station waiting for input looks for an interruption
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const ARCHIVE_FMT: &str = "%Y%m%d-%H%M%S";
+
+// lock files this process holds, removed if it is interrupted before it
+// lets go of them
+static HELD_LOCKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shutdown {
+    // stops the station, whoever is waiting stays in the queues
+    Exit,
+    // the office closed, the queues are archived and start the next day empty
+    EndOfDay,
+}
+
+// asks how the station should stop, `None` when the operator changed their
+// mind
+pub fn ask_shutdown<R: BufRead, W: Write>(
+    io_handler: &mut IOHandler<R, W>,
+) -> Result<Option<Shutdown>> {
+    let menu = Menu::new(i18n::message("shutdown.menu"))
+        .option(i18n::message("shutdown.exit"), Shutdown::Exit)
+        .option(i18n::message("shutdown.end_of_day"), Shutdown::EndOfDay)
+        .back(i18n::message("shutdown.cancel"));
+
+    let shutdown = match io_handler.choose(&menu)? {
+        Some(shutdown) => shutdown,
+        None => return Ok(None),
+    };
+
+    let confirm = match shutdown {
+        Shutdown::Exit => "shutdown.confirm_exit",
+        Shutdown::EndOfDay => "shutdown.confirm_end_of_day",
+    };
+    if io_handler.ask(&Prompt::yes_no(i18n::message(confirm)))? {
+        Ok(Some(shutdown))
+    } else {
+        Ok(None)
+    }
+}
+
+// moves the queue file aside, named after when it was archived, so the
+// next day starts with an empty queue; `None` when there was no queue
+pub fn archive_queue(path: &str, at: DateTime<Local>) -> Result<Option<String>> {
+    let queue = Path::new(path);
+    if !queue.exists() {
+        return Ok(None);
+    }
+
+    let stem = queue
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let archived_name = match queue.extension() {
+        Some(extension) => format!(
+            "{}-{}.{}",
+            stem,
+            at.format(ARCHIVE_FMT),
+            extension.to_string_lossy()
+        ),
+        None => format!("{}-{}", stem, at.format(ARCHIVE_FMT)),
+    };
+    let archived = queue.with_file_name(archived_name);
+
+    fs::rename(queue, &archived)?;
+    Ok(Some(archived.to_string_lossy().to_string()))
+}
+
+pub(crate) fn hold_lock(path: &str) {
+    if let Ok(mut locks) = HELD_LOCKS.lock() {
+        locks.push(path.to_string());
+    }
+}
+
+pub(crate) fn release_lock(path: &str) {
+    if let Ok(mut locks) = HELD_LOCKS.lock() {
+        locks.retain(|lock| lock != path);
+    }
+    let _ = fs::remove_file(path);
+}
+
+// on Ctrl-C the station is only told to stop, so it finishes what it was
+// saving, puts back the ticket it was calling and saves its transcript; a
+// second Ctrl-C stops it at once, releasing the held locks so the other
+// stations don't wait for a lock nobody will let go of
+pub fn handle_interrupts() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = io::stdout().flush();
+        if let Ok(locks) = HELD_LOCKS.lock() {
+            for lock in locks.iter() {
+                let _ = fs::remove_file(lock);
+            }
+        }
+        process::exit(INTERRUPTED_EXIT.into());
+    })?;
+
+    Ok(())
+}
+
+// what Ctrl-C does, the station stops the next time it waits for input
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// how a station that stopped on its own exits, 130 when it was interrupted
+pub fn exit_code() -> ExitCode {
+    if interrupted() {
+        ExitCode::from(INTERRUPTED_EXIT)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// stdin read on a thread of its own, so a station waiting for a line still
+// notices it was interrupted; its input ends when it is
+pub struct InterruptibleStdin {
+    lines: Receiver<io::Result<String>>,
+    line: Cursor<Vec<u8>>,
+}
+
+impl InterruptibleStdin {
+    pub fn new() -> Self {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut line = String::new();
+            let read = io::stdin().lock().read_line(&mut line);
+            let ended = !matches!(read, Ok(read) if read > 0);
+            if sender.send(read.map(|_| line)).is_err() || ended {
+                break;
+            }
+        });
+
+        Self {
+            lines,
+            line: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for InterruptibleStdin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Read for InterruptibleStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.fill_buf()?.read(buf)?;
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for InterruptibleStdin {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.line.position() as usize >= self.line.get_ref().len() && !interrupted() {
+            match self.lines.recv_timeout(INTERRUPT_POLL_INTERVAL) {
+                Ok(line) => self.line = Cursor::new(line?.into_bytes()),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            // an empty line is the end of the input
+            if self.line.get_ref().is_empty() {
+                break;
+            }
+        }
+        self.line.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.line.consume(amount);
+    }
+}
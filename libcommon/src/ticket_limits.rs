@@ -0,0 +1,245 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::datetime_parsing::parse_default_fmt_to_datetime;
+use crate::i18n;
+use crate::priority_queue::TicketPriority;
+use crate::ticket_record::{TicketOutcome, TicketRecord};
+
+const HOURS_FMT: &str = "%H:%M";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    Closed {
+        opens: NaiveTime,
+        closes: NaiveTime,
+    },
+    DailyCapacity(usize),
+    CategoryCapacity(TicketPriority, usize),
+    // everyone waiting wouldn't be seen before closing
+    Cutoff {
+        estimated_end: NaiveTime,
+        closes: NaiveTime,
+    },
+}
+
+impl Refusal {
+    pub fn reason(&self) -> String {
+        match self {
+            Refusal::Closed { opens, closes } => i18n::text(
+                "refusal.closed",
+                &[
+                    ("opens", &opens.format(HOURS_FMT)),
+                    ("closes", &closes.format(HOURS_FMT)),
+                ],
+            ),
+            Refusal::DailyCapacity(capacity) => {
+                i18n::text("refusal.daily_capacity", &[("capacity", capacity)])
+            }
+            Refusal::CategoryCapacity(priority, capacity) => i18n::text(
+                "refusal.category_capacity",
+                &[("capacity", capacity), ("category", &priority.label())],
+            ),
+            Refusal::Cutoff {
+                estimated_end,
+                closes,
+            } => i18n::text(
+                "refusal.cutoff",
+                &[
+                    ("estimated_end", &estimated_end.format(HOURS_FMT)),
+                    ("closes", &closes.format(HOURS_FMT)),
+                ],
+            ),
+        }
+    }
+}
+
+impl Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason())
+    }
+}
+
+impl Error for Refusal {}
+
+// when and how many tickets the kiosk issues, no limit is set by default
+#[derive(Clone, Debug, Default)]
+pub struct TicketLimits {
+    opening_hours: Option<(NaiveTime, NaiveTime)>,
+    daily_capacity: Option<usize>,
+    category_capacities: Vec<(TicketPriority, usize)>,
+}
+
+impl TicketLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // reads the OPENING_HOURS, DAILY_TICKET_CAPACITY and
+    // CATEGORY_TICKET_CAPACITY settings, such as "08:00-18:00", "120" and
+    // "Normal=80,Scheduled=30"
+    pub fn from_config(
+        opening_hours: Option<&str>,
+        daily_capacity: Option<&str>,
+        category_capacities: Option<&str>,
+    ) -> Result<Self> {
+        let mut limits = Self::new();
+
+        if let Some(hours) = opening_hours
+            .map(str::trim)
+            .filter(|hours| !hours.is_empty())
+        {
+            let (opens, closes) = hours
+                .split_once('-')
+                .ok_or_else(|| anyhow!("invalid opening hours: {}", hours))?;
+            let parse = |time: &str| {
+                NaiveTime::parse_from_str(time.trim(), HOURS_FMT)
+                    .map_err(|_| anyhow!("invalid opening hours: {}", hours))
+            };
+            let (opens, closes) = (parse(opens)?, parse(closes)?);
+            if opens >= closes {
+                return Err(anyhow!(
+                    "opening hours must end after they start: {}",
+                    hours
+                ));
+            }
+            limits = limits.with_opening_hours(opens, closes);
+        }
+
+        if let Some(capacity) = daily_capacity
+            .map(str::trim)
+            .filter(|capacity| !capacity.is_empty())
+        {
+            let capacity = capacity
+                .parse()
+                .map_err(|_| anyhow!("invalid daily capacity: {}", capacity))?;
+            limits = limits.with_daily_capacity(capacity);
+        }
+
+        for capacity in category_capacities
+            .unwrap_or_default()
+            .split(',')
+            .filter(|capacity| !capacity.trim().is_empty())
+        {
+            let (category, value) = capacity
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid category capacity: {}", capacity.trim()))?;
+            let priority = TicketPriority::parse(category)
+                .ok_or_else(|| anyhow!("unknown priority category: {}", category.trim()))?;
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid category capacity: {}", capacity.trim()))?;
+            limits = limits.with_category_capacity(priority, value);
+        }
+
+        Ok(limits)
+    }
+
+    pub fn with_opening_hours(mut self, opens: NaiveTime, closes: NaiveTime) -> Self {
+        self.opening_hours = Some((opens, closes));
+        self
+    }
+
+    pub fn with_daily_capacity(mut self, capacity: usize) -> Self {
+        self.daily_capacity = Some(capacity);
+        self
+    }
+
+    pub fn with_category_capacity(mut self, priority: TicketPriority, capacity: usize) -> Self {
+        self.category_capacities
+            .retain(|(category, _)| *category != priority);
+        self.category_capacities.push((priority, capacity));
+        self
+    }
+
+    pub fn opening_hours(&self) -> Option<(NaiveTime, NaiveTime)> {
+        self.opening_hours
+    }
+
+    // `issued_today` holds the category of every ticket issued so far today,
+    // `waiting` how many are in the queue and `minutes_per_call` how long
+    // each of them is expected to take
+    pub fn check(
+        &self,
+        now: NaiveDateTime,
+        priority: TicketPriority,
+        issued_today: &[TicketPriority],
+        waiting: usize,
+        minutes_per_call: f64,
+    ) -> Result<(), Refusal> {
+        if let Some((opens, closes)) = self.opening_hours {
+            if now.time() < opens || now.time() >= closes {
+                return Err(Refusal::Closed { opens, closes });
+            }
+        }
+
+        if let Some(capacity) = self.daily_capacity {
+            if issued_today.len() >= capacity {
+                return Err(Refusal::DailyCapacity(capacity));
+            }
+        }
+
+        if let Some((_, capacity)) = self
+            .category_capacities
+            .iter()
+            .find(|(category, _)| *category == priority)
+        {
+            let issued = issued_today
+                .iter()
+                .filter(|issued| **issued == priority)
+                .count();
+            if issued >= *capacity {
+                return Err(Refusal::CategoryCapacity(priority, *capacity));
+            }
+        }
+
+        // the new ticket is served along with everyone already waiting,
+        // whatever the order they are called in
+        if let Some((_, closes)) = self.opening_hours {
+            let remaining = (waiting + 1) as f64 * minutes_per_call;
+            let estimated_end = now + Duration::seconds((remaining * 60.0).round() as i64);
+            if estimated_end > now.date().and_time(closes) {
+                return Err(Refusal::Cutoff {
+                    estimated_end: estimated_end.time(),
+                    closes,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// the categories of the tickets issued on `date`, as the ticket sequence
+// numbered them, less the cancelled ones since they free their place
+pub fn issued_on(
+    date: NaiveDate,
+    numbered: &[TicketPriority],
+    ticket_history: &[TicketRecord],
+) -> Vec<TicketPriority> {
+    let mut issued = numbered.to_vec();
+
+    for record in ticket_history
+        .iter()
+        .filter(|record| record.outcome() == TicketOutcome::Cancelled)
+        .filter(|record| {
+            record
+                .issued_at()
+                .and_then(parse_default_fmt_to_datetime)
+                .is_some_and(|datetime| datetime.date() == date)
+        })
+    {
+        if let Some(idx) = issued
+            .iter()
+            .position(|priority| *priority == record.priority())
+        {
+            issued.remove(idx);
+        }
+    }
+
+    issued
+}
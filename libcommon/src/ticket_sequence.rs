@@ -0,0 +1,74 @@
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::file_lock::FileLock;
+use crate::priority_queue::TicketPriority;
+
+#[derive(Serialize, Deserialize)]
+struct SequenceState {
+    date: String,
+    last: usize,
+    // the category of every ticket numbered on `date`, wherever it is now;
+    // missing from the files of older versions
+    #[serde(default)]
+    issued: Vec<TicketPriority>,
+}
+
+// ticket numbers shared by every kiosk, starting over at 1 each day
+pub struct TicketSequence {
+    path: String,
+}
+
+impl TicketSequence {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+
+    pub fn next(&self, today: NaiveDate, priority: TicketPriority) -> Result<usize> {
+        let _lock = FileLock::acquire(&self.path)?;
+
+        let mut state = self.read_state(today)?;
+        state.last += 1;
+        state.issued.push(priority);
+
+        // written aside and renamed so a crash never leaves a half written file
+        let tmp_path = format!("{}.tmp", self.path);
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(serde_json::to_string(&state)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(state.last)
+    }
+
+    // the categories of the tickets numbered `today`, including the ones
+    // already called or attended
+    pub fn issued(&self, today: NaiveDate) -> Result<Vec<TicketPriority>> {
+        Ok(self.read_state(today)?.issued)
+    }
+
+    fn read_state(&self, today: NaiveDate) -> Result<SequenceState> {
+        let today = today.format("%Y-%m-%d").to_string();
+
+        match fs::read_to_string(&self.path) {
+            Ok(content) => {
+                let state: SequenceState = serde_json::from_str(&content)?;
+                if state.date == today {
+                    return Ok(state);
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(SequenceState {
+            date: today,
+            last: 0,
+            issued: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use chrono::NaiveDateTime;
+
+use crate::datetime_parsing::parse_default_fmt_to_datetime;
+use crate::i18n;
+use crate::priority_queue::{Priority, PriorityQueue, TicketPriority};
+use crate::ticket_record::TicketRecord;
+
+// assumed before anyone has been called
+pub const DEFAULT_MINUTES_PER_CALL: f64 = 10.0;
+// how many of the latest calls are taken into account
+const SAMPLE_SIZE: usize = 30;
+// longer gaps are breaks, not service time
+const MAX_CALL_INTERVAL_MINUTES: f64 = 60.0;
+// later arrivals that outrank a ticket can't stretch its wait forever
+const MAX_OUTRANKED_SHARE: f64 = 0.75;
+
+pub struct WaitEstimator {
+    minutes_per_call: f64,
+    recent_priorities: Vec<TicketPriority>,
+    // from the first to the last served
+    categories: Vec<TicketPriority>,
+}
+
+impl WaitEstimator {
+    pub fn new(minutes_per_call: f64, recent_priorities: Vec<TicketPriority>) -> Self {
+        Self {
+            minutes_per_call,
+            recent_priorities,
+            categories: TicketPriority::DEFAULT_CATEGORIES.to_vec(),
+        }
+    }
+
+    // the order the queue serves the categories in, as its `categories` gives it
+    pub fn with_categories(mut self, categories: Vec<TicketPriority>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    // `default_minutes_per_call` is used until there are calls to learn from;
+    // each pacient takes their time at the desk, from the call until they
+    // are done, plus the time from finishing with them to calling the next
+    // one, counted only when someone was already waiting then, since before
+    // that the desk was idle rather than slow
+    pub fn from_history(history: &[TicketRecord], default_minutes_per_call: f64) -> Self {
+        // a ticket put back in the queue is counted once, when it is called for good
+        let mut calls: Vec<Call> = history
+            .iter()
+            .filter(|record| record.outcome().is_final())
+            .filter_map(Call::from_record)
+            .collect();
+        calls.sort_by_key(|call| call.called_at);
+
+        let recent_calls = &calls[calls.len().saturating_sub(SAMPLE_SIZE + 1)..];
+
+        let services: Vec<f64> = recent_calls
+            .iter()
+            .filter_map(|call| minutes_between(call.called_at, call.finished_at?))
+            .collect();
+        let changeovers: Vec<f64> = recent_calls
+            .windows(2)
+            .filter(|pair| {
+                matches!(
+                    (pair[1].issued_at, pair[0].finished_at),
+                    (Some(issued_at), Some(finished_at)) if issued_at <= finished_at
+                )
+            })
+            .filter_map(|pair| minutes_between(pair[0].finished_at?, pair[1].called_at))
+            .collect();
+        // older histories have no finish times, only the calls themselves
+        let intervals: Vec<f64> = recent_calls
+            .windows(2)
+            .filter_map(|pair| minutes_between(pair[0].called_at, pair[1].called_at))
+            .collect();
+
+        let minutes_per_call = match (mean(&services), mean(&intervals)) {
+            (Some(service), _) => service + mean(&changeovers).unwrap_or(0.0),
+            (None, Some(interval)) => interval,
+            (None, None) => default_minutes_per_call,
+        };
+
+        Self::new(
+            minutes_per_call,
+            recent_calls.iter().map(|call| call.priority).collect(),
+        )
+    }
+
+    pub fn minutes_per_call(&self) -> f64 {
+        self.minutes_per_call
+    }
+
+    // `ahead` is how many are served before the ticket as the queue stands;
+    // the share of recent calls that outranked its category estimates how
+    // many later arrivals will still pass in front of it
+    pub fn estimate(&self, ahead: usize, priority: TicketPriority) -> i64 {
+        let outranked_share = if self.recent_priorities.is_empty() {
+            0.0
+        } else {
+            // categories no longer served can't have passed in front of it
+            let rank = |priority: TicketPriority| {
+                self.categories
+                    .iter()
+                    .position(|category| *category == priority)
+            };
+            let outranking = self
+                .recent_priorities
+                .iter()
+                .filter(|recent| matches!((rank(**recent), rank(priority)), (Some(a), Some(b)) if a < b))
+                .count();
+            (outranking as f64 / self.recent_priorities.len() as f64).min(MAX_OUTRANKED_SHARE)
+        };
+
+        let minutes = ahead as f64 * self.minutes_per_call / (1.0 - outranked_share);
+        minutes.round() as i64
+    }
+
+    // every element waiting, in serving order, with its estimated wait in minutes
+    pub fn estimate_queue<'a, T: Priority<Key = TicketPriority>>(
+        &self,
+        queue: &'a PriorityQueue<T>,
+    ) -> Vec<(&'a T, i64)> {
+        queue
+            .iter()
+            .enumerate()
+            .map(|(ahead, element)| (element, self.estimate(ahead, element.priority())))
+            .collect()
+    }
+}
+
+struct Call {
+    priority: TicketPriority,
+    issued_at: Option<NaiveDateTime>,
+    called_at: NaiveDateTime,
+    finished_at: Option<NaiveDateTime>,
+}
+
+impl Call {
+    fn from_record(record: &TicketRecord) -> Option<Self> {
+        let lifecycle = record.lifecycle();
+        Some(Self {
+            priority: record.priority(),
+            issued_at: lifecycle
+                .issued_at()
+                .and_then(parse_default_fmt_to_datetime),
+            called_at: parse_default_fmt_to_datetime(lifecycle.called_at()?)?,
+            finished_at: lifecycle
+                .finished_at()
+                .and_then(parse_default_fmt_to_datetime),
+        })
+    }
+}
+
+// gaps across days or longer than an hour are breaks, not service time
+fn minutes_between(start: NaiveDateTime, end: NaiveDateTime) -> Option<f64> {
+    let minutes = (end - start).num_seconds() as f64 / 60.0;
+    (start.date() == end.date() && (0.0..=MAX_CALL_INTERVAL_MINUTES).contains(&minutes))
+        .then_some(minutes)
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+pub fn describe_wait(minutes: i64) -> String {
+    match minutes {
+        minutes if minutes <= 0 => i18n::message("wait.immediate").to_string(),
+        minutes if minutes < 60 => i18n::text("wait.minutes", &[("minutes", &minutes)]),
+        minutes => i18n::text(
+            "wait.hours",
+            &[
+                ("hours", &(minutes / 60)),
+                ("minutes", &format!("{:02}", minutes % 60)),
+            ],
+        ),
+    }
+}
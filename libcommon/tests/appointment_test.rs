@@ -0,0 +1,14 @@
+use chrono::NaiveDate;
+use common::appointment::Appointment;
+
+#[test]
+fn upcoming_appointment_test() {
+    let appointment = |date: &str| Appointment::new("11144477735".parse().unwrap(), date.into());
+    let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+    assert!(appointment("05-03-2024").is_upcoming(today));
+    assert!(appointment("10-04-2024").is_upcoming(today));
+    assert!(!appointment("04-03-2024").is_upcoming(today));
+    assert!(!appointment("sem data").is_upcoming(today));
+    assert_eq!(appointment(" 05-03-2024 ").day(), Some(today));
+}
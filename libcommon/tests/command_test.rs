@@ -0,0 +1,54 @@
+use std::process::ExitCode;
+
+use common::command::{CommandLine, CommandStatus};
+
+fn parse(args: &[&str]) -> CommandLine {
+    CommandLine::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn words_and_options_test() {
+    let command = parse(&[
+        "appointments",
+        "list",
+        "--date",
+        "05-03-2024",
+        "--json",
+        "--output=html",
+    ]);
+
+    assert_eq!(command.words(), vec!["appointments", "list"]);
+    assert_eq!(command.option("--date"), Some("05-03-2024"));
+    assert_eq!(command.option("--output"), Some("html"));
+    assert_eq!(command.option("--priority"), None);
+    assert!(command.flag("--json"));
+    assert!(!command.flag("--tui"));
+    assert!(!command.is_interactive());
+
+    let command = parse(&["cancel", "N002", "--pin", "4821"]);
+    assert_eq!(command.words(), vec!["cancel", "N002"]);
+    assert_eq!(command.option("--pin"), Some("4821"));
+}
+
+#[test]
+fn interactive_test() {
+    // the options of the interactive stations don't make a subcommand
+    let command = parse(&["--output", "json", "--record", "sessao.json", "--tui"]);
+
+    assert!(command.is_interactive());
+    assert_eq!(command.option("--record"), Some("sessao.json"));
+
+    let command = parse(&["ticket", "--priority"]);
+    assert_eq!(command.words(), vec!["ticket"]);
+    assert!(command.flag("--priority"));
+    assert_eq!(command.option("--priority"), None);
+}
+
+#[test]
+fn exit_code_test() {
+    assert_eq!(CommandStatus::Done.code(), 0);
+    assert_eq!(CommandStatus::Usage.code(), 2);
+    assert_eq!(CommandStatus::NotFound.code(), 3);
+    assert_eq!(CommandStatus::Refused.code(), 4);
+    assert_eq!(ExitCode::from(CommandStatus::Done), ExitCode::SUCCESS);
+}
@@ -0,0 +1,64 @@
+use chrono::Local;
+use common::cpf::{Cpf, InvalidCpf};
+use common::pacient_account::{Address, Pacient};
+
+#[test]
+fn parse_test() {
+    let masked = Cpf::parse("123.456.789-09").unwrap();
+    let plain = Cpf::parse(" 123 456 789 09 ").unwrap();
+
+    assert_eq!(masked, plain);
+    assert_eq!(masked.digits(), "12345678909");
+    assert_eq!(Cpf::parse("98765432100").unwrap().digits(), "98765432100");
+
+    // the check digits are wrong
+    assert_eq!(Cpf::parse("123.456.789-00"), Err(InvalidCpf));
+    assert_eq!(Cpf::parse("12345678919"), Err(InvalidCpf));
+    assert_eq!(Cpf::parse("111.111.111-11"), Err(InvalidCpf));
+    assert_eq!(Cpf::parse("1234567890"), Err(InvalidCpf));
+    assert_eq!(Cpf::parse("1234567890a"), Err(InvalidCpf));
+    assert_eq!(Cpf::parse(""), Err(InvalidCpf));
+}
+
+#[test]
+fn display_test() {
+    let cpf: Cpf = "12345678909".parse().unwrap();
+
+    assert_eq!(cpf.to_string(), "123.456.789-09");
+    assert_eq!(cpf.masked(), "123.456.789-09");
+    assert_eq!(
+        InvalidCpf.to_string(),
+        "CPF inválido, confira os 11 dígitos."
+    );
+}
+
+#[test]
+fn serialize_test() {
+    let cpf: Cpf = "123.456.789-09".parse().unwrap();
+
+    assert_eq!(serde_json::to_string(&cpf).unwrap(), "\"12345678909\"");
+    assert_eq!(
+        serde_json::from_str::<Cpf>("\"123.456.789-09\"").unwrap(),
+        cpf
+    );
+
+    // saved before CPFs were checked, kept as it was
+    let legacy = serde_json::from_str::<Cpf>("\"123\"").unwrap();
+    assert!(legacy.is_legacy());
+    assert_eq!(legacy.digits(), "123");
+    assert_eq!(legacy.to_string(), "123");
+    assert_eq!(serde_json::to_string(&legacy).unwrap(), "\"123\"");
+    assert!(!cpf.is_legacy());
+
+    // accounts keep the digits only, and are found by them
+    let pacient = Pacient::new(
+        "Maria".to_string(),
+        cpf,
+        "999".to_string(),
+        "01-01-1990".to_string(),
+        Address::default(),
+        Local::now(),
+    );
+    let json = serde_json::to_value(&pacient).unwrap();
+    assert_eq!(json["cpf"], "12345678909");
+}
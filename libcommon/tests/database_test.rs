@@ -4,13 +4,14 @@ use std::io::{BufReader, Write};
 use anyhow::Result;
 use common::appointment::Appointment;
 use common::database::{Database, GetKeyAttribute};
+use common::pacient_account::Pacient;
 
 #[test]
 fn insert_test() -> Result<()> {
     let db_path = "insert_test.json.db".to_string();
-    let db = Database::new(db_path.clone());
+    let db = Database::new(db_path.clone())?;
 
-    let input = Appointment::new("123".to_string(), "456".to_string());
+    let input = Appointment::new("11144477735".parse().unwrap(), "456".to_string());
 
     db.insert(input.clone())?;
 
@@ -29,13 +30,16 @@ fn insert_test() -> Result<()> {
 fn query_test() -> Result<()> {
     let db_path = "query_test.json.db".to_string();
 
-    let input = vec![Appointment::new("123".to_string(), "456".to_string())];
+    let input = vec![Appointment::new(
+        "11144477735".parse().unwrap(),
+        "456".to_string(),
+    )];
     let serialized = serde_json::to_string_pretty(&input)?;
     let mut file = File::create(&db_path)?;
     file.write_all(serialized.as_bytes())?;
 
-    let db = Database::new(db_path.clone());
-    let output: Appointment = db.query("123")?;
+    let db = Database::new(db_path.clone())?;
+    let output: Appointment = db.query("11144477735")?;
 
     assert_eq!(output, input[0]);
 
@@ -49,16 +53,16 @@ fn query_vec_test() -> Result<()> {
     let db_path = "query_vec_test.json.db".to_string();
 
     let input = vec![
-        Appointment::new("123".to_string(), "456".to_string()),
-        Appointment::new("321".to_string(), "456".to_string()),
-        Appointment::new("123".to_string(), "654".to_string()),
+        Appointment::new("11144477735".parse().unwrap(), "456".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "456".to_string()),
+        Appointment::new("11144477735".parse().unwrap(), "654".to_string()),
     ];
     let serialized = serde_json::to_string_pretty(&input)?;
     let mut file = File::create(&db_path)?;
     file.write_all(serialized.as_bytes())?;
 
-    let db = Database::new(db_path.clone());
-    let result: Vec<Appointment> = db.query_vec("123")?;
+    let db = Database::new(db_path.clone())?;
+    let result: Vec<Appointment> = db.query_vec("11144477735")?;
 
     assert_eq!(result, vec![input[0].clone(), input[2].clone()]);
 
@@ -72,15 +76,15 @@ fn query_all_test() -> Result<()> {
     let db_path = "query_all_test.json.db".to_string();
 
     let input = vec![
-        Appointment::new("123".to_string(), "456".to_string()),
-        Appointment::new("321".to_string(), "456".to_string()),
-        Appointment::new("123".to_string(), "654".to_string()),
+        Appointment::new("11144477735".parse().unwrap(), "456".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "456".to_string()),
+        Appointment::new("11144477735".parse().unwrap(), "654".to_string()),
     ];
     let serialized = serde_json::to_string_pretty(&input)?;
     let mut file = File::create(&db_path)?;
     file.write_all(serialized.as_bytes())?;
 
-    let db = Database::new(db_path.clone());
+    let db = Database::new(db_path.clone())?;
     let result: Vec<Appointment> = db.query_all()?;
 
     assert_eq!(result, input);
@@ -93,12 +97,12 @@ fn query_all_test() -> Result<()> {
 #[test]
 fn update_test() -> Result<()> {
     let db_path = "update_test.json.db".to_string();
-    let db = Database::new(db_path.clone());
+    let db = Database::new(db_path.clone())?;
 
-    let input = Appointment::new("123".to_string(), "456".to_string());
+    let input = Appointment::new("11144477735".parse().unwrap(), "456".to_string());
     db.insert(input.clone())?;
 
-    let updated = Appointment::new("123".to_string(), "654".to_string());
+    let updated = Appointment::new("11144477735".parse().unwrap(), "654".to_string());
     db.update(&input.get_key_attribute(), updated.clone())?;
 
     let file = File::open(&db_path)?;
@@ -115,9 +119,9 @@ fn update_test() -> Result<()> {
 #[test]
 fn delete_test() -> Result<()> {
     let db_path = "delete_test.json.db".to_string();
-    let db = Database::new(db_path.clone());
+    let db = Database::new(db_path.clone())?;
 
-    let input = Appointment::new("123".to_string(), "456".to_string());
+    let input = Appointment::new("11144477735".parse().unwrap(), "456".to_string());
     db.insert(input.clone())?;
 
     let deleted: Appointment = db.delete(&input.get_key_attribute())?;
@@ -129,7 +133,75 @@ fn delete_test() -> Result<()> {
     assert_eq!(deleted, input);
     assert_eq!(output, vec![]);
 
+    // deleting it again says what was looked for
+    let err = db
+        .delete::<Appointment>(&input.get_key_attribute())
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Nenhum registro encontrado para {}.",
+            input.get_key_attribute()
+        )
+    );
+
     fs::remove_file(db_path)?;
 
     Ok(())
 }
+
+#[test]
+fn legacy_cpf_test() -> Result<()> {
+    let db_path = "legacy_cpf_test.json.db".to_string();
+
+    // accounts saved before CPFs were checked, one of them with a CPF that
+    // isn't valid
+    let saved = r#"[
+        {
+            "name": "Maria",
+            "cpf": "123",
+            "phone_number": "999",
+            "date_of_birth": "01-01-1990",
+            "address": { "street": "Rua", "neighborhood": "Bairro", "city": "Cidade" },
+            "date_of_creation": "10:00:00 01-03-2024"
+        },
+        {
+            "name": "João",
+            "cpf": "111.444.777-35",
+            "phone_number": "888",
+            "date_of_birth": "02-02-1980",
+            "address": { "street": "Rua", "neighborhood": "Bairro", "city": "Cidade" },
+            "date_of_creation": "11:00:00 01-03-2024"
+        }
+    ]"#;
+    fs::write(&db_path, saved)?;
+
+    let db = Database::new(db_path.clone())?;
+    let accounts: Vec<Pacient> = db.query_all()?;
+
+    assert!(accounts[0].cpf().is_legacy());
+    assert_eq!(accounts[0].cpf().to_string(), "123");
+    assert!(!accounts[1].cpf().is_legacy());
+    let found: Pacient = db.query("11144477735")?;
+    assert_eq!(found.name(), "João");
+
+    // the legacy CPF is saved back as it was
+    db.update("11144477735", found)?;
+    let accounts: Vec<Pacient> = db.query_all()?;
+    assert_eq!(accounts[0].cpf().digits(), "123");
+
+    fs::remove_file(db_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn create_failed_test() {
+    // the directory of the file doesn't exist
+    let db_path = "create_failed_test/missing/appointments.json.db".to_string();
+
+    let err = Database::new(db_path.clone()).err().unwrap();
+
+    assert!(err.to_string().contains(&db_path));
+}
@@ -0,0 +1,141 @@
+use common::dequeue_policy::{
+    parse_policy, DequeuePolicy, PolicyState, RatioInterleaving, StrictPriority, WeightedFair,
+};
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority};
+
+fn sample_queue(policy: Box<dyn DequeuePolicy>) -> PriorityQueue<PriorityQueueTicket> {
+    let settings = QueueSettings::new(vec![
+        TicketPriority::Elderly60,
+        TicketPriority::Pregnant,
+        TicketPriority::Normal,
+    ])
+    .with_policy(policy);
+    let mut queue = PriorityQueue::with_settings(&settings);
+
+    for code in 1..=4 {
+        queue.enqueue(PriorityQueueTicket::new(code, TicketPriority::Normal));
+    }
+    for code in 5..=8 {
+        queue.enqueue(PriorityQueueTicket::new(code, TicketPriority::Elderly60));
+    }
+
+    queue
+}
+
+fn codes(queue: &PriorityQueue<PriorityQueueTicket>) -> Vec<usize> {
+    queue.queue().iter().map(|ticket| ticket.code()).collect()
+}
+
+#[test]
+fn strict_priority_test() {
+    let queue = sample_queue(Box::new(StrictPriority));
+
+    assert_eq!(codes(&queue), vec![5, 6, 7, 8, 1, 2, 3, 4]);
+}
+
+#[test]
+fn ratio_interleaving_test() {
+    let queue = sample_queue(Box::new(RatioInterleaving::new(2, 1)));
+
+    // once the preferential tickets run out the normal ones are served in a row
+    assert_eq!(codes(&queue), vec![5, 6, 1, 7, 8, 2, 3, 4]);
+}
+
+#[test]
+fn ratio_keeps_turn_between_dequeues_test() {
+    let mut queue = sample_queue(Box::new(RatioInterleaving::new(2, 1)));
+
+    assert_eq!(queue.dequeue().unwrap().code(), 5);
+    assert_eq!(queue.dequeue().unwrap().code(), 6);
+
+    // reloading the contents, as the managers do from the queue file, keeps the turn
+    let waiting: Vec<PriorityQueueTicket> = queue.queue().into_iter().cloned().collect();
+    queue.reload(waiting);
+    assert_eq!(codes(&queue), vec![1, 7, 8, 2, 3, 4]);
+
+    assert_eq!(queue.dequeue().unwrap().code(), 1);
+    assert_eq!(queue.dequeue().unwrap().code(), 7);
+}
+
+#[test]
+fn weighted_fair_test() {
+    let mut policy = WeightedFair::new(vec![
+        (TicketPriority::Emergency, 3),
+        (TicketPriority::Normal, 1),
+    ]);
+    let waiting = [TicketPriority::Emergency, TicketPriority::Normal];
+
+    let served: Vec<usize> = (0..8).map(|_| policy.select(&waiting)).collect();
+
+    assert_eq!(served, vec![0, 0, 1, 0, 0, 0, 1, 0]);
+    assert_eq!(served.iter().filter(|idx| **idx == 0).count(), 6);
+}
+
+#[test]
+fn weighted_fair_queue_test() {
+    let queue = sample_queue(Box::new(WeightedFair::new(vec![(
+        TicketPriority::Elderly60,
+        2,
+    )])));
+
+    assert_eq!(codes(&queue), vec![5, 1, 6, 7, 2, 8, 3, 4]);
+}
+
+#[test]
+fn parse_policy_test() {
+    let mut policy = parse_policy(Some("ratio:1:2")).unwrap();
+    let waiting = [TicketPriority::Pregnant, TicketPriority::Normal];
+    let served: Vec<usize> = (0..6).map(|_| policy.select(&waiting)).collect();
+    assert_eq!(served, vec![0, 1, 1, 0, 1, 1]);
+
+    let mut policy = parse_policy(None).unwrap();
+    assert_eq!(policy.select(&waiting), 0);
+
+    assert!(parse_policy(Some("strict")).is_ok());
+    assert!(parse_policy(Some("weighted:Pregnant=3, Normal=1")).is_ok());
+    assert!(parse_policy(Some("ratio:0:0")).is_err());
+    assert!(parse_policy(Some("ratio:2")).is_err());
+    assert!(parse_policy(Some("weighted:Vip=3")).is_err());
+    assert!(parse_policy(Some("random")).is_err());
+}
+
+#[test]
+fn queue_document_keeps_policy_turn_test() -> anyhow::Result<()> {
+    let mut queue = sample_queue(Box::new(RatioInterleaving::new(2, 1)));
+    assert_eq!(queue.dequeue().unwrap().code(), 5);
+    assert_eq!(queue.dequeue().unwrap().code(), 6);
+
+    let json = serde_json::to_string(&queue)?;
+
+    // a station started afterwards carries on from the saved turn
+    let settings = QueueSettings::default().with_policy(Box::new(RatioInterleaving::new(2, 1)));
+    let mut station: PriorityQueue<PriorityQueueTicket> = PriorityQueue::with_settings(&settings);
+    station.restore(serde_json::from_str(&json)?);
+    assert_eq!(station.dequeue().unwrap().code(), 1);
+    assert_eq!(station.dequeue().unwrap().code(), 7);
+
+    // a state saved by another policy, or other settings, is left aside
+    let settings = QueueSettings::default().with_policy(Box::new(RatioInterleaving::new(3, 1)));
+    let mut station: PriorityQueue<PriorityQueueTicket> = PriorityQueue::with_settings(&settings);
+    station.restore(serde_json::from_str(&json)?);
+    assert_eq!(station.dequeue().unwrap().code(), 7);
+
+    Ok(())
+}
+
+#[test]
+fn policy_state_test() {
+    let mut policy = WeightedFair::new(vec![(TicketPriority::Elderly60, 3)]);
+    policy.select(&[TicketPriority::Elderly60, TicketPriority::Normal]);
+
+    let state = policy.state();
+    assert_eq!(
+        state,
+        PolicyState::Weighted {
+            weights: vec![(TicketPriority::Elderly60, 3)],
+            current: vec![(TicketPriority::Elderly60, -1), (TicketPriority::Normal, 1)],
+        }
+    );
+    assert_eq!(state.policy().state(), state);
+    assert_eq!(StrictPriority.state().policy().state(), PolicyState::Strict);
+}
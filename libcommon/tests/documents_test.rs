@@ -0,0 +1,167 @@
+use std::fs::{self, File};
+use std::io::Write;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use common::documents::{DocumentKind, DocumentPrinter, DocumentTemplate};
+use common::payment::{Payment, PaymentMethod, Procedure};
+use common::pdf_handler::PdfHandler;
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::service_sheet::ServiceSheet;
+
+mod support;
+
+use support::sample_pacient;
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[test]
+fn fill_template_test() {
+    let template = DocumentTemplate::new(
+        "Olá {name}".to_string(),
+        vec![
+            "CPF: {cpf}".to_string(),
+            "{medications}".to_string(),
+            "{unknown}".to_string(),
+        ],
+    );
+
+    let document = template.fill(
+        DocumentKind::Prescription,
+        &[
+            ("name", "Maria".to_string()),
+            ("cpf", "123".to_string()),
+            ("medications", "Dipirona\nIbuprofeno".to_string()),
+        ],
+    );
+
+    assert_eq!(document.kind(), DocumentKind::Prescription);
+    assert_eq!(document.title(), "Olá Maria");
+    assert_eq!(
+        *document.lines(),
+        vec!["CPF: 123", "Dipirona", "Ibuprofeno", "{unknown}"]
+    );
+
+    // what the operator types is never taken for a placeholder
+    let document = template.fill(
+        DocumentKind::Prescription,
+        &[
+            ("name", "{cpf}".to_string()),
+            ("cpf", "123".to_string()),
+            ("medications", "{name} 500mg".to_string()),
+        ],
+    );
+    assert_eq!(document.title(), "Olá {cpf}");
+    assert_eq!(document.lines()[1], "{name} 500mg");
+}
+
+#[test]
+fn load_custom_template_test() -> Result<()> {
+    let dir = "load_custom_template_test";
+    fs::create_dir_all(dir)?;
+
+    let custom = DocumentTemplate::new("Recibo".to_string(), vec!["{amount}".to_string()]);
+    let mut file = File::create(format!("{}/recibo.json", dir))?;
+    file.write_all(serde_json::to_string(&custom)?.as_bytes())?;
+
+    assert_eq!(
+        DocumentTemplate::load(Some(dir), DocumentKind::Receipt)?,
+        custom
+    );
+    assert_eq!(
+        DocumentTemplate::load(Some(dir), DocumentKind::Prescription)?,
+        DocumentTemplate::default_for(DocumentKind::Prescription)
+    );
+    assert_eq!(
+        DocumentTemplate::load(None, DocumentKind::Receipt)?,
+        DocumentTemplate::default_for(DocumentKind::Receipt)
+    );
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn pdf_structure_test() -> Result<()> {
+    let document = DocumentTemplate::default_for(DocumentKind::Receipt).fill(
+        DocumentKind::Receipt,
+        &[("name", "João (Filho)".to_string())],
+    );
+
+    let pdf = PdfHandler::to_pdf(&document)?;
+
+    assert!(pdf.starts_with(b"%PDF-1.4\n"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+    // the font is embedded, with only the glyphs the document uses
+    assert!(contains(&pdf, b"/Subtype /TrueType"));
+    assert!(contains(&pdf, b"+DejaVuSansMono"));
+    assert!(contains(&pdf, b"/FontFile2 5 0 R"));
+    assert!(pdf.len() < 48 * 1024);
+    // accents are written in WinAnsiEncoding and parentheses are escaped
+    assert!(contains(&pdf, b"Jo\\343o \\(Filho\\)"));
+
+    Ok(())
+}
+
+#[test]
+fn pdf_long_document_test() -> Result<()> {
+    let lines: Vec<String> = (0..200).map(|idx| format!("Linha {}", idx)).collect();
+    let document =
+        DocumentTemplate::new("Receita".to_string(), lines).fill(DocumentKind::Prescription, &[]);
+
+    let pdf = PdfHandler::to_pdf(&document)?;
+
+    // 44 lines fit below the title on the first page, 47 on the following ones
+    assert!(contains(&pdf, b"/Count 5 "));
+    assert!(contains(&pdf, b"(Linha 199) Tj"));
+
+    Ok(())
+}
+
+#[test]
+fn printer_test() -> Result<()> {
+    let dir = "printer_test_documents";
+    let printer = DocumentPrinter::new(dir.to_string(), None)?;
+    let datetime = Local.with_ymd_and_hms(2024, 3, 5, 10, 30, 0).unwrap();
+
+    let pacient = sample_pacient("João (Filho)", "11144477735");
+    let payment = Payment::new(
+        "11144477735".parse().unwrap(),
+        Procedure::Filling,
+        PaymentMethod::Pix,
+        datetime,
+    );
+    let sheet = ServiceSheet::new(pacient.clone(), "Dor".to_string(), datetime);
+    let ticket = PriorityQueueTicket::new(7, TicketPriority::High);
+
+    let paths = vec![
+        printer.receipt(&pacient, &payment)?,
+        printer.queue_ticket(&ticket, "cerca de 10 min", datetime)?,
+        printer.attendance_declaration(&sheet, datetime)?,
+        printer.prescription(&pacient, &["Dipirona 500mg".to_string()], datetime)?,
+    ];
+
+    assert!(paths[0].contains("recibo-11144477735-"));
+    assert!(paths[1].contains("senha-P007-"));
+    assert!(paths[2].contains("declaracao-11144477735-"));
+    assert!(paths[3].contains("receita-11144477735-"));
+
+    // a second receipt in the same second doesn't replace the first one
+    let again = printer.receipt(&pacient, &payment)?;
+    let twice = printer.receipt(&pacient, &payment)?;
+    assert_ne!(again, twice);
+    assert!(fs::read(&again)?.starts_with(b"%PDF"));
+
+    for path in paths {
+        assert!(fs::read(path)?.starts_with(b"%PDF"));
+    }
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
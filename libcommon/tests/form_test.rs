@@ -0,0 +1,141 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use common::form::{Form, FormField};
+use common::io_handler::Cancelled;
+use common::pacient_account::Pacient;
+
+mod support;
+
+use support::{output, scripted};
+
+#[derive(Default)]
+struct Contact {
+    name: String,
+    nickname: String,
+}
+
+fn contact_form<'a>() -> Form<'a, Contact> {
+    Form::new()
+        .field(FormField::new(
+            "name",
+            "Nome",
+            |contact: &Contact| contact.name.clone(),
+            |contact, name| contact.name = name,
+        ))
+        .field(
+            FormField::new(
+                "nickname",
+                "Apelido",
+                |contact: &Contact| contact.nickname.clone(),
+                |contact, nickname| contact.nickname = nickname,
+            )
+            .optional(),
+        )
+}
+
+#[test]
+fn field_parse_test() {
+    let form = contact_form();
+    let name = &form.fields()[0];
+    let nickname = &form.fields()[1];
+
+    assert_eq!(name.parse("  Maria "), Ok("Maria".to_string()));
+    assert_eq!(
+        name.parse(" "),
+        Err("Este campo é obrigatório.".to_string())
+    );
+    assert_eq!(nickname.parse(""), Ok(String::new()));
+
+    let form = form.validate("name", |name| {
+        if name.len() > 3 {
+            Ok(())
+        } else {
+            Err("Nome curto.".to_string())
+        }
+    });
+    assert_eq!(
+        form.fields()[0].parse("Ana"),
+        Err("Nome curto.".to_string())
+    );
+}
+
+#[test]
+fn create_test() -> Result<()> {
+    // the name is asked again when left empty, the nickname is fixed during
+    // the review
+    let mut io_handler = scripted("\nMaria\nMari\n1\n2\nMah\n2\n");
+    let mut contact = Contact::default();
+
+    contact_form().create(&mut io_handler, &mut contact)?;
+
+    assert_eq!(contact.name, "Maria");
+    assert_eq!(contact.nickname, "Mah");
+
+    let output = output(&io_handler)?;
+    assert!(output.contains("Nome: Maria\nApelido: Mari\n"));
+    assert!(output.contains("Nome: Maria\nApelido: Mah\n"));
+
+    Ok(())
+}
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+}
+
+#[test]
+fn pacient_form_test() -> Result<()> {
+    // the cpf is given beforehand, it isn't asked again; the birth date is
+    // checked against the given day, not the machine's
+    let mut io_handler = scripted(
+        "Maria\n99999-0000\n31-02-1990\n06-03-2024\n05-03-1990\nRua A\nCentro\nNatal\n2\n",
+    );
+    let mut pacient = Pacient::empty("123.456.789-09".parse()?, Local::now());
+
+    Pacient::form(today()).create(&mut io_handler, &mut pacient)?;
+
+    assert_eq!(pacient.name(), "Maria");
+    assert_eq!(pacient.cpf().digits(), "12345678909");
+    assert_eq!(pacient.date_of_birth(), "05-03-1990");
+    assert_eq!(pacient.street(), "Rua A");
+    assert_eq!(pacient.city(), "Natal");
+    let output = output(&io_handler)?;
+    assert!(output.contains("Data inválida"));
+    assert!(output.contains("A data de nascimento não pode ser no futuro."));
+
+    Ok(())
+}
+
+#[test]
+fn create_cancelled_test() {
+    let mut io_handler = scripted("Maria\ncancelar\n");
+    let mut contact = Contact::default();
+
+    assert!(contact_form()
+        .create(&mut io_handler, &mut contact)
+        .is_err_and(|err| err.is::<Cancelled>()));
+    assert!(contact.nickname.is_empty());
+}
+
+#[test]
+fn edit_test() -> Result<()> {
+    let mut pacient = Pacient::empty("12345678909".parse()?, Local::now());
+    Pacient::form(today()).create(
+        &mut scripted("Mara\n9999\n05-03-1990\nRua A\nCentro\nNatal\n2\n"),
+        &mut pacient,
+    )?;
+
+    // the cpf isn't among the fields that can be corrected, so the second
+    // option is the phone number
+    let mut io_handler = scripted("1\n1\nMaria\n1\n4\nRua B\n2\n");
+    assert!(Pacient::form(today()).edit(&mut io_handler, &mut pacient)?);
+
+    assert_eq!(pacient.name(), "Maria");
+    assert_eq!(pacient.cpf().digits(), "12345678909");
+    assert_eq!(pacient.street(), "Rua B");
+    assert!(!output(&io_handler)?.contains("[2] CPF"));
+
+    let mut io_handler = scripted("1\n0\n2\n");
+    assert!(!Pacient::form(today()).edit(&mut io_handler, &mut pacient)?);
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use common::i18n::{Catalog, Locale, Message};
+
+// the values a message fills in, such as "name" for "{name}"
+fn placeholders(message: &Message) -> BTreeSet<String> {
+    message
+        .forms()
+        .iter()
+        .flat_map(|form| {
+            form.split('{')
+                .skip(1)
+                .filter_map(|rest| rest.split_once('}'))
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+#[test]
+fn locale_test() -> Result<()> {
+    assert_eq!(Locale::from_config(None)?, Locale::PtBr);
+    assert_eq!(Locale::from_config(Some(" "))?, Locale::PtBr);
+    assert_eq!(Locale::from_config(Some("en_us"))?, Locale::EnUs);
+    assert_eq!(Locale::parse("PT-br")?, Locale::PtBr);
+    assert!(Locale::parse("es-ES").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn catalogs_have_the_same_keys_test() -> Result<()> {
+    let default = Catalog::parse(Locale::PtBr.source())?;
+
+    for locale in Locale::ALL {
+        let catalog = Catalog::parse(locale.source())?;
+
+        for key in default.keys() {
+            let expected = default.get(key).unwrap();
+            let message = catalog
+                .get(key)
+                .unwrap_or_else(|| panic!("{} is missing {}", locale.code(), key));
+
+            assert_eq!(
+                placeholders(message),
+                placeholders(expected),
+                "{} fills in other values for {}",
+                locale.code(),
+                key
+            );
+            assert_eq!(
+                matches!(message, Message::Plural { .. }),
+                matches!(expected, Message::Plural { .. }),
+                "{} has other plural forms for {}",
+                locale.code(),
+                key
+            );
+        }
+
+        assert_eq!(
+            catalog.keys(),
+            default.keys(),
+            "{} has keys pt-BR doesn't",
+            locale.code()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn interpolation_test() -> Result<()> {
+    let catalog = Catalog::parse(
+        r#"{
+            "greeting": "Olá, {name}! Senha {code}.",
+            "tickets": { "one": "{count} senha", "other": "{count} senhas" }
+        }"#,
+    )?;
+
+    assert_eq!(
+        catalog.text("greeting", &[("name", &"Maria"), ("code", &"N001")]),
+        "Olá, Maria! Senha N001."
+    );
+    // values are filled in once, the placeholders in them are kept
+    assert_eq!(
+        catalog.text("greeting", &[("name", &"{code}"), ("code", &"N001")]),
+        "Olá, {code}! Senha N001."
+    );
+    assert_eq!(catalog.plural("tickets", 1, &[]), "1 senha");
+    assert_eq!(catalog.plural("tickets", 0, &[]), "0 senhas");
+    assert_eq!(catalog.plural("tickets", 3, &[]), "3 senhas");
+
+    // a missing key is shown as is rather than stopping the station
+    assert_eq!(catalog.message("missing.key"), "missing.key");
+
+    Ok(())
+}
+
+#[test]
+fn bundled_catalogs_test() {
+    let pt_br = Locale::PtBr.catalog();
+    let en_us = Locale::EnUs.catalog();
+
+    assert_eq!(pt_br.message("priority.emergency"), "Emergência");
+    assert_eq!(en_us.message("priority.emergency"), "Emergency");
+    assert_eq!(
+        en_us.plural("reception.exported", 1, &[]),
+        "1 appointment exported\n"
+    );
+    assert_eq!(
+        pt_br.plural("reception.exported", 2, &[]),
+        "2 consultas exportadas\n"
+    );
+    assert_eq!(
+        en_us.text(
+            "refusal.closed",
+            &[("opens", &"08:00"), ("closes", &"18:00")]
+        ),
+        "Tickets are issued from 08:00 to 18:00."
+    );
+}
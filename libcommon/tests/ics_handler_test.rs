@@ -0,0 +1,200 @@
+use std::fs;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use common::appointment::Appointment;
+use common::ics_handler::{ConflictKind, IcsHandler};
+
+mod support;
+
+use support::sample_pacient;
+
+#[test]
+fn export_test() -> Result<()> {
+    let appointments = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "31-12-2024".to_string()),
+    ];
+    let accounts = vec![sample_pacient("Maria, da Silva", "11144477735")];
+    let stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+    let result = IcsHandler::export(&appointments, &accounts, stamp)
+        .calendar()
+        .to_string();
+
+    assert!(result.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(result.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(result.matches("BEGIN:VEVENT").count(), 2);
+    assert!(result.contains("UID:11144477735-20240305@sosdentes\r\n"));
+    assert!(result.contains("DTSTART;VALUE=DATE:20240305\r\n"));
+    assert!(result.contains("DTEND;VALUE=DATE:20250101\r\n"));
+    assert!(result.contains("SUMMARY:Consulta - Maria\\, da Silva\r\n"));
+    // pacients without account fall back to their CPF
+    assert!(result.contains("SUMMARY:Consulta - 222.555.888-46\r\n"));
+
+    Ok(())
+}
+
+#[test]
+fn export_has_stable_uids_test() -> Result<()> {
+    let appointments = vec![Appointment::new(
+        "11144477735".parse().unwrap(),
+        "05-03-2024".to_string(),
+    )];
+    let first_stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let second_stamp = Local.with_ymd_and_hms(2024, 3, 2, 8, 30, 0).unwrap();
+
+    let first = IcsHandler::export(&appointments, &[], first_stamp);
+    let second = IcsHandler::export(&appointments, &[], second_stamp);
+
+    let uid = |calendar: &str| {
+        calendar
+            .lines()
+            .find(|line| line.starts_with("UID:"))
+            .map(str::to_string)
+    };
+
+    assert_eq!(uid(first.calendar()), uid(second.calendar()));
+
+    Ok(())
+}
+
+#[test]
+fn export_folds_long_lines_test() -> Result<()> {
+    let appointments = vec![Appointment::new(
+        "11144477735".parse().unwrap(),
+        "05-03-2024".to_string(),
+    )];
+    let accounts = vec![sample_pacient(&"João ".repeat(30), "11144477735")];
+    let stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+    let result = IcsHandler::export(&appointments, &accounts, stamp)
+        .calendar()
+        .to_string();
+
+    assert!(result.split("\r\n").all(|line| line.len() <= 75));
+
+    let report = IcsHandler::import(&result, &[])?;
+    assert_eq!(report.imported(), &appointments);
+
+    Ok(())
+}
+
+#[test]
+fn export_invalid_date_test() {
+    // free text dates from before they were checked don't stop the export
+    let appointments = vec![
+        Appointment::new("11144477735".parse().unwrap(), "amanhã".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "05-03-2024".to_string()),
+    ];
+    let stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+    let report = IcsHandler::export(&appointments, &[], stamp);
+
+    assert_eq!(report.exported(), 1);
+    assert_eq!(report.skipped(), &vec![appointments[0].clone()]);
+    assert_eq!(report.calendar().matches("BEGIN:VEVENT").count(), 1);
+    assert!(report
+        .calendar()
+        .contains("UID:22255588846-20240305@sosdentes\r\n"));
+}
+
+#[test]
+fn import_round_trip_test() -> Result<()> {
+    let path = "import_round_trip_test.ics";
+    let appointments = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "31-12-2024".to_string()),
+    ];
+    let accounts = vec![sample_pacient("Maria", "11144477735")];
+    let stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+    IcsHandler::save_as_ics(path, &appointments, &accounts, stamp)?;
+    let report = IcsHandler::read_from_ics(path, &[])?;
+
+    assert_eq!(report.imported(), &appointments);
+    assert!(report.conflicts().is_empty());
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn import_from_other_calendar_apps_test() -> Result<()> {
+    let calendar = "BEGIN:VCALENDAR\n\
+        VERSION:2.0\n\
+        BEGIN:VEVENT\n\
+        UID:11144477735-20240305@sosdentes\n\
+        DTSTART:20240305T140000\n\
+        SUMMARY:Consulta\n\
+        END:VEVENT\n\
+        END:VCALENDAR\n";
+
+    let report = IcsHandler::import(calendar, &[])?;
+
+    assert_eq!(
+        report.imported(),
+        &vec![Appointment::new(
+            "11144477735".parse().unwrap(),
+            "05-03-2024".to_string()
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn import_conflicts_test() -> Result<()> {
+    let existing = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "06-03-2024".to_string()),
+    ];
+    let incoming = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "10-03-2024".to_string()),
+        Appointment::new("33366699957".parse().unwrap(), "10-03-2024".to_string()),
+    ];
+    let stamp = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let calendar = IcsHandler::export(&incoming, &[], stamp);
+
+    let report = IcsHandler::import(calendar.calendar(), &existing)?;
+
+    assert_eq!(report.imported(), &vec![incoming[2].clone()]);
+    assert_eq!(report.conflicts().len(), 2);
+
+    assert_eq!(report.conflicts()[0].kind(), ConflictKind::Duplicate);
+    assert_eq!(report.conflicts()[0].existing(), Some(&existing[0]));
+
+    assert_eq!(report.conflicts()[1].kind(), ConflictKind::AlreadyScheduled);
+    assert_eq!(report.conflicts()[1].incoming(), Some(&incoming[1]));
+    assert_eq!(report.conflicts()[1].existing(), Some(&existing[1]));
+
+    Ok(())
+}
+
+#[test]
+fn import_invalid_event_test() -> Result<()> {
+    let calendar = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:qualquer-coisa\r\n\
+        SUMMARY:Reunião\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    let report = IcsHandler::import(calendar, &[])?;
+
+    assert!(report.imported().is_empty());
+    assert_eq!(report.conflicts().len(), 1);
+    assert_eq!(report.conflicts()[0].kind(), ConflictKind::Invalid);
+    assert_eq!(report.conflicts()[0].uid(), "qualquer-coisa");
+
+    Ok(())
+}
+
+#[test]
+fn import_not_a_calendar_test() {
+    let err = IcsHandler::import("[]", &[]).err().unwrap();
+
+    assert_eq!(err.to_string(), "O arquivo não está no formato iCalendar.");
+}
@@ -0,0 +1,35 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use common::io_handler::{stop_at_input_end, Interrupted};
+use common::shutdown::{self, INTERRUPTED_EXIT};
+
+mod support;
+
+use support::{output, scripted};
+
+// the only test of this file, since the interruption is seen by the whole
+// process
+#[test]
+fn interrupt_test() -> Result<()> {
+    let mut io_handler = scripted("1\n2\n");
+    assert_eq!(io_handler.read_line()?, "1\n");
+    assert_eq!(shutdown::exit_code(), ExitCode::SUCCESS);
+
+    shutdown::interrupt();
+
+    // the lines still unread aren't taken for answers
+    let interrupted = io_handler.read_line().map(|_| ());
+    assert!(interrupted
+        .as_ref()
+        .is_err_and(|err| err.is::<Interrupted>()));
+    assert!(io_handler
+        .recover(interrupted)
+        .is_err_and(|err| err.is::<Interrupted>()));
+    assert_eq!(output(&io_handler)?, "");
+    assert!(stop_at_input_end(Err(Interrupted.into())).is_ok());
+
+    assert_eq!(shutdown::exit_code(), ExitCode::from(INTERRUPTED_EXIT));
+
+    Ok(())
+}
@@ -1,7 +1,20 @@
 use std::io::{self, Cursor};
 
+use anyhow::anyhow;
 use anyhow::Result;
-use common::io_handler::IOHandler;
+use chrono::NaiveDate;
+use common::io_handler::{stop_at_input_end, Cancelled, IOHandler, InputEnded, Menu, Prompt};
+
+mod support;
+
+use support::{output, scripted};
+
+fn sample_menu() -> Menu<char> {
+    Menu::new("Escolha: ")
+        .option("Primeira", 'a')
+        .option("Segunda", 'b')
+        .back("Voltar")
+}
 
 #[test]
 fn read_line_test() -> Result<()> {
@@ -28,3 +41,153 @@ fn write_test() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn menu_render_test() {
+    assert_eq!(
+        sample_menu().render(),
+        "\n[1] Primeira\n[2] Segunda\n[0] Voltar\n\nEscolha: "
+    );
+}
+
+#[test]
+fn choose_test() -> Result<()> {
+    let mut io_handler = scripted("3\nabc\n 2 \n0\n");
+    let menu = sample_menu();
+
+    assert_eq!(io_handler.choose(&menu)?, Some('b'));
+    assert_eq!(io_handler.choose(&menu)?, None);
+    assert_eq!(
+        output(&io_handler)?
+            .matches("Opção inválida, tente novamente.")
+            .count(),
+        2
+    );
+
+    // without a back option 0 is just another invalid choice
+    let menu = Menu::new("Escolha: ").option("Primeira", 'a');
+    assert_eq!(menu.parse("0"), None);
+    assert_eq!(menu.parse("1"), Some(Some('a')));
+
+    Ok(())
+}
+
+#[test]
+fn ask_test() -> Result<()> {
+    let mut io_handler = scripted("\nMaria\n123\n123.456.789-00\n123.456.789-09\n");
+
+    assert_eq!(io_handler.ask(&Prompt::text("Nome: "))?, "Maria");
+    assert_eq!(
+        io_handler.ask(&Prompt::cpf("CPF: "))?.digits(),
+        "12345678909"
+    );
+    assert_eq!(
+        Prompt::cpf("CPF: ")
+            .parse(" 123 456 789 09 ")
+            .map(|cpf| cpf.to_string()),
+        Ok("123.456.789-09".to_string())
+    );
+
+    let output = output(&io_handler)?;
+    assert!(output.contains("Este campo é obrigatório."));
+    assert!(output.contains("CPF inválido, confira os 11 dígitos."));
+
+    Ok(())
+}
+
+#[test]
+fn yes_no_test() -> Result<()> {
+    let mut io_handler = scripted("talvez\nS\n2\nnão\n");
+    let prompt = Prompt::yes_no("Confirmar? ");
+
+    assert!(io_handler.ask(&prompt)?);
+    assert!(!io_handler.ask(&prompt)?);
+    assert!(!io_handler.ask(&prompt)?);
+    assert!(output(&io_handler)?.starts_with("\n[1] Sim\n[2] Não\n\nConfirmar? "));
+
+    Ok(())
+}
+
+#[test]
+fn date_test() -> Result<()> {
+    let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+    let prompt = Prompt::date("Data: ")
+        .validate(|date| {
+            if *date >= today {
+                Ok(())
+            } else {
+                Err("A data já passou.".to_string())
+            }
+        })
+        .or_default(today);
+    let mut io_handler = scripted("05/03/2024\n01-03-2024\n10-03-2024\n\n");
+
+    assert_eq!(
+        io_handler.ask(&prompt)?,
+        NaiveDate::from_ymd_opt(2024, 3, 10).unwrap()
+    );
+    assert_eq!(io_handler.ask(&prompt)?, today);
+
+    let output = output(&io_handler)?;
+    assert!(output.contains("Data inválida, use o formato dd-mm-aaaa."));
+    assert!(output.contains("A data já passou."));
+
+    Ok(())
+}
+
+#[test]
+fn input_ended_test() -> Result<()> {
+    let mut io_handler = scripted("abc\n");
+
+    assert!(io_handler
+        .choose(&sample_menu())
+        .is_err_and(|err| err.is::<InputEnded>()));
+    assert!(io_handler
+        .ask(&Prompt::number("Quantidade: "))
+        .is_err_and(|err| err.is::<InputEnded>()));
+    assert!(io_handler
+        .read_line()
+        .is_err_and(|err| err.is::<InputEnded>()));
+
+    // an empty line is still an answer
+    let mut io_handler = scripted("\n");
+    assert_eq!(io_handler.read_line()?, "\n");
+
+    Ok(())
+}
+
+#[test]
+fn cancel_test() -> Result<()> {
+    let mut io_handler = scripted("cancelar\n Cancel \n");
+
+    // only cancellable prompts take it as giving up
+    assert_eq!(io_handler.ask(&Prompt::text("Nome: "))?, "cancelar");
+    assert!(io_handler
+        .ask(&Prompt::cpf("CPF: ").cancellable())
+        .is_err_and(|err| err.is::<Cancelled>()));
+
+    let mut io_handler = scripted("");
+    assert!(io_handler.recover(Err(Cancelled.into())).is_ok());
+    assert_eq!(output(&io_handler)?, "\nOperação cancelada.\n");
+
+    Ok(())
+}
+
+#[test]
+fn recover_test() -> Result<()> {
+    let mut io_handler = scripted("");
+
+    assert!(io_handler.recover(Err(anyhow!("file not found"))).is_ok());
+    assert!(io_handler
+        .recover(Err(InputEnded.into()))
+        .is_err_and(|err| err.is::<InputEnded>()));
+    assert_eq!(
+        output(&io_handler)?,
+        "\nNão foi possível concluir a operação: file not found\nTente novamente.\n"
+    );
+
+    assert!(stop_at_input_end(Err(InputEnded.into())).is_ok());
+    assert!(stop_at_input_end(Err(anyhow!("file not found"))).is_err());
+
+    Ok(())
+}
@@ -22,6 +22,25 @@ fn save_file_as_json_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn overwrite_json_file_test() -> Result<()> {
+    let dir = "overwrite_json_file_test";
+    fs::create_dir_all(dir)?;
+    let path = format!("{}/buff.json", dir);
+
+    JsonHandler::save_as_json(&path, &vec![1, 2, 3])?;
+    JsonHandler::save_as_json(&path, &vec![4, 5])?;
+
+    let result: Vec<u8> = JsonHandler::read_from_json(&path)?;
+    assert_eq!(result, vec![4, 5]);
+    // the file is written aside and renamed, nothing is left behind
+    assert_eq!(fs::read_dir(dir)?.count(), 1);
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
 #[test]
 fn read_json_file_test() -> Result<()> {
     let path = "read_json_file_test.json";
@@ -0,0 +1,106 @@
+use chrono::{Local, TimeZone};
+use common::lifecycle::Lifecycle;
+use common::pacient_account::{Address, Pacient};
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::service_sheet::{ServiceSheet, SheetWithPriority};
+use common::ticket_record::{TicketOutcome, TicketRecord};
+
+fn at(hour: u32, min: u32) -> chrono::DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 3, 5, hour, min, 0).unwrap()
+}
+
+#[test]
+fn lifecycle_durations_test() {
+    let mut lifecycle = Lifecycle::default();
+    assert_eq!(lifecycle.waited_minutes(), None);
+    assert_eq!(lifecycle.service_minutes(), None);
+
+    lifecycle.mark_issued(at(8, 0));
+    lifecycle.mark_called(at(8, 25));
+    lifecycle.mark_started(at(8, 26));
+    lifecycle.mark_finished(at(8, 40));
+
+    assert_eq!(lifecycle.issued_at(), Some("08:00 05-03-2024"));
+    assert_eq!(lifecycle.finished_at(), Some("08:40 05-03-2024"));
+    assert_eq!(lifecycle.waited_minutes(), Some(25));
+    assert_eq!(lifecycle.service_minutes(), Some(14));
+}
+
+#[test]
+fn ticket_record_keeps_lifecycle_test() {
+    let mut ticket = PriorityQueueTicket::new(7, TicketPriority::Pregnant);
+    ticket.set_issued_at(at(8, 0));
+    ticket.lifecycle_mut().mark_called(at(8, 10));
+    ticket.lifecycle_mut().mark_started(at(8, 11));
+    ticket.lifecycle_mut().mark_finished(at(8, 15));
+
+    let record = TicketRecord::new(&ticket);
+
+    assert_eq!(record.code(), 7);
+    assert_eq!(record.priority(), TicketPriority::Pregnant);
+    assert_eq!(record.lifecycle(), ticket.lifecycle());
+    assert_eq!(record.called_at(), Some("08:10 05-03-2024"));
+}
+
+#[test]
+fn lifecycle_is_stored_alongside_the_ticket_test() {
+    let mut ticket = PriorityQueueTicket::new(3, TicketPriority::Normal);
+    ticket.set_issued_at(at(9, 30));
+
+    let json = serde_json::to_value(&ticket).unwrap();
+    assert_eq!(json["issued_at"], "09:30 05-03-2024");
+    assert!(json["called_at"].is_null());
+
+    // files written before the lifecycle existed still load
+    let legacy: PriorityQueueTicket =
+        serde_json::from_str(r#"{"code": 3, "priority": "Normal"}"#).unwrap();
+    assert_eq!(*legacy.lifecycle(), Lifecycle::default());
+}
+
+#[test]
+fn sheet_lifecycle_test() {
+    let pacient = Pacient::new(
+        "Maria".to_string(),
+        "11144477735".parse().unwrap(),
+        "999".to_string(),
+        "01-01-1990".to_string(),
+        Address::new(
+            "Rua".to_string(),
+            "Bairro".to_string(),
+            "Cidade".to_string(),
+        ),
+        Local::now(),
+    );
+    let sheet = ServiceSheet::new(pacient, "Dor".to_string(), at(8, 0));
+    let mut sheet = SheetWithPriority::new(sheet, TicketPriority::Normal);
+    sheet.lifecycle_mut().mark_issued(at(8, 5));
+
+    let json = serde_json::to_string(&sheet).unwrap();
+    let loaded: SheetWithPriority = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(loaded.lifecycle().issued_at(), Some("08:05 05-03-2024"));
+}
+
+#[test]
+fn ticket_outcome_test() {
+    let mut ticket = PriorityQueueTicket::new(4, TicketPriority::Normal);
+    ticket.recall(at(8, 0));
+    ticket.recall(at(8, 2));
+
+    let record = TicketRecord::new(&ticket);
+    assert_eq!(record.outcome(), TicketOutcome::Attended);
+    assert_eq!(record.recalls(), 2);
+
+    let record = record.with_outcome(TicketOutcome::NoShow);
+    let json = serde_json::to_string(&record).unwrap();
+    let loaded: TicketRecord = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.outcome(), TicketOutcome::NoShow);
+
+    // records written before outcomes existed were all attended
+    let legacy: TicketRecord = serde_json::from_str(
+        r#"{"code": 1, "priority": "Normal", "called_at": "08:00 05-03-2024"}"#,
+    )
+    .unwrap();
+    assert_eq!(legacy.outcome(), TicketOutcome::Attended);
+    assert_eq!(legacy.recalls(), 0);
+}
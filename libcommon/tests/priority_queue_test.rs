@@ -1,6 +1,19 @@
+// the statements below are kept as the upstream tests wrote them
+#![allow(clippy::let_unit_value, clippy::single_component_path_imports)]
+
+use std::rc::Rc;
+
 use rand;
 
-use common::priority_queue::{PriorityQueue, PriorityQueueTicket, TicketPriority};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use common::appointment::Appointment;
+use common::clock::ManualClock;
+use common::priority_queue::{
+    Priority, PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 
 #[test]
 fn taking_normal_priority_tickets_10_tickets() {
@@ -15,8 +28,8 @@ fn taking_normal_priority_tickets_10_tickets() {
         expected_queue.push(ticket);
     }
 
-    assert_eq!(*actual_queue.normal_priority_queue(), expected_queue);
-    assert!(actual_queue.high_priority_queue().is_empty());
+    assert_eq!(*actual_queue.level(TicketPriority::Normal), expected_queue);
+    assert!(actual_queue.level(TicketPriority::High).is_empty());
 }
 
 #[test]
@@ -32,8 +45,8 @@ fn taking_normal_priority_tickets_255_tickets() {
         expected_queue.push(ticket);
     }
 
-    assert_eq!(*actual_queue.normal_priority_queue(), expected_queue);
-    assert!(actual_queue.high_priority_queue().is_empty());
+    assert_eq!(*actual_queue.level(TicketPriority::Normal), expected_queue);
+    assert!(actual_queue.level(TicketPriority::High).is_empty());
 }
 
 #[test]
@@ -49,8 +62,8 @@ fn taking_high_priority_tickets_10_tickets() {
         expected_queue.push(ticket);
     }
 
-    assert_eq!(*actual_queue.high_priority_queue(), expected_queue);
-    assert!(actual_queue.normal_priority_queue().is_empty());
+    assert_eq!(*actual_queue.level(TicketPriority::High), expected_queue);
+    assert!(actual_queue.level(TicketPriority::Normal).is_empty());
 }
 
 #[test]
@@ -66,8 +79,8 @@ fn taking_high_priority_tickets_255_tickets() {
         expected_queue.push(ticket);
     }
 
-    assert_eq!(*actual_queue.high_priority_queue(), expected_queue);
-    assert!(actual_queue.normal_priority_queue().is_empty());
+    assert_eq!(*actual_queue.level(TicketPriority::High), expected_queue);
+    assert!(actual_queue.level(TicketPriority::Normal).is_empty());
 }
 
 #[test]
@@ -91,11 +104,11 @@ fn taking_arbitrary_priority_tickets_5_tickets() {
     ];
 
     assert_eq!(
-        *actual_queue.high_priority_queue(),
+        *actual_queue.level(TicketPriority::High),
         expected_high_priority_queue
     );
     assert_eq!(
-        *actual_queue.normal_priority_queue(),
+        *actual_queue.level(TicketPriority::Normal),
         expected_normal_priority_queue
     );
 
@@ -126,15 +139,16 @@ fn taking_arbitrary_priority_tickets_255_tickets() {
         match priority {
             TicketPriority::Normal => expected_normal_priority_queue.push(ticket.clone()),
             TicketPriority::High => expected_high_priority_queue.push(ticket.clone()),
+            _ => unreachable!(),
         }
     }
 
     assert_eq!(
-        *actual_queue.normal_priority_queue(),
+        *actual_queue.level(TicketPriority::Normal),
         expected_normal_priority_queue
     );
     assert_eq!(
-        *actual_queue.high_priority_queue(),
+        *actual_queue.level(TicketPriority::High),
         expected_high_priority_queue
     );
 
@@ -167,11 +181,11 @@ fn convertion_from_vec_test() {
     let actual_queue = PriorityQueue::from(sample);
 
     assert_eq!(
-        *actual_queue.high_priority_queue(),
+        *actual_queue.level(TicketPriority::High),
         expected_high_priority_queue
     );
     assert_eq!(
-        *actual_queue.normal_priority_queue(),
+        *actual_queue.level(TicketPriority::Normal),
         expected_normal_priority_queue
     );
 
@@ -180,3 +194,450 @@ fn convertion_from_vec_test() {
 
     assert_eq!(*actual_queue.queue(), expected_queue);
 }
+
+#[test]
+fn multi_level_priority_order_test() {
+    let mut actual_queue = PriorityQueue::new();
+
+    actual_queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Normal));
+    actual_queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Elderly60));
+    actual_queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Scheduled));
+    actual_queue.enqueue(PriorityQueueTicket::new(4, TicketPriority::Emergency));
+    actual_queue.enqueue(PriorityQueueTicket::new(5, TicketPriority::Elderly80));
+    actual_queue.enqueue(PriorityQueueTicket::new(6, TicketPriority::Elderly60));
+
+    let codes: Vec<usize> = actual_queue
+        .queue()
+        .iter()
+        .map(|ticket| ticket.code())
+        .collect();
+
+    assert_eq!(codes, vec![4, 5, 2, 6, 3, 1]);
+}
+
+#[test]
+fn configured_categories_test() {
+    let categories = TicketPriority::categories(Some("Pregnant, normal,Emergency")).unwrap();
+    assert_eq!(
+        categories,
+        vec![
+            TicketPriority::Pregnant,
+            TicketPriority::Normal,
+            TicketPriority::Emergency
+        ]
+    );
+
+    let mut actual_queue = PriorityQueue::with_categories(categories);
+    actual_queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Emergency));
+    actual_queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Normal));
+    actual_queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Pregnant));
+    // not configured, so it is served right above the first category it outranks
+    actual_queue.enqueue(PriorityQueueTicket::new(4, TicketPriority::Scheduled));
+
+    let codes: Vec<usize> = actual_queue
+        .queue()
+        .iter()
+        .map(|ticket| ticket.code())
+        .collect();
+
+    assert_eq!(codes, vec![3, 4, 2, 1]);
+    assert_eq!(
+        actual_queue.categories(),
+        vec![
+            TicketPriority::Pregnant,
+            TicketPriority::Scheduled,
+            TicketPriority::Normal,
+            TicketPriority::Emergency
+        ]
+    );
+}
+
+#[test]
+fn default_and_invalid_categories_test() {
+    assert_eq!(
+        TicketPriority::categories(None).unwrap(),
+        TicketPriority::DEFAULT_CATEGORIES
+    );
+    assert_eq!(
+        TicketPriority::categories(Some("  ")).unwrap(),
+        TicketPriority::DEFAULT_CATEGORIES
+    );
+    assert!(TicketPriority::categories(Some("Normal,Vip")).is_err());
+}
+
+#[test]
+fn legacy_two_level_file_test() {
+    let legacy = r#"[
+        {"code": 3, "priority": "High"},
+        {"code": 1, "priority": "Normal"},
+        {"code": 5, "priority": "High"}
+    ]"#;
+    let tickets: Vec<PriorityQueueTicket> = serde_json::from_str(legacy).unwrap();
+
+    let mut actual_queue = PriorityQueue::from(tickets);
+    actual_queue.enqueue(PriorityQueueTicket::new(6, TicketPriority::Disability));
+    actual_queue.enqueue(PriorityQueueTicket::new(7, TicketPriority::Scheduled));
+
+    let codes: Vec<usize> = actual_queue
+        .queue()
+        .iter()
+        .map(|ticket| ticket.code())
+        .collect();
+
+    assert_eq!(codes, vec![6, 3, 5, 7, 1]);
+}
+
+fn ticket_issued_at(
+    code: usize,
+    priority: TicketPriority,
+    hour: u32,
+    min: u32,
+) -> PriorityQueueTicket {
+    let mut ticket = PriorityQueueTicket::new(code, priority);
+    ticket.set_issued_at(Local.with_ymd_and_hms(2024, 3, 5, hour, min, 0).unwrap());
+    ticket
+}
+
+fn at(hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, 5)
+        .unwrap()
+        .and_hms_opt(hour, min, 0)
+        .unwrap()
+}
+
+fn codes(queue: &PriorityQueue<PriorityQueueTicket>) -> Vec<usize> {
+    queue.queue().iter().map(|ticket| ticket.code()).collect()
+}
+
+#[test]
+fn aging_raises_waiting_tickets_test() {
+    let clock = ManualClock::new(at(8, 0));
+    let settings = QueueSettings::new(vec![
+        TicketPriority::Emergency,
+        TicketPriority::Elderly60,
+        TicketPriority::Normal,
+    ])
+    .with_aging(Some(Duration::minutes(30)))
+    .with_clock(Rc::new(clock.clone()));
+
+    let mut queue = PriorityQueue::with_settings(&settings);
+    queue.enqueue(ticket_issued_at(1, TicketPriority::Normal, 8, 0));
+    queue.enqueue(ticket_issued_at(2, TicketPriority::Elderly60, 8, 10));
+    queue.enqueue(ticket_issued_at(3, TicketPriority::Emergency, 8, 20));
+
+    clock.set(at(8, 20));
+    assert_eq!(codes(&queue), vec![3, 2, 1]);
+
+    // the normal ticket reaches the elderly level and arrived before ticket 2
+    clock.set(at(8, 30));
+    assert_eq!(codes(&queue), vec![3, 1, 2]);
+
+    // after an hour everyone reached the top level and arrival order decides
+    clock.advance(Duration::minutes(30));
+    assert_eq!(codes(&queue), vec![1, 2, 3]);
+}
+
+#[test]
+fn aging_uses_clock_for_untracked_elements_test() {
+    let clock = ManualClock::new(at(8, 0));
+    let settings = QueueSettings::new(vec![TicketPriority::Elderly60, TicketPriority::Normal])
+        .with_aging(Some(Duration::minutes(15)))
+        .with_clock(Rc::new(clock.clone()));
+
+    let mut queue = PriorityQueue::with_settings(&settings);
+    queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Normal));
+    clock.advance(Duration::minutes(5));
+    queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Elderly60));
+
+    clock.advance(Duration::minutes(9));
+    assert_eq!(codes(&queue), vec![2, 1]);
+
+    clock.advance(Duration::minutes(1));
+    assert_eq!(codes(&queue), vec![1, 2]);
+}
+
+#[test]
+fn short_aging_steps_test() {
+    let clock = ManualClock::new(at(8, 0));
+    let aged_queue = |step| {
+        let settings = QueueSettings::new(vec![TicketPriority::Elderly60, TicketPriority::Normal])
+            .with_aging(Some(step))
+            .with_clock(Rc::new(clock.clone()));
+        let mut queue = PriorityQueue::with_settings(&settings);
+        queue.enqueue(ticket_issued_at(1, TicketPriority::Normal, 7, 59));
+        queue.enqueue(ticket_issued_at(2, TicketPriority::Elderly60, 8, 0));
+        queue
+    };
+    clock.set(at(8, 1));
+
+    // steps of less than a second still age tickets
+    assert_eq!(codes(&aged_queue(Duration::milliseconds(500))), vec![1, 2]);
+    // shorter than a millisecond, or not positive, they age nothing
+    assert_eq!(codes(&aged_queue(Duration::microseconds(10))), vec![2, 1]);
+    assert_eq!(codes(&aged_queue(Duration::zero())), vec![2, 1]);
+    assert_eq!(codes(&aged_queue(Duration::minutes(-5))), vec![2, 1]);
+}
+
+#[test]
+fn no_aging_keeps_strict_order_test() {
+    let clock = ManualClock::new(at(8, 0));
+    let settings = QueueSettings::new(vec![TicketPriority::Elderly60, TicketPriority::Normal])
+        .with_clock(Rc::new(clock.clone()));
+
+    let mut queue = PriorityQueue::with_settings(&settings);
+    queue.enqueue(ticket_issued_at(1, TicketPriority::Normal, 8, 0));
+    queue.enqueue(ticket_issued_at(2, TicketPriority::Elderly60, 8, 0));
+
+    clock.advance(Duration::hours(5));
+    assert_eq!(codes(&queue), vec![2, 1]);
+}
+
+#[test]
+fn aging_config_test() {
+    let settings = QueueSettings::from_config(Some("Pregnant,Normal"), Some("20")).unwrap();
+    assert_eq!(
+        *settings.categories(),
+        vec![TicketPriority::Pregnant, TicketPriority::Normal]
+    );
+    assert_eq!(settings.aging_step(), Some(Duration::minutes(20)));
+
+    assert_eq!(
+        QueueSettings::from_config(None, Some(""))
+            .unwrap()
+            .aging_step(),
+        None
+    );
+    assert!(QueueSettings::from_config(None, Some("0")).is_err());
+    assert!(QueueSettings::from_config(None, Some("soon")).is_err());
+}
+
+#[test]
+fn dequeue_and_peek_test() {
+    let mut queue = PriorityQueue::new();
+    assert!(queue.peek().is_none());
+    assert!(queue.dequeue().is_none());
+
+    queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Normal));
+    queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Pregnant));
+    queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Normal));
+    assert_eq!(queue.len(), 3);
+
+    assert_eq!(queue.peek().unwrap().code(), 2);
+    assert_eq!(queue.len(), 3);
+
+    let served: Vec<usize> = std::iter::from_fn(|| queue.dequeue())
+        .map(|ticket| ticket.code())
+        .collect();
+    assert_eq!(served, vec![2, 1, 3]);
+    assert!(queue.is_empty());
+    assert_eq!(queue.len(), 0);
+}
+
+#[test]
+fn position_and_remove_by_test() {
+    let mut queue = PriorityQueue::new();
+    queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Normal));
+    queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Normal));
+    queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Elderly80));
+
+    assert_eq!(queue.position_of(|ticket| ticket.code() == 3), Some(0));
+    assert_eq!(queue.position_of(|ticket| ticket.code() == 2), Some(2));
+    assert_eq!(queue.position_of(|ticket| ticket.code() == 9), None);
+
+    assert_eq!(
+        queue.remove_by(|ticket| ticket.code() == 1).unwrap().code(),
+        1
+    );
+    assert!(queue.remove_by(|ticket| ticket.code() == 1).is_none());
+    assert_eq!(queue.position_of(|ticket| ticket.code() == 2), Some(1));
+
+    let codes: Vec<usize> = queue.iter().map(|ticket| ticket.code()).collect();
+    assert_eq!(codes, vec![3, 2]);
+
+    let mut codes = Vec::new();
+    for ticket in &queue {
+        codes.push(ticket.code());
+    }
+    assert_eq!(codes, vec![3, 2]);
+}
+
+#[test]
+fn requeue_restores_position_test() {
+    let mut queue = PriorityQueue::new();
+    queue.enqueue(ticket_issued_at(1, TicketPriority::Normal, 8, 0));
+    queue.enqueue(ticket_issued_at(2, TicketPriority::Normal, 8, 5));
+    queue.enqueue(ticket_issued_at(3, TicketPriority::Normal, 8, 10));
+    queue.enqueue(ticket_issued_at(4, TicketPriority::Pregnant, 8, 15));
+
+    let first = queue.dequeue().unwrap();
+    let second = queue.dequeue().unwrap();
+    assert_eq!((first.code(), second.code()), (4, 1));
+
+    queue.requeue(second);
+    assert_eq!(codes(&queue), vec![1, 2, 3]);
+
+    queue.requeue(first);
+    assert_eq!(codes(&queue), vec![4, 1, 2, 3]);
+}
+
+#[test]
+fn recall_test() {
+    let mut ticket = ticket_issued_at(1, TicketPriority::Normal, 8, 0);
+    ticket
+        .lifecycle_mut()
+        .mark_called(Local.with_ymd_and_hms(2024, 3, 5, 8, 30, 0).unwrap());
+
+    ticket.recall(Local.with_ymd_and_hms(2024, 3, 5, 8, 32, 0).unwrap());
+
+    assert_eq!(ticket.recalls(), 1);
+    assert_eq!(ticket.lifecycle().called_at(), Some("08:32 05-03-2024"));
+}
+
+#[test]
+fn queue_document_test() -> serde_json::Result<()> {
+    let mut queue = PriorityQueue::new();
+    queue.enqueue(ticket_issued_at(1, TicketPriority::Normal, 8, 0));
+    queue.enqueue(ticket_issued_at(2, TicketPriority::Pregnant, 8, 5));
+    queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Normal));
+    queue.dequeue();
+
+    let json = serde_json::to_value(&queue)?;
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["sequence"], 3);
+    assert_eq!(json["policy"]["kind"], "strict");
+
+    let loaded: PriorityQueue<PriorityQueueTicket> = serde_json::from_value(json)?;
+    assert_eq!(codes(&loaded), vec![1, 3]);
+    assert_eq!(loaded.sequence(), 3);
+    assert_eq!(loaded.categories(), queue.categories());
+
+    let unsupported =
+        r#"{"version": 2, "sequence": 0, "policy": {"kind": "strict"}, "levels": []}"#;
+    assert!(serde_json::from_str::<PriorityQueue<PriorityQueueTicket>>(unsupported).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn legacy_flat_queue_file_test() -> serde_json::Result<()> {
+    let legacy = r#"[
+        {"code": 1, "priority": "Normal"},
+        {"code": 2, "priority": "High"}
+    ]"#;
+
+    let loaded: PriorityQueue<PriorityQueueTicket> = serde_json::from_str(legacy)?;
+    assert_eq!(codes(&loaded), vec![2, 1]);
+
+    // the settings of the station are kept over the ones read
+    let settings = QueueSettings::new(vec![TicketPriority::Normal, TicketPriority::High]);
+    let mut queue = PriorityQueue::with_settings(&settings);
+    queue.restore(loaded);
+    assert_eq!(codes(&queue), vec![1, 2]);
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Job {
+    code: usize,
+    urgency: u32,
+}
+
+impl Priority for Job {
+    type Key = u32;
+
+    fn priority(&self) -> u32 {
+        self.urgency
+    }
+}
+
+fn job(code: usize, urgency: u32) -> Job {
+    Job { code, urgency }
+}
+
+#[test]
+fn greatest_key_first_test() {
+    let mut queue = PriorityQueue::new();
+    queue.enqueue(job(1, 1));
+    queue.enqueue(job(2, 5));
+    queue.enqueue(job(3, 3));
+
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.peek(), Some(&job(2, 5)));
+    assert_eq!(queue.dequeue(), Some(job(2, 5)));
+    assert_eq!(queue.dequeue(), Some(job(3, 3)));
+    assert_eq!(queue.dequeue(), Some(job(1, 1)));
+    assert_eq!(queue.dequeue(), None);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn equal_keys_keep_arrival_order_test() {
+    let mut rng = StdRng::seed_from_u64(40);
+    let mut urgencies: Vec<u32> = (0..200).map(|idx| idx % 4).collect();
+    urgencies.shuffle(&mut rng);
+
+    let queue: PriorityQueue<Job> = urgencies
+        .iter()
+        .enumerate()
+        .map(|(code, urgency)| job(code, *urgency))
+        .collect();
+
+    let order: Vec<Job> = queue.queue().into_iter().copied().collect();
+    assert_eq!(queue.level(3).len(), 50);
+    let served = queue.into_vec();
+    assert_eq!(order, served);
+
+    for pair in served.windows(2) {
+        assert!(pair[0].urgency >= pair[1].urgency);
+        if pair[0].urgency == pair[1].urgency {
+            assert!(pair[0].code < pair[1].code);
+        }
+    }
+}
+
+#[test]
+fn categories_of_any_key_test() {
+    let mut queue = PriorityQueue::with_categories(vec![1, 7]);
+    queue.enqueue(job(1, 7));
+    queue.enqueue(job(2, 1));
+    queue.enqueue(job(3, 4));
+    queue.enqueue(job(4, 9));
+
+    // keys left out are served right above the first configured one they outrank
+    assert_eq!(queue.categories(), vec![9, 4, 1, 7]);
+    let codes: Vec<usize> = queue.into_vec().iter().map(|job| job.code).collect();
+    assert_eq!(codes, vec![4, 3, 2, 1]);
+}
+
+#[test]
+fn appointments_by_date_test() {
+    let appointment =
+        |cpf: &str, date: &str| Appointment::new(cpf.parse().unwrap(), date.to_string());
+
+    let queue: PriorityQueue<Appointment> = vec![
+        appointment("11144477735", "10-03-2024"),
+        appointment("22255588846", "sem data"),
+        appointment("33366699957", "05-03-2024"),
+        appointment("44477700083", "01-04-2024"),
+        appointment("55588811194", "05-03-2024"),
+    ]
+    .into_iter()
+    .collect();
+
+    let cpfs: Vec<String> = queue
+        .into_vec()
+        .iter()
+        .map(|appointment| appointment.cpf().digits().to_string())
+        .collect();
+    assert_eq!(
+        cpfs,
+        vec![
+            "33366699957",
+            "55588811194",
+            "11144477735",
+            "44477700083",
+            "22255588846"
+        ]
+    );
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use common::cpf::Cpf;
+use common::pacient_account::{Address, Pacient};
+use common::payment::Procedure;
+use common::priority_queue::{QueueSettings, TicketPriority};
+use common::queue_router::{QueueRouter, RouteCriterion, RoutingRule};
+use common::service_sheet::{ServiceSheet, SheetWithPriority};
+
+// the pacients of these tests, known by their place in the list
+const CPFS: [&str; 5] = [
+    "11144477735",
+    "22255588846",
+    "33366699957",
+    "44477700083",
+    "55588811194",
+];
+
+fn cpf(label: &str) -> Cpf {
+    CPFS[label.parse::<usize>().unwrap() - 1].parse().unwrap()
+}
+
+fn label(cpf: &Cpf) -> String {
+    let idx = CPFS
+        .iter()
+        .position(|digits| *digits == cpf.digits())
+        .unwrap();
+    (idx + 1).to_string()
+}
+
+fn sample_sheet(label: &str, reason: &str, min: u32) -> ServiceSheet {
+    let pacient = Pacient::new(
+        "Maria".to_string(),
+        cpf(label),
+        "999".to_string(),
+        "01-01-1990".to_string(),
+        Address::new(
+            "Rua".to_string(),
+            "Bairro".to_string(),
+            "Cidade".to_string(),
+        ),
+        Local::now(),
+    );
+    let datetime = Local.with_ymd_and_hms(2024, 3, 5, 8, min, 0).unwrap();
+    ServiceSheet::new(pacient, reason.to_string(), datetime)
+}
+
+fn with_priority(sheet: ServiceSheet) -> SheetWithPriority {
+    SheetWithPriority::new(sheet, TicketPriority::Normal)
+}
+
+// the queue files of a test, in the directory named after it
+fn queues_config(test: &str) -> String {
+    format!(
+        "geral={test}/geral.json, ortodontia={test}/ortodontia.json, limpeza={test}/limpeza.json"
+    )
+}
+
+fn sample_router(test: &str) -> Result<QueueRouter> {
+    fs::create_dir_all(test)?;
+    QueueRouter::from_config(
+        &queues_config(test),
+        Some("reason:aparelho=ortodontia,dentist:Ana=ortodontia,procedure:Cleaning=limpeza"),
+        &QueueSettings::default(),
+    )
+}
+
+fn cpfs(router: &QueueRouter, name: &str) -> Result<Vec<String>> {
+    Ok(router
+        .queue(name)?
+        .iter()
+        .map(|sheet| label(sheet.service_sheet().pacient_account().cpf()))
+        .collect())
+}
+
+#[test]
+fn config_test() -> Result<()> {
+    let dir = "config_test";
+    let router = sample_router(dir)?;
+
+    assert_eq!(router.names(), vec!["geral", "ortodontia", "limpeza"]);
+    assert_eq!(
+        router.rules()[2],
+        RoutingRule::new(
+            RouteCriterion::Procedure(Procedure::Cleaning),
+            "limpeza".to_string()
+        )
+    );
+
+    let settings = QueueSettings::default();
+    assert!(QueueRouter::from_config("", None, &settings).is_err());
+    assert!(QueueRouter::from_config("geral", None, &settings).is_err());
+    assert!(QueueRouter::from_config("a=a.json,a=b.json", None, &settings).is_err());
+    assert!(QueueRouter::from_config("a=a.json", Some("reason:dor=b"), &settings).is_err());
+    assert!(QueueRouter::from_config("a=a.json", Some("color:red=a"), &settings).is_err());
+    assert!(QueueRouter::from_config("a=a.json", Some("procedure:Surgery=a"), &settings).is_err());
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn single_queue_config_test() -> Result<()> {
+    // setups from before named queues only have DENTIST_QUEUE_FILE_PATH
+    assert_eq!(
+        QueueRouter::queues_config(None, Some("dentist_queue.json"))?,
+        "geral=dentist_queue.json"
+    );
+    assert_eq!(
+        QueueRouter::queues_config(Some(" "), Some("dentist_queue.json"))?,
+        "geral=dentist_queue.json"
+    );
+    assert_eq!(
+        QueueRouter::queues_config(Some("a=a.json"), Some("dentist_queue.json"))?,
+        "a=a.json"
+    );
+    assert!(QueueRouter::queues_config(None, None).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn route_test() -> Result<()> {
+    let dir = "route_test";
+    let router = sample_router(dir)?;
+
+    let by_reason = with_priority(sample_sheet("1", "Ajustar o APARELHO", 0));
+    assert_eq!(router.route(&by_reason), "ortodontia");
+
+    let by_dentist =
+        with_priority(sample_sheet("2", "Dor de dente", 0).with_dentist(Some("ana".to_string())));
+    assert_eq!(router.route(&by_dentist), "ortodontia");
+
+    let by_procedure = with_priority(
+        sample_sheet("3", "Dentes amarelados", 0).with_procedure(Some(Procedure::Cleaning)),
+    );
+    assert_eq!(router.route(&by_procedure), "limpeza");
+
+    // the first rule that matches wins
+    let both =
+        with_priority(sample_sheet("4", "Aparelho", 0).with_procedure(Some(Procedure::Cleaning)));
+    assert_eq!(router.route(&both), "ortodontia");
+
+    let unmatched = with_priority(sample_sheet("5", "Dor de dente", 0));
+    assert_eq!(router.route(&unmatched), "geral");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn enqueue_and_dequeue_test() -> Result<()> {
+    let dir = "enqueue_and_dequeue_test";
+    let mut router = sample_router(dir)?;
+
+    assert_eq!(
+        router.enqueue(with_priority(sample_sheet("1", "Aparelho", 0)))?,
+        "ortodontia"
+    );
+    router.enqueue(with_priority(sample_sheet("2", "Dor", 5)))?;
+    router.enqueue(with_priority(sample_sheet("3", "Aparelho", 10)))?;
+    assert!(Path::new("enqueue_and_dequeue_test/ortodontia.json").exists());
+
+    // another station sees the queues through their files
+    let mut station =
+        QueueRouter::from_config(&queues_config(dir), None, &QueueSettings::default())?;
+    station.refresh()?;
+    assert_eq!(cpfs(&station, "ortodontia")?, vec!["1", "3"]);
+    let sheet = station.dequeue("ortodontia")?.unwrap();
+    assert_eq!(*sheet.service_sheet().pacient_account().cpf(), cpf("1"));
+    assert!(station.dequeue("limpeza")?.is_none());
+    assert!(station.dequeue("cirurgia").is_err());
+
+    router.dequeue("geral")?;
+    assert_eq!(cpfs(&router, "geral")?, Vec::<String>::new());
+    assert_eq!(router.find(&cpf("3"))?, Some("ortodontia".to_string()));
+    assert_eq!(router.find(&cpf("1"))?, None);
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn transfer_test() -> Result<()> {
+    let dir = "transfer_test";
+    let mut router = sample_router(dir)?;
+
+    router.enqueue(with_priority(sample_sheet("1", "Dor", 0)))?;
+    router.enqueue(with_priority(sample_sheet("2", "Aparelho", 5)))?;
+    router.enqueue(with_priority(sample_sheet("3", "Dor", 10)))?;
+    router.enqueue(with_priority(sample_sheet("4", "Aparelho", 15)))?;
+
+    // the transferred pacient keeps the place its arrival time gives it
+    assert!(router.transfer(&cpf("3"), "geral", "ortodontia")?);
+    assert_eq!(cpfs(&router, "geral")?, vec!["1"]);
+    assert_eq!(cpfs(&router, "ortodontia")?, vec!["2", "3", "4"]);
+
+    assert!(!router.transfer(&cpf("3"), "geral", "ortodontia")?);
+    assert!(router.transfer(&cpf("1"), "geral", "cirurgia").is_err());
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use chrono::Local;
+use common::appointment::Appointment;
+use common::payment::{Payment, PaymentMethod, Procedure};
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::render::{OutputFormat, Render};
+use common::service_sheet::{ServiceSheet, SheetWithPriority};
+use common::ticket_record::TicketRecord;
+
+mod support;
+
+use support::sample_pacient;
+
+#[test]
+fn plain_text_render_test() {
+    let result = OutputFormat::Text.render(&sample_pacient("Maria", "11144477735"));
+
+    let expected = "Nome: Maria\n\
+        CPF: 111.444.777-35\n\
+        Contato: 999\n\
+        Data de nascimento: 01-01-1990\n\
+        Rua: Rua\n\
+        Bairro: Bairro\n\
+        Cidade: Cidade\n";
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn json_render_test() -> Result<()> {
+    let appointment = Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string());
+
+    let result = OutputFormat::Json.render(&appointment);
+
+    assert_eq!(
+        result,
+        "{\"cpf\":\"111.444.777-35\",\"date\":\"05-03-2024\"}\n"
+    );
+
+    let parsed: serde_json::Value = serde_json::from_str(&result)?;
+    assert_eq!(parsed["date"], "05-03-2024");
+
+    Ok(())
+}
+
+#[test]
+fn json_render_all_test() -> Result<()> {
+    let appointments = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "06-03-2024".to_string()),
+    ];
+
+    let result = OutputFormat::Json.render_all(&appointments);
+    let parsed: Vec<serde_json::Value> = serde_json::from_str(&result)?;
+
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[1]["cpf"], "222.555.888-46");
+
+    Ok(())
+}
+
+#[test]
+fn html_render_escapes_values_test() {
+    let result = OutputFormat::Html.render(&sample_pacient("Maria <A>", "11144477735"));
+
+    assert!(result.starts_with("<dl>\n"));
+    assert!(result.ends_with("</dl>\n"));
+    assert!(result.contains("<dt class=\"name\">Nome</dt><dd>Maria &lt;A&gt;</dd>"));
+}
+
+#[test]
+fn sheet_with_priority_fields_test() {
+    let sheet = ServiceSheet::new(
+        sample_pacient("Maria", "11144477735"),
+        "Dor de dente".to_string(),
+        Local::now(),
+    );
+    let sheet = SheetWithPriority::new(sheet, TicketPriority::High);
+
+    let keys: Vec<String> = sheet
+        .fields()
+        .iter()
+        .map(|field| field.key().to_string())
+        .collect();
+
+    assert_eq!(
+        keys,
+        vec![
+            "name",
+            "cpf",
+            "phone_number",
+            "date_of_birth",
+            "street",
+            "neighborhood",
+            "city",
+            "reason"
+        ]
+    );
+}
+
+#[test]
+fn ticket_render_test() {
+    let ticket = PriorityQueueTicket::new(7, TicketPriority::High);
+
+    assert_eq!(
+        OutputFormat::Text.render(&ticket),
+        "Senha: P007\nAtendimento: Prioritário\n"
+    );
+    assert_eq!(
+        OutputFormat::Json.render(&ticket),
+        "{\"code\":\"P007\",\"priority\":\"Prioritário\"}\n"
+    );
+}
+
+#[test]
+fn json_keeps_value_types_test() -> Result<()> {
+    let payment = Payment::new(
+        "11144477735".parse().unwrap(),
+        Procedure::Cleaning,
+        PaymentMethod::Pix,
+        Local::now(),
+    );
+    let record = TicketRecord::new(&PriorityQueueTicket::new(7, TicketPriority::High));
+
+    // amounts and counts are numbers, timestamps not yet taken are null
+    let parsed: serde_json::Value = serde_json::from_str(&OutputFormat::Json.render(&payment))?;
+    assert_eq!(parsed["amount"], Procedure::Cleaning.price());
+
+    let parsed: serde_json::Value = serde_json::from_str(&OutputFormat::Json.render(&record))?;
+    assert_eq!(parsed["recalls"], 0);
+    assert!(parsed["called_at"].is_null());
+
+    // the text formats still show them the way people read them
+    let text = OutputFormat::Text.render(&payment);
+    assert!(text.contains(&format!("Valor: R${}\n", Procedure::Cleaning.price())));
+    assert!(OutputFormat::Text
+        .render(&record)
+        .contains("Chamada em: -\n"));
+
+    Ok(())
+}
+
+#[test]
+fn output_format_from_args_test() -> Result<()> {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(OutputFormat::from_args(args(&[]))?, OutputFormat::Text);
+    assert_eq!(
+        OutputFormat::from_args(args(&["--output", "json"]))?,
+        OutputFormat::Json
+    );
+    assert_eq!(
+        OutputFormat::from_args(args(&["--output=html"]))?,
+        OutputFormat::Html
+    );
+    assert_eq!(
+        OutputFormat::from_args(args(&["patient", "show", "--json"]))?,
+        OutputFormat::Json
+    );
+    assert!(OutputFormat::from_args(args(&["--output", "xml"])).is_err());
+    assert!(OutputFormat::from_args(args(&["--output"])).is_err());
+
+    Ok(())
+}
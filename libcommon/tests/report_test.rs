@@ -0,0 +1,218 @@
+use std::fs;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use common::appointment::Appointment;
+use common::payment::{Payment, PaymentMethod, Procedure};
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::report::DailyReport;
+use common::service_sheet::ServiceSheet;
+use common::ticket_record::{TicketOutcome, TicketRecord};
+
+mod support;
+
+use support::sample_pacient;
+
+fn issued_ticket(
+    code: usize,
+    priority: TicketPriority,
+    hour: u32,
+    min: u32,
+) -> PriorityQueueTicket {
+    let mut ticket = PriorityQueueTicket::new(code, priority);
+    ticket.set_issued_at(Local.with_ymd_and_hms(2024, 3, 5, hour, min, 0).unwrap());
+    ticket
+}
+
+fn called(mut ticket: PriorityQueueTicket, called_at: DateTime<Local>) -> TicketRecord {
+    ticket.lifecycle_mut().mark_called(called_at);
+    TicketRecord::new(&ticket)
+}
+
+fn sample_report() -> DailyReport {
+    let at = |day, hour, min| Local.with_ymd_and_hms(2024, 3, day, hour, min, 0).unwrap();
+
+    let queue = vec![issued_ticket(4, TicketPriority::Normal, 11, 0)];
+    let ticket_history = vec![
+        called(issued_ticket(1, TicketPriority::High, 8, 0), at(5, 8, 10)),
+        called(issued_ticket(2, TicketPriority::Normal, 8, 5), at(5, 8, 35)),
+        called(issued_ticket(3, TicketPriority::Normal, 9, 0), at(5, 9, 20)),
+        called(
+            issued_ticket(5, TicketPriority::Normal, 9, 30),
+            at(5, 9, 50),
+        )
+        .with_outcome(TicketOutcome::NoShow),
+        TicketRecord::new(&issued_ticket(6, TicketPriority::Normal, 9, 40))
+            .with_outcome(TicketOutcome::Cancelled),
+        // called and put back, the ticket still waiting in the queue
+        called(
+            issued_ticket(4, TicketPriority::Normal, 11, 0),
+            at(5, 11, 5),
+        )
+        .with_outcome(TicketOutcome::Requeued),
+        // issued and called on another day
+        called(
+            PriorityQueueTicket::new(9, TicketPriority::High),
+            at(4, 9, 0),
+        ),
+    ];
+    let service_sheets = vec![
+        ServiceSheet::new(
+            sample_pacient("Maria", "11144477735"),
+            "Dor".to_string(),
+            at(5, 8, 15),
+        ),
+        ServiceSheet::new(
+            sample_pacient("Maria", "33366699957"),
+            "Limpeza".to_string(),
+            at(5, 8, 40),
+        ),
+        ServiceSheet::new(
+            sample_pacient("Maria", "22255588846"),
+            "Dor".to_string(),
+            at(4, 8, 40),
+        ),
+    ];
+    let appointments = vec![
+        Appointment::new("11144477735".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("22255588846".parse().unwrap(), "05-03-2024".to_string()),
+        Appointment::new("33366699957".parse().unwrap(), "06-03-2024".to_string()),
+    ];
+    let payments = vec![
+        Payment::new(
+            "11144477735".parse().unwrap(),
+            Procedure::Filling,
+            PaymentMethod::Pix,
+            at(5, 9, 0),
+        ),
+        Payment::new(
+            "33366699957".parse().unwrap(),
+            Procedure::Cleaning,
+            PaymentMethod::Cash,
+            at(5, 9, 30),
+        ),
+        Payment::new(
+            "44477700083".parse().unwrap(),
+            Procedure::Filling,
+            PaymentMethod::Card,
+            at(5, 10, 0),
+        ),
+        Payment::new(
+            "22255588846".parse().unwrap(),
+            Procedure::RoutineCheckup,
+            PaymentMethod::Card,
+            at(4, 10, 0),
+        ),
+    ];
+
+    DailyReport::build(
+        NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        &queue,
+        &ticket_history,
+        &service_sheets,
+        &appointments,
+        &payments,
+    )
+}
+
+#[test]
+fn tickets_by_priority_test() {
+    let report = sample_report();
+
+    assert_eq!(
+        *report.tickets_by_priority(),
+        vec![(TicketPriority::High, 1), (TicketPriority::Normal, 5)]
+    );
+    assert_eq!(report.tickets_issued(), 6);
+}
+
+#[test]
+fn attendance_test() {
+    let report = sample_report();
+
+    assert_eq!(report.pacients_attended(), 2);
+    // waits of 10, 30, 20 and 20 minutes
+    assert_eq!(report.average_wait_minutes(), Some(20));
+    assert_eq!(report.no_shows(), 1);
+    assert_eq!(report.cancelled_tickets(), 1);
+}
+
+#[test]
+fn appointments_test() {
+    let report = sample_report();
+
+    assert_eq!(
+        *report.appointments_kept(),
+        vec![Appointment::new(
+            "11144477735".parse().unwrap(),
+            "05-03-2024".to_string()
+        )]
+    );
+    assert_eq!(
+        *report.appointments_missed(),
+        vec![Appointment::new(
+            "22255588846".parse().unwrap(),
+            "05-03-2024".to_string()
+        )]
+    );
+}
+
+#[test]
+fn revenue_by_procedure_test() {
+    let report = sample_report();
+
+    let revenue: Vec<(Procedure, usize, usize)> = report
+        .revenue_by_procedure()
+        .iter()
+        .map(|revenue| (revenue.procedure(), revenue.count(), revenue.total()))
+        .collect();
+
+    assert_eq!(
+        revenue,
+        vec![
+            (Procedure::RoutineCheckup, 0, 0),
+            (Procedure::Filling, 2, 200),
+            (Procedure::Cleaning, 1, 50),
+        ]
+    );
+    assert_eq!(report.total_revenue(), 250);
+}
+
+#[test]
+fn empty_day_test() {
+    let report = DailyReport::build(
+        NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    );
+
+    assert_eq!(report.tickets_issued(), 0);
+    assert_eq!(report.average_wait_minutes(), None);
+    assert!(report
+        .to_html()
+        .contains("<td>Espera média na recepção</td><td class=\"number\">-</td>"));
+}
+
+#[test]
+fn save_as_html_test() -> Result<()> {
+    let path = "save_as_html_test.html";
+    let report = sample_report();
+
+    report.save_as_html(path)?;
+    let html = fs::read_to_string(path)?;
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.ends_with("</html>\n"));
+    // self-contained: no external stylesheets or scripts
+    assert!(!html.contains("<link"));
+    assert!(!html.contains("<script"));
+    assert!(html.contains("<p class=\"date\">05-03-2024</p>"));
+    assert!(html.contains("<tr class=\"total\"><td>Total</td><td class=\"number\">3</td><td class=\"number\">R$250</td></tr>"));
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
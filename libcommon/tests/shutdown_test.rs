@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use common::shutdown::{archive_queue, ask_shutdown, Shutdown};
+
+mod support;
+
+use support::scripted;
+
+#[test]
+fn ask_shutdown_test() -> Result<()> {
+    assert_eq!(ask_shutdown(&mut scripted("1\n1\n"))?, Some(Shutdown::Exit));
+    assert_eq!(
+        ask_shutdown(&mut scripted("2\n1\n"))?,
+        Some(Shutdown::EndOfDay)
+    );
+
+    // nothing happens unless it is confirmed
+    assert_eq!(ask_shutdown(&mut scripted("2\n2\n"))?, None);
+    assert_eq!(ask_shutdown(&mut scripted("0\n"))?, None);
+
+    Ok(())
+}
+
+#[test]
+fn archive_queue_test() -> Result<()> {
+    let dir = "shutdown_test_queues";
+    fs::create_dir_all(dir)?;
+    let path = format!("{}/fila.json", dir);
+    let at = Local.with_ymd_and_hms(2024, 3, 5, 18, 30, 0).unwrap();
+
+    assert_eq!(archive_queue(&path, at)?, None);
+
+    fs::write(&path, "[]")?;
+    let archived = archive_queue(&path, at)?;
+
+    let expected = format!("{}/fila-20240305-183000.json", dir);
+    assert_eq!(archived.as_deref(), Some(expected.as_str()));
+    assert!(!Path::new(&path).exists());
+    assert_eq!(fs::read_to_string(&expected)?, "[]");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+// helpers shared by the test files; not every file uses all of them
+#![allow(dead_code)]
+
+use std::io::Cursor;
+
+use anyhow::Result;
+use chrono::Local;
+use common::io_handler::IOHandler;
+use common::pacient_account::{Address, Pacient};
+
+pub type ScriptedIO = IOHandler<Cursor<String>, Cursor<Vec<u8>>>;
+
+// an IO handler that reads the given answers and keeps what it writes
+pub fn scripted(input: &str) -> ScriptedIO {
+    IOHandler::new(Cursor::new(input.to_string()), Cursor::new(vec![]))
+}
+
+pub fn output(io_handler: &ScriptedIO) -> Result<String> {
+    Ok(String::from_utf8(io_handler.writer().clone().into_inner())?)
+}
+
+pub fn sample_pacient(name: &str, cpf: &str) -> Pacient {
+    Pacient::new(
+        name.to_string(),
+        cpf.parse().unwrap(),
+        "999".to_string(),
+        "01-01-1990".to_string(),
+        Address::new(
+            "Rua".to_string(),
+            "Bairro".to_string(),
+            "Cidade".to_string(),
+        ),
+        Local::now(),
+    )
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::ticket_limits::{issued_on, Refusal, TicketLimits};
+use common::ticket_record::{TicketOutcome, TicketRecord};
+
+fn at(hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 3, 5)
+        .unwrap()
+        .and_hms_opt(hour, min, 0)
+        .unwrap()
+}
+
+fn time(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+}
+
+fn issued_ticket(code: usize, priority: TicketPriority, day: u32) -> PriorityQueueTicket {
+    let mut ticket = PriorityQueueTicket::new(code, priority);
+    ticket.set_issued_at(Local.with_ymd_and_hms(2024, 3, day, 9, 0, 0).unwrap());
+    ticket
+}
+
+#[test]
+fn no_limits_test() {
+    let limits = TicketLimits::new();
+    let issued = vec![TicketPriority::Normal; 500];
+
+    assert_eq!(
+        limits.check(at(23, 30), TicketPriority::Normal, &issued, 400, 10.0),
+        Ok(())
+    );
+}
+
+#[test]
+fn opening_hours_test() -> Result<()> {
+    let limits = TicketLimits::from_config(Some("08:00-18:00"), None, None)?;
+    let closed = Err(Refusal::Closed {
+        opens: time(8, 0),
+        closes: time(18, 0),
+    });
+
+    assert_eq!(
+        limits.check(at(7, 59), TicketPriority::Normal, &[], 0, 10.0),
+        closed
+    );
+    assert_eq!(
+        limits.check(at(8, 0), TicketPriority::Normal, &[], 0, 10.0),
+        Ok(())
+    );
+    assert_eq!(
+        limits.check(at(18, 0), TicketPriority::Normal, &[], 0, 10.0),
+        closed
+    );
+
+    assert!(TicketLimits::from_config(Some("18:00-08:00"), None, None).is_err());
+    assert!(TicketLimits::from_config(Some("8h-18h"), None, None).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn capacity_test() -> Result<()> {
+    let limits = TicketLimits::from_config(None, Some("3"), Some("Normal=2, Pregnant=5"))?;
+
+    let issued = vec![TicketPriority::Normal, TicketPriority::Normal];
+    assert_eq!(
+        limits.check(at(9, 0), TicketPriority::Normal, &issued, 0, 10.0),
+        Err(Refusal::CategoryCapacity(TicketPriority::Normal, 2))
+    );
+    assert_eq!(
+        limits.check(at(9, 0), TicketPriority::Pregnant, &issued, 0, 10.0),
+        Ok(())
+    );
+
+    let issued = vec![TicketPriority::Pregnant; 3];
+    assert_eq!(
+        limits.check(at(9, 0), TicketPriority::Pregnant, &issued, 0, 10.0),
+        Err(Refusal::DailyCapacity(3))
+    );
+
+    assert!(TicketLimits::from_config(None, Some("many"), None).is_err());
+    assert!(TicketLimits::from_config(None, None, Some("Child=2")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn cutoff_test() -> Result<()> {
+    let limits = TicketLimits::from_config(Some("08:00-18:00"), None, None)?;
+
+    // 5 waiting plus the new ticket at 10 minutes each end at 18:00
+    assert_eq!(
+        limits.check(at(17, 0), TicketPriority::Normal, &[], 5, 10.0),
+        Ok(())
+    );
+    assert_eq!(
+        limits.check(at(17, 0), TicketPriority::Normal, &[], 6, 10.0),
+        Err(Refusal::Cutoff {
+            estimated_end: time(18, 10),
+            closes: time(18, 0),
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn issued_on_test() {
+    let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+    // numbered today, whether waiting, being called or already attended
+    let numbered = vec![
+        TicketPriority::Normal,
+        TicketPriority::Elderly60,
+        TicketPriority::Pregnant,
+        TicketPriority::Normal,
+    ];
+    let history = vec![
+        TicketRecord::new(&issued_ticket(2, TicketPriority::Elderly60, 5)),
+        TicketRecord::new(&issued_ticket(3, TicketPriority::Pregnant, 5))
+            .with_outcome(TicketOutcome::NoShow),
+        TicketRecord::new(&issued_ticket(4, TicketPriority::Normal, 5))
+            .with_outcome(TicketOutcome::Cancelled),
+        // cancelled on another day, its place was freed back then
+        TicketRecord::new(&issued_ticket(5, TicketPriority::Elderly60, 4))
+            .with_outcome(TicketOutcome::Cancelled),
+    ];
+
+    assert_eq!(
+        issued_on(date, &numbered, &history),
+        vec![
+            TicketPriority::Elderly60,
+            TicketPriority::Pregnant,
+            TicketPriority::Normal
+        ]
+    );
+}
+
+#[test]
+fn refusal_reason_test() {
+    assert_eq!(
+        Refusal::CategoryCapacity(TicketPriority::Normal, 80).reason(),
+        "O limite de 80 senhas do dia para Normal foi atingido."
+    );
+    assert_eq!(
+        Refusal::Closed {
+            opens: time(8, 0),
+            closes: time(18, 0),
+        }
+        .reason(),
+        "As senhas são emitidas das 08:00 às 18:00."
+    );
+}
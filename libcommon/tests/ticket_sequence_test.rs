@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use common::priority_queue::{PriorityQueueTicket, TicketPriority};
+use common::ticket_sequence::TicketSequence;
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+}
+
+#[test]
+fn sequence_persists_and_resets_daily_test() -> Result<()> {
+    let path = "sequence_persists_and_resets_daily_test.json";
+
+    let sequence = TicketSequence::new(path.to_string());
+    assert_eq!(sequence.next(day(5), TicketPriority::Normal)?, 1);
+    assert_eq!(sequence.next(day(5), TicketPriority::Elderly60)?, 2);
+
+    // a restarted kiosk carries on from where the last one stopped
+    let sequence = TicketSequence::new(path.to_string());
+    assert_eq!(sequence.next(day(5), TicketPriority::Normal)?, 3);
+    assert_eq!(
+        sequence.issued(day(5))?,
+        vec![
+            TicketPriority::Normal,
+            TicketPriority::Elderly60,
+            TicketPriority::Normal
+        ]
+    );
+
+    assert_eq!(sequence.next(day(6), TicketPriority::Normal)?, 1);
+    assert_eq!(sequence.issued(day(6))?, vec![TicketPriority::Normal]);
+    assert_eq!(sequence.issued(day(7))?, Vec::new());
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn sequence_is_unique_across_kiosks_test() -> Result<()> {
+    let path = "sequence_is_unique_across_kiosks_test.json";
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            thread::spawn(move || {
+                let sequence = TicketSequence::new(path.to_string());
+                (0..25)
+                    .map(|_| sequence.next(day(5), TicketPriority::Normal).unwrap())
+                    .collect::<Vec<usize>>()
+            })
+        })
+        .collect();
+
+    let codes: Vec<usize> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    let unique: HashSet<&usize> = codes.iter().collect();
+
+    assert_eq!(codes.len(), 200);
+    assert_eq!(unique.len(), 200);
+    assert_eq!(codes.iter().max(), Some(&200));
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn stale_lock_is_broken_once_test() -> Result<()> {
+    let path = "stale_lock_is_broken_once_test.json";
+    let lock_path = format!("{}.lock", path);
+    // left behind by a kiosk that crashed a minute ago
+    File::create(&lock_path)?.set_modified(SystemTime::now() - Duration::from_secs(60))?;
+
+    // every kiosk finds the stale lock at once, yet only one of them takes it
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            thread::spawn(move || {
+                let sequence = TicketSequence::new(path.to_string());
+                (0..10)
+                    .map(|_| sequence.next(day(5), TicketPriority::Normal).unwrap())
+                    .collect::<Vec<usize>>()
+            })
+        })
+        .collect();
+
+    let codes: Vec<usize> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    let unique: HashSet<&usize> = codes.iter().collect();
+
+    assert_eq!(unique.len(), 80);
+    assert!(!fs::exists(&lock_path)?);
+    // nothing is left aside either
+    assert!(fs::read_dir(".")?
+        .filter_map(|entry| entry.ok())
+        .all(|entry| !entry.file_name().to_string_lossy().starts_with(&lock_path)));
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn sequence_times_out_on_held_lock_test() -> Result<()> {
+    let path = "sequence_times_out_on_held_lock_test.json";
+    let lock_path = format!("{}.lock", path);
+    File::create(&lock_path)?;
+
+    let sequence = TicketSequence::new(path.to_string());
+    assert!(sequence.next(day(5), TicketPriority::Normal).is_err());
+
+    fs::remove_file(lock_path)?;
+    assert_eq!(sequence.next(day(5), TicketPriority::Normal)?, 1);
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+#[test]
+fn display_code_test() {
+    let code = |priority| PriorityQueueTicket::new(42, priority).display_code();
+
+    assert_eq!(code(TicketPriority::Normal), "N042");
+    assert_eq!(code(TicketPriority::Scheduled), "A042");
+    assert_eq!(code(TicketPriority::Elderly80), "P042");
+    assert_eq!(code(TicketPriority::High), "P042");
+    assert_eq!(code(TicketPriority::Emergency), "E042");
+    assert_eq!(TicketPriority::Pregnant.format_code(1234), "P1234");
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::io::{BufRead, Cursor, Write};
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use common::io_handler::{stop_at_input_end, IOHandler, Prompt};
+use common::transcript::{Entry, Session, Transcript};
+
+// a tiny station that greets whoever types their name
+fn greet<R: BufRead, W: Write>(io_handler: &mut IOHandler<R, W>, greeting: &str) -> Result<()> {
+    loop {
+        let name = io_handler.ask(&Prompt::text("Nome: "))?;
+        io_handler.write(format!("{}, {}!\n", greeting, name))?;
+    }
+}
+
+fn replay(recorded: &Transcript, greeting: &str) -> Result<Transcript> {
+    let mut io_handler =
+        IOHandler::new(Cursor::new(recorded.input()), Cursor::new(vec![])).recording();
+    stop_at_input_end(greet(&mut io_handler, greeting))?;
+
+    Ok(io_handler.transcript().cloned().unwrap_or_default())
+}
+
+fn recorded() -> Result<Transcript> {
+    let mut io_handler =
+        IOHandler::new(Cursor::new("Maria\n\nJoão\n"), Cursor::new(vec![])).recording();
+    stop_at_input_end(greet(&mut io_handler, "Olá"))?;
+
+    Ok(io_handler.transcript().cloned().unwrap_or_default())
+}
+
+#[test]
+fn recording_test() -> Result<()> {
+    let transcript = recorded()?;
+
+    let kinds: Vec<bool> = transcript
+        .entries()
+        .iter()
+        .map(|entry| matches!(entry, Entry::Input { .. }))
+        .collect();
+    assert_eq!(kinds, vec![false, true, false, true, false, true, false]);
+    assert_eq!(transcript.inputs(), vec!["Maria", "", "João"]);
+    assert_eq!(transcript.input(), "Maria\n\nJoão\n");
+    assert_eq!(
+        transcript.outputs(),
+        vec![
+            "Nome: ",
+            "Olá, Maria!\nNome: ",
+            "Este campo é obrigatório.\nNome: ",
+            "Olá, João!\nNome: "
+        ]
+    );
+
+    // nothing is kept unless asked for
+    let io_handler = IOHandler::new(Cursor::new(""), Cursor::new(vec![]));
+    assert!(io_handler.transcript().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn replay_test() -> Result<()> {
+    let recorded = recorded()?;
+
+    assert_eq!(recorded.compare(&replay(&recorded, "Olá")?), Ok(()));
+
+    let mismatch = recorded
+        .compare(&replay(&recorded, "Oi")?)
+        .expect_err("the greeting changed");
+    assert_eq!(mismatch.step, 1);
+    assert_eq!(mismatch.input.as_deref(), Some("Maria"));
+    assert_eq!(mismatch.expected, "Olá, Maria!\nNome: ");
+    assert_eq!(mismatch.found, "Oi, Maria!\nNome: ");
+
+    Ok(())
+}
+
+#[test]
+fn save_and_load_test() -> Result<()> {
+    let path = "transcript_test.json";
+    let at = Local.with_ymd_and_hms(2024, 3, 5, 9, 30, 15).unwrap();
+
+    let mut transcript = Transcript::new();
+    transcript.record_output("Nome: ".to_string(), at);
+    transcript.record_input("Maria".to_string(), at);
+    transcript.save(path)?;
+
+    let loaded = Transcript::load(path)?;
+    fs::remove_file(path)?;
+
+    assert_eq!(loaded, transcript);
+    assert_eq!(loaded.entries()[1].at(), "09:30:15 05-03-2024");
+    assert_eq!(loaded.entries()[1].text(), "Maria");
+
+    Ok(())
+}
+
+#[test]
+fn session_args_test() -> Result<()> {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        Session::from_args(args(&["--output", "json"]))?,
+        Session::Live
+    );
+    assert_eq!(
+        Session::from_args(args(&["--record", "sessao.json"]))?,
+        Session::Record("sessao.json".to_string())
+    );
+    assert_eq!(
+        Session::from_args(args(&["--replay", "sessao.json", "--output", "html"]))?,
+        Session::Replay("sessao.json".to_string())
+    );
+    assert!(Session::from_args(args(&["--replay"])).is_err());
+
+    Ok(())
+}
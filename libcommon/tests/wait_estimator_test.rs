@@ -0,0 +1,112 @@
+use chrono::{Local, TimeZone};
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket, TicketPriority};
+use common::ticket_record::TicketRecord;
+use common::wait_estimator::{describe_wait, WaitEstimator};
+
+fn called(code: usize, priority: TicketPriority, hour: u32, min: u32) -> TicketRecord {
+    let mut ticket = PriorityQueueTicket::new(code, priority);
+    ticket
+        .lifecycle_mut()
+        .mark_called(Local.with_ymd_and_hms(2024, 3, 5, hour, min, 0).unwrap());
+    TicketRecord::new(&ticket)
+}
+
+fn sample_history() -> Vec<TicketRecord> {
+    vec![
+        called(4, TicketPriority::Normal, 8, 30),
+        called(1, TicketPriority::Normal, 8, 0),
+        called(2, TicketPriority::Pregnant, 8, 10),
+        called(3, TicketPriority::Normal, 8, 20),
+        // back from lunch, the gap isn't service time
+        called(5, TicketPriority::Normal, 10, 0),
+    ]
+}
+
+#[test]
+fn minutes_per_call_test() {
+    let estimator = WaitEstimator::from_history(&sample_history(), 15.0);
+    assert_eq!(estimator.minutes_per_call(), 10.0);
+
+    let estimator = WaitEstimator::from_history(&[], 15.0);
+    assert_eq!(estimator.minutes_per_call(), 15.0);
+}
+
+fn served(
+    code: usize,
+    issued: (u32, u32),
+    called: (u32, u32),
+    finished: (u32, u32),
+) -> TicketRecord {
+    let at = |(hour, min): (u32, u32)| Local.with_ymd_and_hms(2024, 3, 5, hour, min, 0).unwrap();
+    let mut ticket = PriorityQueueTicket::new(code, TicketPriority::Normal);
+    ticket.lifecycle_mut().mark_issued(at(issued));
+    ticket.lifecycle_mut().mark_called(at(called));
+    ticket.lifecycle_mut().mark_finished(at(finished));
+    TicketRecord::new(&ticket)
+}
+
+#[test]
+fn minutes_per_call_from_service_time_test() {
+    let history = vec![
+        served(1, (8, 0), (8, 0), (8, 6)),
+        // already waiting when the first one was done, so the 2 minutes until
+        // it was called count
+        served(2, (8, 2), (8, 8), (8, 14)),
+        // nobody waited from 8:14 to 8:40, the desk was idle
+        served(3, (8, 40), (8, 40), (8, 46)),
+        // still at the desk
+        called(4, TicketPriority::Normal, 8, 47),
+    ];
+
+    // 6 minutes at the desk plus 2 to call the next, where the calls alone
+    // would make it nearly 16
+    let estimator = WaitEstimator::from_history(&history, 15.0);
+    assert_eq!(estimator.minutes_per_call(), 8.0);
+}
+
+#[test]
+fn estimate_by_position_and_priority_test() {
+    let estimator = WaitEstimator::from_history(&sample_history(), 15.0);
+
+    assert_eq!(estimator.estimate(0, TicketPriority::Normal), 0);
+    // one in five recent calls outranked a normal ticket
+    assert_eq!(estimator.estimate(3, TicketPriority::Normal), 38);
+    assert_eq!(estimator.estimate(3, TicketPriority::Pregnant), 30);
+    assert_eq!(estimator.estimate(3, TicketPriority::Emergency), 30);
+}
+
+#[test]
+fn estimate_by_configured_order_test() {
+    // pregnant pacients are served after everyone else here, so the recent
+    // call of one passed in front of no normal ticket
+    let estimator = WaitEstimator::from_history(&sample_history(), 15.0)
+        .with_categories(vec![TicketPriority::Normal, TicketPriority::Pregnant]);
+
+    assert_eq!(estimator.estimate(3, TicketPriority::Normal), 30);
+    // while the normal ones outranked it, as far as the cap allows
+    assert_eq!(estimator.estimate(3, TicketPriority::Pregnant), 120);
+}
+
+#[test]
+fn estimate_queue_test() {
+    let estimator = WaitEstimator::new(5.0, Vec::new());
+    let mut queue = PriorityQueue::new();
+    queue.enqueue(PriorityQueueTicket::new(1, TicketPriority::Normal));
+    queue.enqueue(PriorityQueueTicket::new(2, TicketPriority::Normal));
+    queue.enqueue(PriorityQueueTicket::new(3, TicketPriority::Elderly60));
+
+    let board: Vec<(usize, i64)> = estimator
+        .estimate_queue(&queue)
+        .iter()
+        .map(|(ticket, minutes)| (ticket.code(), *minutes))
+        .collect();
+
+    assert_eq!(board, vec![(3, 0), (1, 5), (2, 10)]);
+}
+
+#[test]
+fn describe_wait_test() {
+    assert_eq!(describe_wait(0), "imediata");
+    assert_eq!(describe_wait(25), "cerca de 25 min");
+    assert_eq!(describe_wait(65), "cerca de 1h05");
+}
@@ -0,0 +1,121 @@
+use std::io::Write;
+
+use anyhow::Result;
+use common::command::{CommandLine, CommandStatus};
+use common::documents::DocumentPrinter;
+use common::i18n;
+use common::priority_queue::TicketPriority;
+use common::render::OutputFormat;
+use common::ticket_limits::Refusal;
+
+use crate::kiosk::Kiosk;
+
+// runs a subcommand without the kiosk menu: records go to stdout, messages
+// to stderr
+pub fn run<W: Write>(
+    command: &CommandLine,
+    kiosk: &mut Kiosk,
+    output_format: OutputFormat,
+    document_printer: &DocumentPrinter,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    match command.words().as_slice() {
+        ["ticket"] => issue_ticket(command, kiosk, output_format, document_printer, stdout),
+        ["cancel", code] => match command.option("--pin") {
+            Some(pin) => cancel_ticket(code, pin, kiosk, output_format, stdout),
+            None => {
+                eprintln!("{}", i18n::message("cli.pacient_usage"));
+                Ok(CommandStatus::Usage)
+            }
+        },
+        _ => {
+            eprintln!("{}", i18n::message("cli.pacient_usage"));
+            Ok(CommandStatus::Usage)
+        }
+    }
+}
+
+fn issue_ticket<W: Write>(
+    command: &CommandLine,
+    kiosk: &mut Kiosk,
+    output_format: OutputFormat,
+    document_printer: &DocumentPrinter,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    let categories = kiosk.categories();
+    let requested = command.option("--priority").unwrap_or_default();
+    let priority = match TicketPriority::parse(requested) {
+        // the old `High` priority, when it isn't configured, is the top
+        // preferential category; an emergency is never asked for this way
+        Some(TicketPriority::High) if !categories.contains(&TicketPriority::High) => categories
+            .iter()
+            .copied()
+            .find(|category| category.is_preferential() && *category != TicketPriority::Emergency),
+        priority => priority.filter(|priority| categories.contains(priority)),
+    };
+    let priority = match priority {
+        Some(priority) => priority,
+        None => {
+            let names: Vec<String> = categories
+                .iter()
+                .map(|category| format!("{:?}", category))
+                .collect();
+            eprintln!(
+                "{}",
+                i18n::text(
+                    "cli.unknown_priority",
+                    &[("priority", &requested), ("categories", &names.join(", "))],
+                )
+            );
+            return Ok(CommandStatus::Usage);
+        }
+    };
+
+    let issued = match kiosk.issue(priority) {
+        Ok(issued) => issued,
+        Err(err) => {
+            return match err.downcast::<Refusal>() {
+                Ok(refusal) => {
+                    eprintln!("{}", refusal);
+                    Ok(CommandStatus::Refused)
+                }
+                Err(err) => Err(err),
+            }
+        }
+    };
+
+    let ticket_path =
+        document_printer.queue_ticket(&issued.ticket, &issued.estimated_wait, kiosk.now())?;
+
+    write!(stdout, "{}", output_format.render(&issued))?;
+    eprint!(
+        "{}",
+        i18n::text("kiosk.ticket_printed", &[("path", &ticket_path)])
+    );
+    eprint!("{}", i18n::plural("kiosk.people_ahead", issued.ahead, &[]));
+    eprint!(
+        "{}",
+        i18n::text("kiosk.estimated_wait", &[("wait", &issued.estimated_wait)])
+    );
+
+    Ok(CommandStatus::Done)
+}
+
+fn cancel_ticket<W: Write>(
+    code: &str,
+    pin: &str,
+    kiosk: &mut Kiosk,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    match kiosk.cancel(code, pin)? {
+        Some(ticket) => {
+            write!(stdout, "{}", output_format.render(&ticket))?;
+            Ok(CommandStatus::Done)
+        }
+        None => {
+            eprintln!("{}", i18n::message("kiosk.ticket_not_found").trim());
+            Ok(CommandStatus::NotFound)
+        }
+    }
+}
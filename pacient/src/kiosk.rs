@@ -0,0 +1,195 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use common::clock::{Clock, SystemClock};
+use common::database::Database;
+use common::file_lock::FileLock;
+use common::i18n;
+use common::json_handler::JsonHandler;
+use common::priority_queue::{
+    Priority, PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority,
+};
+use common::render::{Field, Render};
+use common::shutdown::archive_queue;
+use common::ticket_limits::{issued_on, TicketLimits};
+use common::ticket_record::{TicketOutcome, TicketRecord};
+use common::ticket_sequence::TicketSequence;
+use common::transcript::random_seed;
+use common::wait_estimator::{describe_wait, WaitEstimator, DEFAULT_MINUTES_PER_CALL};
+
+pub struct IssuedTicket {
+    pub ticket: PriorityQueueTicket,
+    // how many pacients will be called before it
+    pub ahead: usize,
+    pub estimated_wait: String,
+}
+
+// the ticket along with the PIN that cancels it, which only the pacient who
+// took it gets to see
+impl Render for IssuedTicket {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = self.ticket.fields();
+        fields.push(Field::optional(
+            "pin",
+            i18n::message("field.pin"),
+            self.ticket.pin(),
+        ));
+        fields
+    }
+}
+
+// issues and cancels tickets, whichever front-end the pacient uses
+pub struct Kiosk {
+    queue: PriorityQueue<PriorityQueueTicket>,
+    queue_path: String,
+    ticket_history: Database,
+    ticket_sequence: TicketSequence,
+    ticket_limits: TicketLimits,
+    clock: Rc<dyn Clock>,
+    // the PINs of a kiosk come from it, so a replayed session gets the same
+    pin_seed: u64,
+}
+
+impl Kiosk {
+    pub fn new(
+        queue_settings: QueueSettings,
+        queue_path: String,
+        ticket_history: Database,
+        ticket_sequence: TicketSequence,
+        ticket_limits: TicketLimits,
+    ) -> Self {
+        Self {
+            queue: PriorityQueue::with_settings(&queue_settings),
+            queue_path,
+            ticket_history,
+            ticket_sequence,
+            ticket_limits,
+            clock: Rc::new(SystemClock),
+            pin_seed: random_seed(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_pin_seed(mut self, seed: u64) -> Self {
+        self.pin_seed = seed;
+        self
+    }
+
+    // the time at this kiosk
+    pub fn now(&self) -> DateTime<Local> {
+        self.clock.local_now()
+    }
+
+    pub fn categories(&self) -> Vec<TicketPriority> {
+        self.queue.categories()
+    }
+
+    // a ticket the limits don't allow fails with the `Refusal`
+    pub fn issue(&mut self, priority: TicketPriority) -> Result<IssuedTicket> {
+        // no other station may change the queue until this one is saved
+        let _lock = FileLock::acquire(&self.queue_path)?;
+        self.pull_file_updates()?;
+
+        let history: Vec<TicketRecord> = self.ticket_history.query_all()?;
+        let estimator = WaitEstimator::from_history(&history, DEFAULT_MINUTES_PER_CALL)
+            .with_categories(self.queue.categories());
+        let now = self.now();
+        let issued_today = issued_on(
+            now.date_naive(),
+            &self.ticket_sequence.issued(now.date_naive())?,
+            &history,
+        );
+        self.ticket_limits.check(
+            now.naive_local(),
+            priority,
+            &issued_today,
+            self.queue.len(),
+            estimator.minutes_per_call(),
+        )?;
+
+        let code = self.ticket_sequence.next(now.date_naive(), priority)?;
+        let mut ticket = PriorityQueueTicket::new(code, priority);
+        ticket.set_issued_at(now);
+        ticket.set_pin(self.new_pin(code, now.date_naive()));
+        self.queue.enqueue(ticket.clone());
+
+        JsonHandler::save_as_json(&self.queue_path, &self.queue)?;
+
+        let ahead = self
+            .queue
+            .position_of(|queued| queued.code() == ticket.code())
+            .unwrap_or_default();
+        let estimated_wait = describe_wait(estimator.estimate(ahead, ticket.priority()));
+
+        Ok(IssuedTicket {
+            ticket,
+            ahead,
+            estimated_wait,
+        })
+    }
+
+    // the code is announced aloud, so the PIN printed on the ticket is asked
+    // as well; `None` when no waiting ticket has both
+    pub fn cancel(&mut self, code: &str, pin: &str) -> Result<Option<PriorityQueueTicket>> {
+        let _lock = FileLock::acquire(&self.queue_path)?;
+        self.pull_file_updates()?;
+        let cancelled = self.queue.remove_by(|ticket| {
+            ticket.display_code().eq_ignore_ascii_case(code.trim()) && ticket.matches_pin(pin)
+        });
+
+        let mut ticket = match cancelled {
+            Some(ticket) => ticket,
+            None => return Ok(None),
+        };
+
+        JsonHandler::save_as_json(&self.queue_path, &self.queue)?;
+        ticket.lifecycle_mut().mark_finished(self.now());
+        self.ticket_history
+            .insert(TicketRecord::new(&ticket).with_outcome(TicketOutcome::Cancelled))?;
+
+        Ok(Some(ticket))
+    }
+
+    // the queue is archived when the office closes, so the next day starts
+    // with an empty one
+    pub fn close_day(&mut self, at: DateTime<Local>) -> Result<Option<String>> {
+        let _lock = FileLock::acquire(&self.queue_path)?;
+        let archived = archive_queue(&self.queue_path, at)?;
+        self.queue.reload(Vec::new());
+        Ok(archived)
+    }
+
+    // four digits no one can tell from the code, since the seed is random
+    fn new_pin(&self, code: usize, day: NaiveDate) -> String {
+        // the finalizer of splitmix64
+        let mut mixed = self.pin_seed
+            ^ ((day.num_days_from_ce() as u64) << 32)
+            ^ (code as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        mixed ^= mixed >> 31;
+
+        format!("{:04}", mixed % 10_000)
+    }
+
+    fn pull_file_updates(&mut self) -> Result<()> {
+        // no ticket was issued yet while no kiosk has saved the queue; a queue
+        // that can't be read is reported rather than overwritten
+        if Path::new(&self.queue_path).exists() {
+            // the receptionist's policy turn comes along, so it isn't
+            // overwritten when the kiosk saves the queue
+            self.queue
+                .restore(JsonHandler::read_document(&self.queue_path)?);
+        } else {
+            self.queue.reload(Vec::new());
+        }
+
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
+pub mod commands;
+pub mod kiosk;
 pub mod pacient_manager;
@@ -1,19 +1,88 @@
 use std::env;
+use std::io;
+use std::process::ExitCode;
 
 use anyhow::Result;
-use common::io_handler::{DefaultIOHandler, IOHandler};
-use common::priority_queue::PriorityQueue;
+use common::command::CommandLine;
+use common::database::Database;
+use common::dequeue_policy::parse_policy;
+use common::documents::DocumentPrinter;
+use common::i18n::{self, Locale};
+use common::priority_queue::QueueSettings;
+use common::render::OutputFormat;
+use common::shutdown;
+use common::ticket_limits::TicketLimits;
+use common::ticket_sequence::TicketSequence;
+use common::transcript::Session;
 use dotenv::dotenv;
 
+use pacient::commands;
+use pacient::kiosk::Kiosk;
 use pacient::pacient_manager::PacientManager;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     dotenv().ok();
+    i18n::set_locale(Locale::from_config(env::var("LOCALE").ok().as_deref())?);
+    shutdown::handle_interrupts()?;
 
-    let queue_file_path = env::var("PACIENT_QUEUE_FILE_PATH")?;
-    let io_handler = IOHandler::default();
+    let command = CommandLine::parse(env::args().skip(1));
+    let output_format = OutputFormat::from_args(env::args().skip(1))?;
+    let mut session = Session::from_args(env::args().skip(1))?.start()?;
+    let queue_file_path = session.data_file(env::var("PACIENT_QUEUE_FILE_PATH")?)?;
+    let queue_settings = QueueSettings::from_config(
+        env::var("PRIORITY_CATEGORIES").ok().as_deref(),
+        env::var("PRIORITY_AGING_MINUTES").ok().as_deref(),
+    )?
+    // the kiosk queue is the one the receptionist calls from
+    .with_policy(parse_policy(
+        env::var("RECEPTIONIST_DEQUEUE_POLICY").ok().as_deref(),
+    )?)
+    .with_clock(session.clock());
+    let ticket_limits = TicketLimits::from_config(
+        env::var("OPENING_HOURS").ok().as_deref(),
+        env::var("DAILY_TICKET_CAPACITY").ok().as_deref(),
+        env::var("CATEGORY_TICKET_CAPACITY").ok().as_deref(),
+    )?;
+    let document_printer = DocumentPrinter::new(
+        session.data_dir(env::var("DOCUMENTS_DIR")?)?,
+        env::var("DOCUMENT_TEMPLATES_DIR").ok(),
+    )?
+    .with_clock(session.clock());
 
-    let mut manager = PacientManager::new(io_handler, PriorityQueue::new(), queue_file_path);
-    manager.start();
-    Ok(())
+    let mut kiosk = Kiosk::new(
+        queue_settings,
+        queue_file_path,
+        Database::new(session.data_file(env::var("TICKET_HISTORY_DATABASE")?)?)?,
+        TicketSequence::new(session.data_file(env::var("TICKET_SEQUENCE_FILE")?)?),
+        ticket_limits,
+    )
+    .with_clock(session.clock())
+    .with_pin_seed(session.seed());
+
+    if !command.is_interactive() {
+        return Ok(commands::run(
+            &command,
+            &mut kiosk,
+            output_format,
+            &document_printer,
+            &mut io::stdout(),
+        )?
+        .into());
+    }
+
+    let mut manager = PacientManager::new(
+        session.io_handler()?,
+        kiosk,
+        Database::new(session.data_file(env::var("APPOINTMENT_SCHEDULE_DATABASE")?)?)?,
+        output_format,
+        document_printer,
+    );
+    // started by an operator, the kiosk can be shut down from its menu
+    if command.flag("--operator") {
+        manager = manager.with_shutdown_option();
+    }
+    let result = manager.start();
+    result.and(session.finish(manager.io_handler()))?;
+
+    Ok(shutdown::exit_code())
 }
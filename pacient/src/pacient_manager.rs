@@ -1,15 +1,34 @@
-use std::fs;
 use std::io;
 
-use common::io_handler::IOHandler;
-use common::json_handler::JsonHandler;
-use common::priority_queue::{PriorityQueue, PriorityQueueTicket, TicketPriority};
+use anyhow::Result;
+
+use common::appointment::Appointment;
+use common::database::Database;
+use common::documents::DocumentPrinter;
+use common::i18n;
+use common::io_handler::{stop_at_input_end, IOHandler, Menu, Prompt};
+use common::priority_queue::TicketPriority;
+use common::render::OutputFormat;
+use common::shutdown::{ask_shutdown, Shutdown};
+use common::ticket_limits::Refusal;
+
+use crate::kiosk::Kiosk;
+
+#[derive(Clone)]
+enum KioskOperation {
+    Enqueue(TicketPriority),
+    Cancel,
+    Shutdown,
+}
 
 pub struct PacientManager<R, W> {
     io_handler: IOHandler<R, W>,
-    queue: PriorityQueue<PriorityQueueTicket>,
-    queue_path: String,
-    ticket_code: usize,
+    kiosk: Kiosk,
+    appointment_schedule: Database,
+    output_format: OutputFormat,
+    document_printer: DocumentPrinter,
+    // the kiosk faces the pacients, only an operator gets to shut it down
+    shutdown_option: bool,
 }
 
 impl<R, W> PacientManager<R, W>
@@ -19,76 +38,204 @@ where
 {
     pub fn new(
         io_handler: IOHandler<R, W>,
-        queue: PriorityQueue<PriorityQueueTicket>,
-        queue_path: String,
+        kiosk: Kiosk,
+        appointment_schedule: Database,
+        output_format: OutputFormat,
+        document_printer: DocumentPrinter,
     ) -> Self {
         Self {
             io_handler,
-            queue,
-            queue_path,
-            ticket_code: 1,
+            kiosk,
+            appointment_schedule,
+            output_format,
+            document_printer,
+            shutdown_option: false,
         }
     }
 
-    pub fn start(&mut self) {
-        self.io_handler
-            .write("Seja bem-vindo(a) à SOS Dentes!\n")
-            .unwrap();
+    pub fn with_shutdown_option(mut self) -> Self {
+        self.shutdown_option = true;
+        self
+    }
 
-        loop {
-            let ticket_priority = self.get_ticket_priority_input();
-            if ticket_priority.trim() == "69" {
-                fs::remove_file(&self.queue_path).unwrap();
-                break;
-            }
+    pub fn io_handler(&self) -> &IOHandler<R, W> {
+        &self.io_handler
+    }
+
+    // runs until the station is shut down or the input ends
+    pub fn start(&mut self) -> Result<()> {
+        stop_at_input_end(self.serve())
+    }
 
-            let parsed_priority = self.parse_ticket_priority_input(&ticket_priority);
-            self.handle_enqueue(parsed_priority);
+    fn serve(&mut self) -> Result<()> {
+        self.io_handler.write(i18n::message("kiosk.welcome"))?;
+
+        loop {
+            let result = match self.io_handler.choose(&self.operation_menu())? {
+                Some(KioskOperation::Enqueue(priority)) => self.handle_enqueue(priority),
+                Some(KioskOperation::Cancel) => self.handle_cancel(),
+                Some(KioskOperation::Shutdown) => match ask_shutdown(&mut self.io_handler)? {
+                    Some(shutdown) => return self.shut_down(shutdown),
+                    None => Ok(()),
+                },
+                None => Ok(()),
+            };
+            self.io_handler.recover(result)?;
         }
     }
 
-    fn get_ticket_priority_input(&mut self) -> String {
-        self.io_handler
-            .write(
-                "\n[1] Prioritário\n\
-                [2] Normal\n\
-                \n\
-                Insira o tipo de atendimento desejado\n\
-                para entrar na fila de atendimento: ",
-            )
-            .unwrap();
-
-        self.io_handler.read_line().unwrap()
+    fn shut_down(&mut self, shutdown: Shutdown) -> Result<()> {
+        if shutdown == Shutdown::EndOfDay {
+            if let Some(archived) = self.kiosk.close_day(self.kiosk.now())? {
+                self.io_handler
+                    .write(i18n::text("shutdown.archived", &[("path", &archived)]))?;
+            }
+        }
+
+        Ok(())
     }
 
-    fn parse_ticket_priority_input(&self, priority: &str) -> TicketPriority {
-        let trimmed = priority.trim();
-        if trimmed == "1" {
-            TicketPriority::High
+    fn operation_menu(&self) -> Menu<KioskOperation> {
+        let menu = Menu::new(i18n::message("kiosk.menu"));
+
+        let menu = self
+            .kiosk
+            .categories()
+            .into_iter()
+            .fold(menu, |menu, priority| {
+                menu.option(priority.label(), KioskOperation::Enqueue(priority))
+            })
+            .option(i18n::message("kiosk.cancel_ticket"), KioskOperation::Cancel);
+
+        if self.shutdown_option {
+            menu.option(i18n::message("shutdown.option"), KioskOperation::Shutdown)
         } else {
-            TicketPriority::Normal
+            menu
         }
     }
 
-    fn handle_enqueue(&mut self, priority: TicketPriority) {
-        self.pull_file_updates();
+    fn handle_enqueue(&mut self, priority: TicketPriority) -> Result<()> {
+        let issued = match self.kiosk.issue(priority) {
+            Ok(issued) => issued,
+            Err(err) => {
+                return match err.downcast::<Refusal>() {
+                    Ok(refusal) => self.handle_refusal(refusal),
+                    Err(err) => Err(err),
+                }
+            }
+        };
 
-        let ticket = PriorityQueueTicket::new(self.ticket_code, priority);
-        self.queue.enqueue(ticket);
+        let ticket_path = self.document_printer.queue_ticket(
+            &issued.ticket,
+            &issued.estimated_wait,
+            self.kiosk.now(),
+        )?;
 
-        JsonHandler::save_as_json(&self.queue_path, &self.queue.queue()).unwrap();
+        self.io_handler
+            .write(i18n::message("kiosk.ticket_accepted"))?;
+        self.io_handler
+            .write(self.output_format.render(&issued.ticket))?;
+        self.io_handler.write(i18n::text(
+            "kiosk.ticket_pin",
+            &[("pin", &issued.ticket.pin().unwrap_or_default())],
+        ))?;
+        self.io_handler.write(i18n::text(
+            "kiosk.ticket_printed",
+            &[("path", &ticket_path)],
+        ))?;
+        self.io_handler
+            .write(i18n::plural("kiosk.people_ahead", issued.ahead, &[]))?;
+        self.io_handler.write(i18n::text(
+            "kiosk.estimated_wait",
+            &[("wait", &issued.estimated_wait)],
+        ))?;
+        Ok(())
+    }
 
-        self.ticket_code += 1;
+    fn handle_refusal(&mut self, refusal: Refusal) -> Result<()> {
+        self.io_handler.write(i18n::text(
+            "kiosk.refused",
+            &[("reason", &refusal.reason())],
+        ))?;
+
+        let book = self
+            .io_handler
+            .ask(&Prompt::yes_no(i18n::message("kiosk.offer_appointment")))?;
+        if book {
+            self.book_appointment()?;
+        }
+        Ok(())
+    }
 
-        let accepted_service_msg = "\nPedido de atendimento aceito.\n\
-                    Você será chamado(a) quando for sua vez.\nPor favor, aguarde.\n";
+    fn book_appointment(&mut self) -> Result<()> {
+        let cpf = self
+            .io_handler
+            .ask(&Prompt::cpf(i18n::message("kiosk.ask_cpf")))?;
+
+        // past appointments don't keep the pacient from booking another
+        let booked: Vec<Appointment> = self
+            .appointment_schedule
+            .query_vec(cpf.digits())
+            .unwrap_or_default();
+        let today = self.kiosk.now().date_naive();
+        if let Some(appointment) = booked
+            .iter()
+            .find(|appointment| appointment.is_upcoming(today))
+        {
+            self.io_handler.write(i18n::text(
+                "kiosk.already_scheduled",
+                &[("date", &appointment.date())],
+            ))?;
+            return Ok(());
+        }
 
-        self.io_handler.write(accepted_service_msg).unwrap();
+        let date = self
+            .io_handler
+            .ask(
+                &Prompt::date(i18n::message("kiosk.ask_appointment_date")).validate(|date| {
+                    if *date > self.kiosk.now().date_naive() {
+                        Ok(())
+                    } else {
+                        Err(i18n::message("kiosk.appointment_too_soon").to_string())
+                    }
+                }),
+            )?
+            .format("%d-%m-%Y")
+            .to_string();
+
+        // the schedule keeps one appointment per pacient, so a past one is replaced
+        let key = cpf.digits().to_string();
+        let appointment = Appointment::new(cpf, date.clone());
+        if booked.is_empty() {
+            self.appointment_schedule.insert(appointment)?;
+        } else {
+            self.appointment_schedule.update(&key, appointment)?;
+        }
+        self.io_handler
+            .write(i18n::text("kiosk.appointment_booked", &[("date", &date)]))?;
+        Ok(())
     }
 
-    fn pull_file_updates(&mut self) {
-        if let Ok(queue) = JsonHandler::read_from_json(&self.queue_path) {
-            self.queue = PriorityQueue::from(queue);
+    fn handle_cancel(&mut self) -> Result<()> {
+        let code = self
+            .io_handler
+            .ask(&Prompt::text(i18n::message("kiosk.ask_ticket_code")))?;
+        let pin = self
+            .io_handler
+            .ask(&Prompt::text(i18n::message("kiosk.ask_ticket_pin")))?;
+
+        match self.kiosk.cancel(&code, &pin)? {
+            Some(ticket) => {
+                self.io_handler.write(i18n::text(
+                    "kiosk.ticket_cancelled",
+                    &[("code", &ticket.display_code())],
+                ))?;
+            }
+            None => {
+                self.io_handler
+                    .write(i18n::message("kiosk.ticket_not_found"))?;
+            }
         }
+        Ok(())
     }
 }
@@ -0,0 +1,129 @@
+use std::fs;
+
+use anyhow::Result;
+use common::command::{CommandLine, CommandStatus};
+use common::documents::DocumentPrinter;
+use common::priority_queue::TicketPriority;
+use common::render::OutputFormat;
+use common::ticket_limits::TicketLimits;
+use pacient::commands;
+use pacient::kiosk::Kiosk;
+use serde_json::Value;
+
+mod support;
+use support::sample_kiosk;
+
+// the status and what went to stdout
+fn run(kiosk: &mut Kiosk, dir: &str, args: &[&str]) -> Result<(CommandStatus, String)> {
+    let command = CommandLine::parse(args.iter().map(|arg| arg.to_string()));
+    let output_format = OutputFormat::from_args(args.iter().map(|arg| arg.to_string()))?;
+    let document_printer = DocumentPrinter::new(format!("{}/documents", dir), None)?;
+
+    let mut stdout = Vec::new();
+    let status = commands::run(
+        &command,
+        kiosk,
+        output_format,
+        &document_printer,
+        &mut stdout,
+    )?;
+
+    Ok((status, String::from_utf8(stdout)?))
+}
+
+#[test]
+fn ticket_command_test() -> Result<()> {
+    let dir = "ticket_command_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::new())?;
+
+    let (status, stdout) = run(
+        &mut kiosk,
+        dir,
+        &["ticket", "--priority", "elderly60", "--json"],
+    )?;
+    assert_eq!(status.code(), 0);
+    let ticket: Value = serde_json::from_str(&stdout)?;
+    assert_eq!(ticket["code"], "P001");
+    assert_eq!(ticket["pin"].as_str().map(str::len), Some(4));
+
+    // the old `High` priority isn't configured, it takes the top preferential
+    // category rather than an emergency
+    let (status, stdout) = run(&mut kiosk, dir, &["ticket", "--priority", "high", "--json"])?;
+    assert_eq!(status.code(), 0);
+    let ticket: Value = serde_json::from_str(&stdout)?;
+    assert_eq!(ticket["code"], "P002");
+    assert_eq!(ticket["priority"], TicketPriority::Elderly80.label());
+
+    for args in [
+        &["ticket", "--priority", "vip"][..],
+        &["ticket"],
+        &["ticket", "--priority", "Emergency", "extra"],
+    ] {
+        let (status, stdout) = run(&mut kiosk, dir, args)?;
+        assert_eq!(status.code(), 2);
+        assert_eq!(stdout, "");
+    }
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn refused_ticket_command_test() -> Result<()> {
+    let dir = "refused_ticket_command_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::from_config(None, Some("1"), None)?)?;
+
+    assert_eq!(
+        run(&mut kiosk, dir, &["ticket", "--priority", "Normal"])?
+            .0
+            .code(),
+        0
+    );
+    let (status, stdout) = run(&mut kiosk, dir, &["ticket", "--priority", "Normal"])?;
+    assert_eq!(status.code(), 4);
+    assert_eq!(stdout, "");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn cancel_command_test() -> Result<()> {
+    let dir = "cancel_command_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::new())?;
+
+    let (_, stdout) = run(
+        &mut kiosk,
+        dir,
+        &["ticket", "--priority", "Normal", "--json"],
+    )?;
+    let ticket: Value = serde_json::from_str(&stdout)?;
+    let pin = ticket["pin"].as_str().unwrap().to_string();
+    let wrong_pin = if pin == "0000" { "0001" } else { "0000" };
+
+    let (status, stdout) = run(&mut kiosk, dir, &["cancel", "N001", "--pin", wrong_pin])?;
+    assert_eq!(status.code(), 3);
+    assert_eq!(stdout, "");
+    assert_eq!(run(&mut kiosk, dir, &["cancel", "N001"])?.0.code(), 2);
+
+    let (status, stdout) = run(
+        &mut kiosk,
+        dir,
+        &["cancel", "n001", "--pin", &pin, "--json"],
+    )?;
+    assert_eq!(status.code(), 0);
+    let cancelled: Value = serde_json::from_str(&stdout)?;
+    assert_eq!(cancelled["code"], "N001");
+    assert_eq!(
+        run(&mut kiosk, dir, &["cancel", "N001", "--pin", &pin])?
+            .0
+            .code(),
+        3
+    );
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
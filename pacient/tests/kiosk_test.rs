@@ -0,0 +1,112 @@
+use std::fs;
+use std::thread;
+
+use anyhow::Result;
+use common::json_handler::JsonHandler;
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority};
+use common::ticket_limits::{Refusal, TicketLimits};
+
+mod support;
+use support::sample_kiosk;
+
+#[test]
+fn cancel_needs_the_pin_test() -> Result<()> {
+    let dir = "cancel_needs_the_pin_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::new())?;
+
+    let issued = kiosk.issue(TicketPriority::Normal)?;
+    let code = issued.ticket.display_code();
+    let pin = issued.ticket.pin().unwrap().to_string();
+    assert_eq!(pin.len(), 4);
+
+    // the code alone, announced to everyone, isn't enough
+    let wrong_pin = if pin == "0000" { "0001" } else { "0000" };
+    assert!(kiosk.cancel(&code, wrong_pin)?.is_none());
+    assert!(kiosk.cancel(&code, "")?.is_none());
+
+    let cancelled = kiosk.cancel(&code.to_lowercase(), &format!(" {} ", pin))?;
+    assert_eq!(
+        cancelled.map(|ticket| ticket.code()),
+        Some(issued.ticket.code())
+    );
+    assert!(kiosk.cancel(&code, &pin)?.is_none());
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn unreadable_queue_test() -> Result<()> {
+    let dir = "unreadable_queue_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::new())?;
+    let queue_path = format!("{}/pacient_queue.json", dir);
+
+    // with no queue file yet, the queue is empty
+    assert_eq!(kiosk.issue(TicketPriority::Normal)?.ahead, 0);
+
+    // a queue that can't be read is neither taken for an empty one nor
+    // overwritten
+    fs::write(&queue_path, "{ not a queue")?;
+    assert!(kiosk.issue(TicketPriority::Normal).is_err());
+    assert!(kiosk.cancel("N001", "0000").is_err());
+    assert_eq!(fs::read_to_string(&queue_path)?, "{ not a queue");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn kiosks_share_the_queue_file_test() -> Result<()> {
+    let dir = "kiosks_share_the_queue_file_test";
+    fs::create_dir_all(dir)?;
+
+    // two kiosks issuing at once, each reading and saving the same queue
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut kiosk = sample_kiosk(dir, TicketLimits::new()).unwrap();
+                for _ in 0..20 {
+                    kiosk.issue(TicketPriority::Normal).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // no kiosk saved over a ticket the other had just issued
+    let mut queue: PriorityQueue<PriorityQueueTicket> =
+        PriorityQueue::with_settings(&QueueSettings::from_config(None, None)?);
+    queue.restore(JsonHandler::read_document(&format!(
+        "{}/pacient_queue.json",
+        dir
+    ))?);
+    assert_eq!(queue.len(), 40);
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn called_tickets_count_test() -> Result<()> {
+    let dir = "called_tickets_count_test";
+    let mut kiosk = sample_kiosk(dir, TicketLimits::from_config(None, Some("1"), None)?)?;
+
+    kiosk.issue(TicketPriority::Normal)?;
+    // the desk called the ticket, so it left the queue
+    fs::remove_file(format!("{}/pacient_queue.json", dir))?;
+    let refused = kiosk.issue(TicketPriority::Normal).err().unwrap();
+    assert_eq!(refused.downcast::<Refusal>()?, Refusal::DailyCapacity(1));
+
+    // a history that can't be read isn't taken for an empty one
+    fs::write(format!("{}/ticket_history.json.db", dir), "{ not a history")?;
+    assert!(kiosk.issue(TicketPriority::Normal).is_err());
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
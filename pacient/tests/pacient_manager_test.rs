@@ -1,3 +1,53 @@
+use anyhow::Result;
+use common::database::Database;
+use common::dequeue_policy::parse_policy;
+use common::documents::DocumentPrinter;
+use common::priority_queue::QueueSettings;
+use common::render::OutputFormat;
+use common::ticket_limits::TicketLimits;
+use common::ticket_sequence::TicketSequence;
+use common::transcript::Session;
+use pacient::kiosk::Kiosk;
+use pacient::pacient_manager::PacientManager;
+
 // test if the ticket priority selection is working fine
 
 // test if when a ticket is get successfully, the file is created/updated
+
+// a recorded kiosk session: one ticket issued with two already waiting, then
+// the first of them cancelled with its PIN
+#[test]
+fn recorded_session_test() -> Result<()> {
+    let mut session = Session::Replay(format!(
+        "{}/tests/sessions/pacient_manager.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .start()?;
+
+    // the settings of the .env the session was recorded with
+    let queue_settings = QueueSettings::from_config(None, Some("30"))?
+        .with_policy(parse_policy(Some("ratio:2:1"))?)
+        .with_clock(session.clock());
+    let kiosk = Kiosk::new(
+        queue_settings,
+        session.data_file("pacient_queue.json".to_string())?,
+        Database::new(session.data_file("ticket_history.json.db".to_string())?)?,
+        TicketSequence::new(session.data_file("ticket_sequence.json".to_string())?),
+        TicketLimits::new(),
+    )
+    .with_clock(session.clock())
+    .with_pin_seed(session.seed());
+    let document_printer = DocumentPrinter::new(session.data_dir("documents".to_string())?, None)?
+        .with_clock(session.clock());
+
+    let mut manager = PacientManager::new(
+        session.io_handler()?,
+        kiosk,
+        Database::new(session.data_file("appointment_schedule.json.db".to_string())?)?,
+        OutputFormat::Text,
+        document_printer,
+    );
+    manager.start()?;
+
+    session.finish(manager.io_handler())
+}
@@ -0,0 +1,23 @@
+// helpers shared by the test files; not every file uses all of them
+#![allow(dead_code)]
+
+use std::fs;
+
+use anyhow::Result;
+use common::database::Database;
+use common::priority_queue::QueueSettings;
+use common::ticket_limits::TicketLimits;
+use common::ticket_sequence::TicketSequence;
+use pacient::kiosk::Kiosk;
+
+// a kiosk keeping its files in `dir`
+pub fn sample_kiosk(dir: &str, ticket_limits: TicketLimits) -> Result<Kiosk> {
+    fs::create_dir_all(dir)?;
+    Ok(Kiosk::new(
+        QueueSettings::from_config(None, None)?,
+        format!("{}/pacient_queue.json", dir),
+        Database::new(format!("{}/ticket_history.json.db", dir))?,
+        TicketSequence::new(format!("{}/ticket_sequence.json", dir)),
+        ticket_limits,
+    ))
+}
@@ -0,0 +1,116 @@
+use std::io::Write;
+
+use anyhow::Result;
+use common::appointment::Appointment;
+use common::command::{CommandLine, CommandStatus};
+use common::cpf::Cpf;
+use common::i18n;
+use common::io_handler::Prompt;
+use common::priority_queue::PriorityQueue;
+use common::render::OutputFormat;
+
+use crate::reception::Reception;
+
+// runs a subcommand without the reception menus: records go to stdout,
+// messages to stderr
+pub fn run<W: Write>(
+    command: &CommandLine,
+    reception: &mut Reception,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    match command.words().as_slice() {
+        ["appointments", "list"] => list_appointments(command, reception, output_format, stdout),
+        ["patient" | "pacient", "show", cpf] => show_pacient(cpf, reception, output_format, stdout),
+        ["queue", "list"] => list_queue(reception, output_format, stdout),
+        ["queue", "next"] => show_next_ticket(reception, output_format, stdout),
+        _ => {
+            eprintln!("{}", i18n::message("cli.receptionist_usage"));
+            Ok(CommandStatus::Usage)
+        }
+    }
+}
+
+fn list_appointments<W: Write>(
+    command: &CommandLine,
+    reception: &Reception,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    let appointments = match command.option("--date") {
+        Some(date) => match Prompt::date("--date").parse(date) {
+            Ok(date) => reception.appointments_on(date)?,
+            Err(err) => {
+                eprintln!("{}", err);
+                return Ok(CommandStatus::Usage);
+            }
+        },
+        None => reception.appointment_schedule().query_all()?,
+    };
+
+    let appointments: PriorityQueue<Appointment> = appointments.into_iter().collect();
+    write!(
+        stdout,
+        "{}",
+        output_format.render_all(&appointments.into_vec())
+    )?;
+
+    Ok(CommandStatus::Done)
+}
+
+fn show_pacient<W: Write>(
+    cpf: &str,
+    reception: &Reception,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    let pacient = Cpf::parse(cpf)
+        .ok()
+        .and_then(|cpf| reception.find_account(&cpf));
+
+    match pacient {
+        Some(pacient) => {
+            write!(stdout, "{}", output_format.render(&pacient))?;
+            Ok(CommandStatus::Done)
+        }
+        None => {
+            eprintln!("{}", i18n::message("reception.account_not_found"));
+            Ok(CommandStatus::NotFound)
+        }
+    }
+}
+
+fn list_queue<W: Write>(
+    reception: &mut Reception,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    reception.refresh()?;
+    write!(
+        stdout,
+        "{}",
+        output_format.render_all(&reception.estimated_waits()?)
+    )?;
+
+    Ok(CommandStatus::Done)
+}
+
+// only shows the ticket, calling it is left to the desk
+fn show_next_ticket<W: Write>(
+    reception: &mut Reception,
+    output_format: OutputFormat,
+    stdout: &mut W,
+) -> Result<CommandStatus> {
+    reception.refresh()?;
+
+    match reception.waiting_tickets().first() {
+        Some(ticket) => {
+            write!(stdout, "{}", output_format.render(*ticket))?;
+            Ok(CommandStatus::Done)
+        }
+        None => {
+            eprintln!("{}", i18n::message("reception.queue_empty").trim());
+            Ok(CommandStatus::NotFound)
+        }
+    }
+}
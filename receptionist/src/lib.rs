@@ -1 +1,4 @@
+pub mod commands;
+pub mod reception;
 pub mod service_manager;
+pub mod tui;
@@ -1,32 +1,106 @@
 use std::env;
+use std::io;
+use std::process::ExitCode;
 
 use anyhow::Result;
+use common::command::CommandLine;
 use common::database::Database;
-use common::io_handler::{DefaultIOHandler, IOHandler};
+use common::dequeue_policy::parse_policy;
+use common::documents::DocumentPrinter;
+use common::i18n::{self, Locale};
+use common::priority_queue::QueueSettings;
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::shutdown;
+use common::transcript::Session;
 use dotenv::dotenv;
 
+use receptionist::commands;
+use receptionist::reception::{Reception, DEFAULT_MAX_RECALLS};
 use receptionist::service_manager::ServiceManager;
+use receptionist::tui::ReceptionTui;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     dotenv().ok();
+    i18n::set_locale(Locale::from_config(env::var("LOCALE").ok().as_deref())?);
+    shutdown::handle_interrupts()?;
 
-    let pacient_queue_file_path = env::var("PACIENT_QUEUE_FILE_PATH")?;
-    let dentist_queue_file_path = env::var("DENTIST_QUEUE_FILE_PATH")?;
+    let command = CommandLine::parse(env::args().skip(1));
+    let output_format = OutputFormat::from_args(env::args().skip(1))?;
+    let mut session = Session::from_args(env::args().skip(1))?.start()?;
+    let pacient_queue_file_path = session.data_file(env::var("PACIENT_QUEUE_FILE_PATH")?)?;
+    let queue_settings = QueueSettings::from_config(
+        env::var("PRIORITY_CATEGORIES").ok().as_deref(),
+        env::var("PRIORITY_AGING_MINUTES").ok().as_deref(),
+    )?
+    .with_policy(parse_policy(
+        env::var("RECEPTIONIST_DEQUEUE_POLICY").ok().as_deref(),
+    )?)
+    .with_clock(session.clock());
 
-    let io_handler = IOHandler::default();
+    let dentist_queues = QueueRouter::from_config(
+        &QueueRouter::queues_config(
+            env::var("DENTIST_QUEUES").ok().as_deref(),
+            env::var("DENTIST_QUEUE_FILE_PATH").ok().as_deref(),
+        )?,
+        env::var("DENTIST_QUEUE_ROUTES").ok().as_deref(),
+        &queue_settings,
+    )?
+    .with_files(|path| session.data_file(path))?;
 
-    let pacient_accounts = Database::new(env::var("PACIENT_ACCOUNTS_DATABASE")?);
-    let service_sheets_history = Database::new(env::var("SERVICE_SHEETS_HISTORY_DATABASE")?);
-    let appointment_schedule = Database::new(env::var("APPOINTMENT_SCHEDULE_DATABASE")?);
+    let pacient_accounts =
+        Database::new(session.data_file(env::var("PACIENT_ACCOUNTS_DATABASE")?)?)?;
+    let service_sheets_history =
+        Database::new(session.data_file(env::var("SERVICE_SHEETS_HISTORY_DATABASE")?)?)?;
+    let appointment_schedule =
+        Database::new(session.data_file(env::var("APPOINTMENT_SCHEDULE_DATABASE")?)?)?;
+    let payments = Database::new(session.data_file(env::var("PAYMENTS_DATABASE")?)?)?;
+    let ticket_history = Database::new(session.data_file(env::var("TICKET_HISTORY_DATABASE")?)?)?;
 
-    let mut manager = ServiceManager::new(
-        io_handler,
+    let document_printer = DocumentPrinter::new(
+        session.data_dir(env::var("DOCUMENTS_DIR")?)?,
+        env::var("DOCUMENT_TEMPLATES_DIR").ok(),
+    )?
+    .with_clock(session.clock());
+
+    let mut reception = Reception::new(
         pacient_queue_file_path,
-        dentist_queue_file_path,
+        dentist_queues,
+        queue_settings,
         pacient_accounts,
         service_sheets_history,
         appointment_schedule,
+        ticket_history,
+        match env::var("TICKET_MAX_RECALLS") {
+            Ok(recalls) if !recalls.trim().is_empty() => recalls.trim().parse()?,
+            _ => DEFAULT_MAX_RECALLS,
+        },
+    )
+    .with_clock(session.clock());
+
+    if !command.is_interactive() {
+        return Ok(
+            commands::run(&command, &mut reception, output_format, &mut io::stdout())?.into(),
+        );
+    }
+
+    // the full-screen interface covers the attendance desk, the line-based
+    // one everything else
+    if command.flag("--tui") {
+        ReceptionTui::new(reception).start()?;
+        return Ok(shutdown::exit_code());
+    }
+
+    let mut manager = ServiceManager::new(
+        session.io_handler()?,
+        reception,
+        payments,
+        output_format,
+        document_printer,
+        session.data_dir(env::var("REPORTS_DIR")?)?,
     );
-    manager.start();
-    Ok(())
+    let result = manager.start();
+    result.and(session.finish(manager.io_handler()))?;
+
+    Ok(shutdown::exit_code())
 }
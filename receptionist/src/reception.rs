@@ -0,0 +1,272 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
+use common::appointment::Appointment;
+use common::clock::{Clock, SystemClock};
+use common::cpf::Cpf;
+use common::database::{Database, GetKeyAttribute};
+use common::file_lock::FileLock;
+use common::i18n;
+use common::json_handler::JsonHandler;
+use common::pacient_account::Pacient;
+use common::priority_queue::{Priority, PriorityQueue, PriorityQueueTicket, QueueSettings};
+use common::queue_router::QueueRouter;
+use common::render::{Field, Render};
+use common::service_sheet::{ServiceSheet, SheetWithPriority};
+use common::shutdown::archive_queue;
+use common::ticket_record::{TicketOutcome, TicketRecord};
+use common::wait_estimator::{describe_wait, WaitEstimator, DEFAULT_MINUTES_PER_CALL};
+
+// times a ticket is called again when TICKET_MAX_RECALLS is left empty
+pub const DEFAULT_MAX_RECALLS: usize = 2;
+
+// a ticket in the queue along with how long it is expected to wait
+pub struct WaitingTicket {
+    pub ticket: PriorityQueueTicket,
+    pub wait_minutes: i64,
+}
+
+impl Render for WaitingTicket {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = self.ticket.fields();
+        fields.push(
+            Field::number(
+                "estimated_wait",
+                i18n::message("field.estimated_wait"),
+                self.wait_minutes,
+            )
+            .shown_as(describe_wait(self.wait_minutes)),
+        );
+        fields
+    }
+}
+
+// what the reception desk does with tickets, accounts and the dentist queues,
+// whichever front-end the receptionist works on
+pub struct Reception {
+    pacient_queue_path: String,
+    pacient_queue: PriorityQueue<PriorityQueueTicket>,
+    dentist_queues: QueueRouter,
+    pacient_accounts: Database,
+    service_sheets_history: Database,
+    appointment_schedule: Database,
+    ticket_history: Database,
+    max_recalls: usize,
+    clock: Rc<dyn Clock>,
+}
+
+impl Reception {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        pacient_queue_path: String,
+        dentist_queues: QueueRouter,
+        queue_settings: QueueSettings,
+        pacient_accounts: Database,
+        service_sheets_history: Database,
+        appointment_schedule: Database,
+        ticket_history: Database,
+        max_recalls: usize,
+    ) -> Self {
+        Self {
+            pacient_queue_path,
+            pacient_queue: PriorityQueue::with_settings(&queue_settings),
+            dentist_queues,
+            pacient_accounts,
+            service_sheets_history,
+            appointment_schedule,
+            ticket_history,
+            max_recalls,
+            clock: Rc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // the time at this desk
+    pub fn now(&self) -> DateTime<Local> {
+        self.clock.local_now()
+    }
+
+    pub fn pacient_accounts(&self) -> &Database {
+        &self.pacient_accounts
+    }
+
+    pub fn service_sheets_history(&self) -> &Database {
+        &self.service_sheets_history
+    }
+
+    pub fn appointment_schedule(&self) -> &Database {
+        &self.appointment_schedule
+    }
+
+    pub fn ticket_history(&self) -> &Database {
+        &self.ticket_history
+    }
+
+    pub fn dentist_queues(&self) -> &QueueRouter {
+        &self.dentist_queues
+    }
+
+    pub fn dentist_queues_mut(&mut self) -> &mut QueueRouter {
+        &mut self.dentist_queues
+    }
+
+    // reads every queue file again, the kiosk and the dentist stations
+    // change them in the meantime
+    pub fn refresh(&mut self) -> Result<()> {
+        self.load_pacient_queue()?;
+        self.dentist_queues.refresh()
+    }
+
+    // in the order they would be called, as of the last refresh
+    pub fn waiting_tickets(&self) -> Vec<&PriorityQueueTicket> {
+        self.pacient_queue.queue()
+    }
+
+    // the waiting tickets with the wait the past calls suggest, for the
+    // displays of the waiting room
+    pub fn estimated_waits(&self) -> Result<Vec<WaitingTicket>> {
+        let history: Vec<TicketRecord> = self.ticket_history.query_all()?;
+        let estimator = WaitEstimator::from_history(&history, DEFAULT_MINUTES_PER_CALL)
+            .with_categories(self.pacient_queue.categories());
+
+        Ok(estimator
+            .estimate_queue(&self.pacient_queue)
+            .into_iter()
+            .map(|(ticket, wait_minutes)| WaitingTicket {
+                ticket: ticket.clone(),
+                wait_minutes,
+            })
+            .collect())
+    }
+
+    pub fn call_next(&mut self) -> Result<Option<PriorityQueueTicket>> {
+        // no kiosk may issue a ticket until the queue is saved again
+        let _lock = FileLock::acquire(&self.pacient_queue_path)?;
+        // the policy turn is saved in the queue file along with the tickets
+        self.load_pacient_queue()?;
+        let mut ticket = match self.pacient_queue.dequeue() {
+            Some(ticket) => ticket,
+            None => return Ok(None),
+        };
+
+        JsonHandler::save_as_json(&self.pacient_queue_path, &self.pacient_queue)?;
+        ticket.lifecycle_mut().mark_called(self.now());
+
+        Ok(Some(ticket))
+    }
+
+    // returns false when the ticket ran out of calls and was marked as a
+    // no-show instead
+    pub fn call_again(&mut self, ticket: &mut PriorityQueueTicket) -> Result<bool> {
+        if ticket.recalls() < self.max_recalls {
+            ticket.recall(self.now());
+            return Ok(true);
+        }
+
+        self.ticket_history
+            .insert(TicketRecord::new(ticket).with_outcome(TicketOutcome::NoShow))?;
+        Ok(false)
+    }
+
+    pub fn return_to_queue(&mut self, ticket: PriorityQueueTicket) -> Result<()> {
+        let record = TicketRecord::new(&ticket).with_outcome(TicketOutcome::Requeued);
+
+        // other tickets may have been issued while this one was being called
+        let _lock = FileLock::acquire(&self.pacient_queue_path)?;
+        self.load_pacient_queue()?;
+        self.pacient_queue.requeue(ticket);
+        JsonHandler::save_as_json(&self.pacient_queue_path, &self.pacient_queue)?;
+
+        self.ticket_history.insert(record)
+    }
+
+    // archives the ticket queue and the dentist queues when the office
+    // closes, the paths they were moved to are returned
+    pub fn close_day(&mut self, at: DateTime<Local>) -> Result<Vec<String>> {
+        let mut archived: Vec<String> = {
+            let _lock = FileLock::acquire(&self.pacient_queue_path)?;
+            archive_queue(&self.pacient_queue_path, at)?
+                .into_iter()
+                .collect()
+        };
+        self.pacient_queue.reload(Vec::new());
+        archived.extend(self.dentist_queues.archive(at)?);
+
+        Ok(archived)
+    }
+
+    // the records saved before CPFs were checked whose CPF isn't valid, so
+    // the operator knows to fix them
+    pub fn legacy_cpfs(&self) -> Result<Vec<Cpf>> {
+        let accounts: Vec<Pacient> = self.pacient_accounts.query_all()?;
+        let appointments: Vec<Appointment> = self.appointment_schedule.query_all()?;
+
+        Ok(accounts
+            .iter()
+            .map(Pacient::cpf)
+            .chain(appointments.iter().map(Appointment::cpf))
+            .filter(|cpf| cpf.is_legacy())
+            .cloned()
+            .collect())
+    }
+
+    pub fn find_account(&self, cpf: &Cpf) -> Option<Pacient> {
+        self.pacient_accounts.query(cpf.digits()).ok()
+    }
+
+    // creates the account or updates the one with the same CPF
+    pub fn save_account(&self, pacient: Pacient) -> Result<()> {
+        let key = pacient.get_key_attribute();
+        match self.find_account(pacient.cpf()) {
+            Some(_) => self.pacient_accounts.update(&key, pacient),
+            None => self.pacient_accounts.insert(pacient),
+        }
+    }
+
+    // returns the name of the queue the sheet went to
+    pub fn send_to_dentist(
+        &mut self,
+        ticket: &PriorityQueueTicket,
+        sheet: ServiceSheet,
+    ) -> Result<String> {
+        self.service_sheets_history.insert(sheet.clone())?;
+
+        let mut sheet = SheetWithPriority::new(sheet, ticket.priority());
+        sheet.lifecycle_mut().mark_issued(self.now());
+        self.dentist_queues.enqueue(sheet)
+    }
+
+    // the ticket leaves the system once its pacient is sent to the dentist
+    pub fn finish_ticket(&self, ticket: &mut PriorityQueueTicket) -> Result<()> {
+        ticket.lifecycle_mut().mark_finished(self.now());
+        self.ticket_history.insert(TicketRecord::new(ticket))
+    }
+
+    pub fn appointments_on(&self, date: NaiveDate) -> Result<Vec<Appointment>> {
+        let date = date.format("%d-%m-%Y").to_string();
+        let appointments: Vec<Appointment> = self.appointment_schedule.query_all()?;
+
+        Ok(appointments
+            .into_iter()
+            .filter(|appointment| appointment.date() == date)
+            .collect())
+    }
+
+    fn load_pacient_queue(&mut self) -> Result<()> {
+        // no ticket was issued yet while the kiosk hasn't saved the queue
+        if Path::new(&self.pacient_queue_path).exists() {
+            self.pacient_queue
+                .restore(JsonHandler::read_document(&self.pacient_queue_path)?);
+        } else {
+            self.pacient_queue.reload(Vec::new());
+        }
+
+        Ok(())
+    }
+}
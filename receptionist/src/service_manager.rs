@@ -1,30 +1,62 @@
 use std::fs;
 use std::io;
+use std::path::Path;
 
-use chrono::Local;
+use anyhow::Result;
 use common::appointment::Appointment;
+use common::cpf::Cpf;
 use common::database::{Database, GetKeyAttribute};
-use common::io_handler::IOHandler;
-use common::json_handler::JsonHandler;
-use common::pacient_account::{Address, Pacient};
-use common::priority_queue::{Priority, PriorityQueue, PriorityQueueTicket, TicketPriority};
-use common::service_sheet::{ServiceSheet, SheetWithPriority};
-
+use common::documents::DocumentPrinter;
+use common::i18n;
+use common::ics_handler::{ConflictKind, IcsHandler};
+use common::io_handler::{stop_at_input_end, IOHandler, Interrupted, Menu, Prompt};
+use common::pacient_account::Pacient;
+use common::payment::{Payment, PaymentMethod, Procedure};
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket};
+use common::render::OutputFormat;
+use common::report::DailyReport;
+use common::service_sheet::ServiceSheet;
+use common::shutdown::{ask_shutdown, Shutdown};
+use common::ticket_record::TicketRecord;
+
+use crate::reception::Reception;
+
+#[derive(Clone)]
 enum OperationMode {
     AttendPacient,
     ProcessPayment,
     ManageAppointment,
+    GenerateDailyReport,
+    TransferPacient,
     GetTodaysPayments,
+    Shutdown,
+}
+
+#[derive(Clone)]
+enum CallAnswer {
+    CameForward,
+    CallAgain,
+    ReturnToQueue,
+}
+
+#[derive(Clone)]
+enum AppointmentOperation {
+    Make,
+    Update,
+    Delete,
+    Show,
+    Export,
+    Import,
 }
 
 pub struct ServiceManager<R, W> {
     io_handler: IOHandler<R, W>,
-    pacient_queue_path: String,
-    dentist_queue_path: String,
-    pacient_accounts: Database,
-    service_sheets_history: Database,
-    appointment_schedule: Database,
+    reception: Reception,
+    payments: Database,
     payments_of_the_day: Vec<usize>,
+    output_format: OutputFormat,
+    document_printer: DocumentPrinter,
+    reports_dir: String,
 }
 
 impl<R, W> ServiceManager<R, W>
@@ -34,400 +66,653 @@ where
 {
     pub fn new(
         io_handler: IOHandler<R, W>,
-        pacient_queue_path: String,
-        dentist_queue_path: String,
-        pacient_accounts: Database,
-        service_sheets_history: Database,
-        appointment_schedule: Database,
+        reception: Reception,
+        payments: Database,
+        output_format: OutputFormat,
+        document_printer: DocumentPrinter,
+        reports_dir: String,
     ) -> Self {
         Self {
             io_handler,
-            pacient_queue_path,
-            dentist_queue_path,
-            pacient_accounts,
-            service_sheets_history,
-            appointment_schedule,
+            reception,
+            payments,
             payments_of_the_day: Vec::new(),
+            output_format,
+            document_printer,
+            reports_dir,
         }
     }
 
-    pub fn start(&mut self) {
-        self.io_handler
-            .write("Obrigado por trabalhar conosco na SOS Dentes!\n")
-            .unwrap();
-
-        loop {
-            let operation_input = self.get_operation_input();
+    pub fn io_handler(&self) -> &IOHandler<R, W> {
+        &self.io_handler
+    }
 
-            if operation_input.trim() == "69" {
-                fs::remove_file(&self.dentist_queue_path).unwrap();
-                break;
-            }
+    // runs until the station is shut down, interrupted or the input ends
+    pub fn start(&mut self) -> Result<()> {
+        let result = self.serve();
 
-            match self.parse_operation_input(&operation_input) {
-                OperationMode::AttendPacient => self.attend_pacient(),
-                OperationMode::ProcessPayment => self.process_payment(),
-                OperationMode::ManageAppointment => self.manage_appointments(),
-                OperationMode::GetTodaysPayments => self.get_todays_payments(),
-            }
+        // only this station knows what it took today, so it is shown before
+        // an interrupted station stops
+        if result.as_ref().is_err_and(|err| err.is::<Interrupted>())
+            && !self.payments_of_the_day.is_empty()
+        {
+            self.get_todays_payments()?;
         }
+        stop_at_input_end(result)
     }
 
-    fn get_operation_input(&mut self) -> String {
-        self.io_handler
-            .write(
-                "\n[1] Atender paciente na fila de espera\n\
-                [2] Receber pagamento e finalizar atendimento\n\
-                [3] Gerenciar consultas\n\
-                \n\
-                Insira o tipo de operação que deseja fazer: ",
-            )
-            .unwrap();
+    fn serve(&mut self) -> Result<()> {
+        self.io_handler.write(i18n::message("reception.welcome"))?;
+        let legacy_cpfs = self.reception.legacy_cpfs()?;
+        if !legacy_cpfs.is_empty() {
+            let cpfs: Vec<String> = legacy_cpfs.iter().map(Cpf::to_string).collect();
+            self.io_handler.write(i18n::plural(
+                "reception.legacy_cpfs",
+                cpfs.len(),
+                &[("cpfs", &cpfs.join(", "))],
+            ))?;
+        }
 
-        self.io_handler.read_line().unwrap()
-    }
+        let menu = Menu::new(i18n::message("reception.menu"))
+            .option(
+                i18n::message("reception.attend_pacient"),
+                OperationMode::AttendPacient,
+            )
+            .option(
+                i18n::message("reception.process_payment"),
+                OperationMode::ProcessPayment,
+            )
+            .option(
+                i18n::message("reception.manage_appointments"),
+                OperationMode::ManageAppointment,
+            )
+            .option(
+                i18n::message("reception.daily_report"),
+                OperationMode::GenerateDailyReport,
+            )
+            .option(
+                i18n::message("reception.transfer_pacient"),
+                OperationMode::TransferPacient,
+            )
+            .option(
+                i18n::message("reception.todays_payments"),
+                OperationMode::GetTodaysPayments,
+            )
+            .option(i18n::message("shutdown.option"), OperationMode::Shutdown);
 
-    fn parse_operation_input(&self, operation: &str) -> OperationMode {
-        let trimmed = operation.trim();
-        if trimmed == "1" {
-            OperationMode::AttendPacient
-        } else if trimmed == "2" {
-            OperationMode::ProcessPayment
-        } else if trimmed == "3" {
-            OperationMode::ManageAppointment
-        } else {
-            OperationMode::GetTodaysPayments
+        loop {
+            let result = match self.io_handler.choose(&menu)? {
+                Some(OperationMode::AttendPacient) => self.attend_pacient(),
+                Some(OperationMode::ProcessPayment) => self.process_payment(),
+                Some(OperationMode::ManageAppointment) => self.manage_appointments(),
+                Some(OperationMode::GenerateDailyReport) => self.generate_daily_report(),
+                Some(OperationMode::TransferPacient) => self.transfer_pacient(),
+                Some(OperationMode::GetTodaysPayments) => self.get_todays_payments(),
+                Some(OperationMode::Shutdown) => match ask_shutdown(&mut self.io_handler)? {
+                    Some(shutdown) => return self.shut_down(shutdown),
+                    None => Ok(()),
+                },
+                None => Ok(()),
+            };
+            self.io_handler.recover(result)?;
         }
     }
 
-    fn attend_pacient(&mut self) {
-        match self.get_next_pacient() {
-            Some(ticket) => {
+    fn shut_down(&mut self, shutdown: Shutdown) -> Result<()> {
+        if shutdown == Shutdown::EndOfDay {
+            for archived in self.reception.close_day(self.reception.now())? {
                 self.io_handler
-                    .write(&format!("Código do próximo paciente: {}\n", ticket.code()))
-                    .unwrap();
-
-                self.io_handler
-                    .write(
-                        "\n[1] Sim\n\
-                        [2] Não\n\
-                        \n\
-                        O paciente já possui conta? ",
-                    )
-                    .unwrap();
-                let has_account = self.io_handler.read_line().unwrap();
-
-                let pacient = if has_account.trim() == "1" {
-                    let account = self.get_pacient_account();
-                    self.check_pacient_data(account)
-                } else {
-                    let key = self.create_pacient_account();
-                    self.pacient_accounts.query(&key).unwrap()
-                };
-
-                let sheet = self.create_service_sheet(pacient);
-                self.enqueue_pacient_in_dentist_queue(sheet, ticket.priority());
+                    .write(i18n::text("shutdown.archived", &[("path", &archived)]))?;
             }
+        }
+
+        Ok(())
+    }
+
+    fn attend_pacient(&mut self) -> Result<()> {
+        let mut ticket = match self.reception.call_next()? {
+            Some(ticket) => ticket,
             None => {
-                self.io_handler
-                    .write("\nNão há ticket de pacientes na fila!\n")
-                    .unwrap();
+                return self
+                    .io_handler
+                    .write(i18n::message("reception.queue_empty"));
+            }
+        };
+
+        let sent = match self.send_to_dentist(&mut ticket) {
+            Ok(sent) => sent,
+            Err(err) => {
+                // the ticket isn't lost when something goes wrong on the way
+                self.reception.return_to_queue(ticket)?;
+                return Err(err);
             }
+        };
+
+        if sent {
+            self.reception.finish_ticket(&mut ticket)?;
         }
+
+        Ok(())
     }
 
-    fn get_next_pacient(&self) -> Option<PriorityQueueTicket> {
-        let mut queue: Vec<PriorityQueueTicket> =
-            JsonHandler::read_from_json(&self.pacient_queue_path).unwrap();
-        if queue.is_empty() {
-            return None;
+    // returns false when the pacient never came forward
+    fn send_to_dentist(&mut self, ticket: &mut PriorityQueueTicket) -> Result<bool> {
+        if !self.wait_for_pacient(ticket)? {
+            return Ok(false);
         }
-        let result = queue.remove(0);
+        ticket.lifecycle_mut().mark_started(self.reception.now());
 
-        JsonHandler::save_as_json(&self.pacient_queue_path, &queue).unwrap();
+        let has_account = self
+            .io_handler
+            .ask(&Prompt::yes_no(i18n::message("reception.has_account")))?;
 
-        Some(result)
-    }
+        let pacient = if has_account {
+            let account = self.get_pacient_account()?;
+            self.check_pacient_data(account)?
+        } else {
+            self.create_pacient_account()?
+        };
 
-    fn get_pacient_account(&mut self) -> Pacient {
-        self.io_handler
-            .write("Realizando busca por paciente...\n")
-            .unwrap();
+        let sheet = self.create_service_sheet(pacient)?;
+        let queue = self.reception.send_to_dentist(ticket, sheet)?;
 
-        self.io_handler.write("Insira o CPF do paciente: ").unwrap();
-        let cpf = self.io_handler.read_line().unwrap();
+        self.io_handler
+            .write(i18n::text("reception.sent_to_queue", &[("queue", &queue)]))?;
 
-        self.pacient_accounts.query(cpf.trim()).unwrap()
+        Ok(true)
     }
 
-    fn check_pacient_data(&mut self, mut pacient: Pacient) -> Pacient {
-        self.io_handler.write("Dados do paciente:\n").unwrap();
-        self.io_handler.write(&pacient).unwrap();
+    // calls the ticket until its pacient comes forward, returns false when
+    // the ticket was put back in the queue or marked as a no-show
+    fn wait_for_pacient(&mut self, ticket: &mut PriorityQueueTicket) -> Result<bool> {
+        let menu = Menu::new(i18n::message("reception.came_forward"))
+            .option(
+                i18n::message("reception.pacient_came"),
+                CallAnswer::CameForward,
+            )
+            .option(i18n::message("reception.call_again"), CallAnswer::CallAgain)
+            .option(
+                i18n::message("reception.return_to_queue"),
+                CallAnswer::ReturnToQueue,
+            );
 
+        loop {
+            self.io_handler.write(i18n::text(
+                "reception.next_code",
+                &[("code", &ticket.display_code())],
+            ))?;
+
+            match self.io_handler.choose(&menu)? {
+                Some(CallAnswer::CallAgain) => {
+                    if !self.reception.call_again(ticket)? {
+                        self.io_handler.write(i18n::plural(
+                            "reception.no_show",
+                            ticket.recalls() + 1,
+                            &[("code", &ticket.display_code())],
+                        ))?;
+                        return Ok(false);
+                    }
+                }
+                Some(CallAnswer::ReturnToQueue) => {
+                    self.reception.return_to_queue(ticket.clone())?;
+                    self.io_handler.write(i18n::text(
+                        "reception.returned_to_queue",
+                        &[("code", &ticket.display_code())],
+                    ))?;
+                    return Ok(false);
+                }
+                Some(CallAnswer::CameForward) | None => return Ok(true),
+            }
+        }
+    }
+
+    fn get_pacient_account(&mut self) -> Result<Pacient> {
         self.io_handler
-            .write(
-                "\n[1] Sim\n\
-                [2] Não\n\
-                \n\
-                Algum dos campos está errado? ",
-            )
-            .unwrap();
-        let wrong_field = self.io_handler.read_line().unwrap();
+            .write(i18n::message("reception.searching_pacient"))?;
+        self.io_handler.write(i18n::message("prompt.cancel_hint"))?;
+
+        // a pacient whose account can't be found is sent back to the queue
+        // by cancelling
+        let pacient_accounts = self.reception.pacient_accounts();
+        let cpf = self.io_handler.ask(
+            &Prompt::cpf(i18n::message("reception.ask_pacient_cpf"))
+                .validate(|cpf| {
+                    pacient_accounts
+                        .query::<Pacient>(cpf.digits())
+                        .map(|_| ())
+                        .map_err(|_| i18n::message("reception.account_not_found").to_string())
+                })
+                .cancellable(),
+        )?;
+
+        self.reception.pacient_accounts().query(cpf.digits())
+    }
 
-        if wrong_field.trim() == "1" {
-            self.update_pacient_account(&mut pacient);
+    fn check_pacient_data(&mut self, mut pacient: Pacient) -> Result<Pacient> {
+        if Pacient::form(self.reception.now().date_naive())
+            .edit(&mut self.io_handler, &mut pacient)?
+        {
+            self.reception
+                .pacient_accounts()
+                .update(&pacient.get_key_attribute(), pacient.clone())?;
         }
 
-        pacient
+        Ok(pacient)
     }
 
-    // FIXME: the next two functions are extremely ugly
-    fn update_pacient_account(&mut self, pacient: &mut Pacient) {
+    fn create_pacient_account(&mut self) -> Result<Pacient> {
         self.io_handler
-            .write("Deixe o campo vazio em caso de não alteração\n")
-            .unwrap();
+            .write(i18n::message("reception.creating_account"))?;
+
+        let pacient_accounts = self.reception.pacient_accounts();
+        let cpf = self.io_handler.ask(
+            &Prompt::cpf(i18n::message("reception.ask_new_cpf"))
+                .validate(
+                    |cpf| match pacient_accounts.query::<Pacient>(cpf.digits()) {
+                        Ok(_) => Err(i18n::message("reception.account_exists").to_string()),
+                        Err(_) => Ok(()),
+                    },
+                )
+                .cancellable(),
+        )?;
+
+        let mut pacient = Pacient::empty(cpf, self.reception.now());
+        Pacient::form(self.reception.now().date_naive())
+            .create(&mut self.io_handler, &mut pacient)?;
+        pacient_accounts.insert(pacient.clone())?;
+
+        Ok(pacient)
+    }
 
-        self.io_handler.write("Número de celular: ").unwrap();
-        let phone_number = self.io_handler.read_line().unwrap();
-        if !phone_number.trim().is_empty() {
-            pacient.set_phone_number(phone_number.trim().to_string());
-        }
+    fn create_service_sheet(&mut self, pacient: Pacient) -> Result<ServiceSheet> {
+        self.io_handler
+            .write(i18n::message("reception.creating_sheet"))?;
 
-        self.io_handler.write("Rua: ").unwrap();
-        let street = self.io_handler.read_line().unwrap();
-        if !street.trim().is_empty() {
-            pacient.set_street(street.trim().to_string());
-        }
+        let reason = self
+            .io_handler
+            .ask(&Prompt::text(i18n::message("reception.ask_reason")))?;
 
-        self.io_handler.write("Bairro: ").unwrap();
-        let neighborhood = self.io_handler.read_line().unwrap();
-        if !neighborhood.trim().is_empty() {
-            pacient.set_neighborhood(neighborhood.trim().to_string());
-        }
+        let dentist = self.io_handler.ask(&Prompt::optional_text(i18n::message(
+            "reception.ask_dentist",
+        )))?;
 
-        self.io_handler.write("Cidade: ").unwrap();
-        let city = self.io_handler.read_line().unwrap();
-        if !city.trim().is_empty() {
-            pacient.set_city(city.trim().to_string());
-        }
+        let menu = Procedure::ALL
+            .iter()
+            .fold(
+                Menu::new(i18n::message("reception.ask_procedure")),
+                |menu, procedure| menu.option(procedure.label(), *procedure),
+            )
+            .back(i18n::message("reception.procedure_undecided"));
+        let procedure = self.io_handler.choose(&menu)?;
+
+        let sheet = ServiceSheet::new(pacient, reason, self.reception.now())
+            .with_dentist(dentist)
+            .with_procedure(procedure);
 
-        self.pacient_accounts
-            .update(&pacient.get_key_attribute(), pacient.clone())
-            .unwrap();
+        Ok(sheet)
     }
 
-    fn create_pacient_account(&mut self) -> String {
+    fn transfer_pacient(&mut self) -> Result<()> {
         self.io_handler
-            .write("\nCriando nova conta de paciente...\n")
-            .unwrap();
+            .write(i18n::message("reception.transferring"))?;
 
-        self.io_handler
-            .write("Insira o nome do paciente: ")
-            .unwrap();
-        let mut name = self.io_handler.read_line().unwrap();
-        name = name.trim().to_string();
+        let cpf = self
+            .io_handler
+            .ask(&Prompt::cpf(i18n::message("reception.cpf")))?;
 
-        self.io_handler
-            .write("\nInsira o cpf do paciente: ")
-            .unwrap();
-        let mut cpf = self.io_handler.read_line().unwrap();
-        cpf = cpf.trim().to_string();
-        let cpf_copy = cpf.to_string();
+        let from = match self.reception.dentist_queues_mut().find(&cpf)? {
+            Some(from) => from,
+            None => {
+                self.io_handler
+                    .write(i18n::message("reception.not_in_any_queue"))?;
+                return Ok(());
+            }
+        };
 
         self.io_handler
-            .write("\nInsira um número para contato do paciente: ")
-            .unwrap();
-        let mut phone_number = self.io_handler.read_line().unwrap();
-        phone_number = phone_number.trim().to_string();
+            .write(i18n::text("reception.in_queue", &[("queue", &from)]))?;
+        let menu = self
+            .reception
+            .dentist_queues()
+            .names()
+            .into_iter()
+            .filter(|name| *name != from)
+            .fold(
+                Menu::new(i18n::message("reception.ask_target_queue")),
+                |menu, name| menu.option(name, name.to_string()),
+            )
+            .back(i18n::message("reception.cancel_transfer"));
 
-        self.io_handler
-            .write("\nInsira a data de nascimento do paciente: ")
-            .unwrap();
-        let mut date_of_birth = self.io_handler.read_line().unwrap();
-        date_of_birth = date_of_birth.trim().to_string();
+        let to = match self.io_handler.choose(&menu)? {
+            Some(to) => to,
+            None => {
+                self.io_handler
+                    .write(i18n::message("reception.transfer_cancelled"))?;
+                return Ok(());
+            }
+        };
+
+        if self
+            .reception
+            .dentist_queues_mut()
+            .transfer(&cpf, &from, &to)?
+        {
+            self.io_handler
+                .write(i18n::text("reception.transferred", &[("queue", &to)]))?;
+        } else {
+            // called away by the dentist while the queue was being chosen
+            self.io_handler
+                .write(i18n::text("reception.transfer_failed", &[("queue", &from)]))?;
+        }
 
-        self.io_handler
-            .write("\nInsira a rua onde o paciente mora: ")
-            .unwrap();
-        let mut street = self.io_handler.read_line().unwrap();
-        street = street.trim().to_string();
+        Ok(())
+    }
 
+    fn process_payment(&mut self) -> Result<()> {
         self.io_handler
-            .write("\nInsira o bairro onde o paciente mora: ")
-            .unwrap();
-        let mut neighborhood = self.io_handler.read_line().unwrap();
-        neighborhood = neighborhood.trim().to_string();
+            .write(i18n::message("reception.processing_payment"))?;
+
+        let cpf = self
+            .io_handler
+            .ask(&Prompt::cpf(i18n::message("reception.cpf")))?;
+
+        let menu = Procedure::ALL
+            .iter()
+            .fold(
+                Menu::new(i18n::message("reception.ask_paid_procedure")),
+                |menu, procedure| {
+                    menu.option(
+                        i18n::text(
+                            "reception.procedure_price",
+                            &[
+                                ("procedure", &procedure.label()),
+                                ("price", &procedure.price()),
+                            ],
+                        ),
+                        *procedure,
+                    )
+                },
+            )
+            .back(i18n::message("reception.cancel_payment"));
+        let procedure = match self.io_handler.choose(&menu)? {
+            Some(procedure) => procedure,
+            None => return Ok(()),
+        };
+
+        let menu = PaymentMethod::ALL
+            .iter()
+            .fold(
+                Menu::new(i18n::message("reception.ask_payment_method")),
+                |menu, method| menu.option(method.label(), *method),
+            )
+            .back(i18n::message("reception.cancel_payment"));
+        let method = match self.io_handler.choose(&menu)? {
+            Some(method) => method,
+            None => return Ok(()),
+        };
+        self.payments_of_the_day.push(procedure.price());
 
-        self.io_handler
-            .write("\nInsira a cidade onde o paciente mora: ")
-            .unwrap();
-        let mut city = self.io_handler.read_line().unwrap();
-        city = city.trim().to_string();
-
-        let pacient_address = Address::new(street, neighborhood, city);
-
-        let pacient = Pacient::new(
-            name,
-            cpf,
-            phone_number,
-            date_of_birth,
-            pacient_address,
-            Local::now(),
-        );
+        let payment = Payment::new(cpf, procedure, method, self.reception.now());
+        self.payments.insert(payment.clone())?;
 
-        self.pacient_accounts.insert(pacient).unwrap();
+        self.io_handler
+            .write(i18n::message("reception.payment_done"))?;
+
+        if let Ok(pacient) = self
+            .reception
+            .pacient_accounts()
+            .query::<Pacient>(payment.cpf().digits())
+        {
+            let path = self.document_printer.receipt(&pacient, &payment)?;
+            self.io_handler
+                .write(i18n::text("reception.receipt_printed", &[("path", &path)]))?;
+        }
 
-        cpf_copy
+        Ok(())
     }
 
-    fn create_service_sheet(&mut self, pacient: Pacient) -> ServiceSheet {
+    fn get_todays_payments(&mut self) -> Result<()> {
         self.io_handler
-            .write("\nCriando ficha de atendimento...\n")
-            .unwrap();
+            .write(i18n::message("reception.todays_payments_title"))?;
+        self.io_handler.write(i18n::text(
+            "reception.todays_total",
+            &[("total", &self.payments_of_the_day.iter().sum::<usize>())],
+        ))?;
+
+        Ok(())
+    }
 
+    fn generate_daily_report(&mut self) -> Result<()> {
         self.io_handler
-            .write("Insira o motivo do atendimento: ")
-            .unwrap();
-        let mut reason = self.io_handler.read_line().unwrap();
-        reason = reason.trim().to_string();
+            .write(i18n::message("reception.generating_report"))?;
+
+        let date = self.io_handler.ask(
+            &Prompt::date(i18n::message("reception.ask_report_date"))
+                .or_default(self.reception.now().date_naive()),
+        )?;
+
+        self.reception.refresh()?;
+        let queue: Vec<PriorityQueueTicket> = self
+            .reception
+            .waiting_tickets()
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let report = DailyReport::build(
+            date,
+            &queue,
+            &self
+                .reception
+                .ticket_history()
+                .query_all::<TicketRecord>()?,
+            &self
+                .reception
+                .service_sheets_history()
+                .query_all::<ServiceSheet>()?,
+            &self
+                .reception
+                .appointment_schedule()
+                .query_all::<Appointment>()?,
+            &self.payments.query_all::<Payment>()?,
+        );
 
-        let sheet = ServiceSheet::new(pacient, reason, Local::now());
+        fs::create_dir_all(&self.reports_dir)?;
+        let path = Path::new(&self.reports_dir)
+            .join(format!("relatorio-{}.html", date.format("%Y-%m-%d")))
+            .to_string_lossy()
+            .to_string();
+        report.save_as_html(&path)?;
 
-        self.service_sheets_history.insert(sheet.clone()).unwrap();
+        self.io_handler
+            .write(i18n::text("reception.report_saved", &[("path", &path)]))?;
 
-        sheet
+        Ok(())
     }
 
-    fn enqueue_pacient_in_dentist_queue(&self, sheet: ServiceSheet, priority: TicketPriority) {
-        let mut dentist_queue;
-        if let Ok(queue) = JsonHandler::read_from_json(&self.dentist_queue_path) {
-            dentist_queue = PriorityQueue::from(queue);
-        } else {
-            dentist_queue = PriorityQueue::new()
+    fn manage_appointments(&mut self) -> Result<()> {
+        let menu = Menu::new(i18n::message("reception.appointments_menu"))
+            .option(
+                i18n::message("reception.make_appointment"),
+                AppointmentOperation::Make,
+            )
+            .option(
+                i18n::message("reception.update_appointment"),
+                AppointmentOperation::Update,
+            )
+            .option(
+                i18n::message("reception.delete_appointment"),
+                AppointmentOperation::Delete,
+            )
+            .option(
+                i18n::message("reception.show_appointments"),
+                AppointmentOperation::Show,
+            )
+            .option(
+                i18n::message("reception.export_appointments"),
+                AppointmentOperation::Export,
+            )
+            .option(
+                i18n::message("reception.import_appointments"),
+                AppointmentOperation::Import,
+            )
+            .back(i18n::message("reception.back"));
+
+        match self.io_handler.choose(&menu)? {
+            Some(AppointmentOperation::Make) => self.make_appointment(),
+            Some(AppointmentOperation::Update) => self.update_appointment(),
+            Some(AppointmentOperation::Delete) => self.delete_appointment(),
+            Some(AppointmentOperation::Show) => self.show_appointments(),
+            Some(AppointmentOperation::Export) => self.export_appointments(),
+            Some(AppointmentOperation::Import) => self.import_appointments(),
+            None => Ok(()),
         }
-        dentist_queue.enqueue(SheetWithPriority::new(sheet, priority));
-        JsonHandler::save_as_json(&self.dentist_queue_path, &dentist_queue.queue()).unwrap();
     }
 
-    fn process_payment(&mut self) {
-        self.io_handler
-            .write("\nProcessando pagamento...\n")
-            .unwrap();
+    fn ask_appointment_date(&mut self, message: &str) -> Result<String> {
+        Ok(self
+            .io_handler
+            .ask(&Prompt::date(message))?
+            .format("%d-%m-%Y")
+            .to_string())
+    }
 
+    // only pacients with an appointment are accepted
+    fn ask_scheduled_cpf(&mut self) -> Result<Cpf> {
+        let appointment_schedule = self.reception.appointment_schedule();
+        self.io_handler.ask(
+            &Prompt::cpf(i18n::message("reception.cpf")).validate(|cpf| {
+                appointment_schedule
+                    .query::<Appointment>(cpf.digits())
+                    .map(|_| ())
+                    .map_err(|_| i18n::message("reception.no_appointment").to_string())
+            }),
+        )
+    }
+
+    fn make_appointment(&mut self) -> Result<()> {
         self.io_handler
-            .write(
-                "[1] Consulta de rotina - R$200\n\
-                [2] Obturação - R$100\n\
-                [3] Limpeza - R$50\n\
-                \n\
-                Qual foi o tipo de antedimento? ",
-            )
-            .unwrap();
-        let procedure = self.io_handler.read_line().unwrap();
+            .write(i18n::message("reception.making_appointment"))?;
 
-        if procedure.trim() == "1" {
-            self.payments_of_the_day.push(200);
-        } else if procedure.trim() == "2" {
-            self.payments_of_the_day.push(100);
-        } else {
-            self.payments_of_the_day.push(50);
-        }
+        let cpf = self
+            .io_handler
+            .ask(&Prompt::cpf(i18n::message("reception.cpf")))?;
+        let date = self.ask_appointment_date(i18n::message("reception.ask_appointment_date"))?;
 
-        self.io_handler
-            .write("[1] Pix\n[2] Cartão\n[3] Espécie\n\nInsira a forma de pagamento: ")
-            .unwrap();
-        let _ = self.io_handler.read_line().unwrap();
+        self.reception
+            .appointment_schedule()
+            .insert(Appointment::new(cpf, date))?;
 
-        self.io_handler.write("Pagamento efetuado\n").unwrap();
+        Ok(())
     }
 
-    fn get_todays_payments(&mut self) {
-        self.io_handler
-            .write("\nRelatório de pagamentos do dia\n")
-            .unwrap();
+    fn update_appointment(&mut self) -> Result<()> {
         self.io_handler
-            .write(&format!(
-                "Total arrecadado: R${}\n",
-                self.payments_of_the_day.iter().sum::<usize>()
-            ))
-            .unwrap();
+            .write(i18n::message("reception.updating_appointment"))?;
+
+        let cpf = self.ask_scheduled_cpf()?;
+        let date =
+            self.ask_appointment_date(i18n::message("reception.ask_new_appointment_date"))?;
+
+        let appointment = Appointment::new(cpf.clone(), date);
+
+        self.reception
+            .appointment_schedule()
+            .update(cpf.digits(), appointment)?;
+
+        Ok(())
     }
 
-    fn manage_appointments(&mut self) {
+    fn delete_appointment(&mut self) -> Result<()> {
         self.io_handler
-            .write(
-                "[1] Marcar consulta\n\
-                [2] Remarcar consulta\n\
-                [3] Desmarcar consulta\n\
-                [4] Mostrar consultas marcadas\n\
-                \n\
-                Insira a operação que deseja fazer: ",
-            )
-            .unwrap();
-        let appointment_operation = self.io_handler.read_line().unwrap();
-
-        if appointment_operation.trim() == "1" {
-            self.make_appointment()
-        } else if appointment_operation.trim() == "2" {
-            self.update_appointment()
-        } else if appointment_operation.trim() == "3" {
-            self.delete_appointment()
-        } else {
-            self.show_appointments()
-        }
-    }
+            .write(i18n::message("reception.deleting_appointment"))?;
 
-    fn make_appointment(&mut self) {
-        self.io_handler.write("Marcando consulta...\n").unwrap();
+        let cpf = self.ask_scheduled_cpf()?;
 
-        self.io_handler.write("CPF do paciente: ").unwrap();
-        let cpf = self.io_handler.read_line().unwrap();
+        let _: Appointment = self.reception.appointment_schedule().delete(cpf.digits())?;
 
-        self.io_handler.write("Data em dd-mm-aaaa: ").unwrap();
-        let date = self.io_handler.read_line().unwrap();
+        Ok(())
+    }
+
+    fn show_appointments(&mut self) -> Result<()> {
+        self.io_handler
+            .write(i18n::message("reception.appointments"))?;
+
+        let appointments: PriorityQueue<Appointment> = self
+            .reception
+            .appointment_schedule()
+            .query_all::<Appointment>()?
+            .into_iter()
+            .collect();
+        self.io_handler
+            .write(self.output_format.render_all(&appointments.into_vec()))?;
 
-        self.appointment_schedule
-            .insert(Appointment::new(
-                cpf.trim().to_string(),
-                date.trim().to_string(),
-            ))
-            .unwrap();
+        Ok(())
     }
 
-    fn update_appointment(&mut self) {
-        self.io_handler.write("Remarcando consulta...\n").unwrap();
+    fn export_appointments(&mut self) -> Result<()> {
+        self.io_handler
+            .write(i18n::message("reception.exporting"))?;
+
+        let path = self
+            .io_handler
+            .ask(&Prompt::text(i18n::message("reception.ask_ics_path")))?;
+
+        let appointments: Vec<Appointment> = self.reception.appointment_schedule().query_all()?;
+        let accounts: Vec<Pacient> = self.reception.pacient_accounts().query_all()?;
 
-        self.io_handler.write("CPF do paciente: ").unwrap();
-        let mut cpf = self.io_handler.read_line().unwrap();
-        cpf = cpf.trim().to_string();
+        let report =
+            IcsHandler::save_as_ics(&path, &appointments, &accounts, self.reception.now())?;
 
         self.io_handler
-            .write("Insira a nova data (dd-mm-aaaa): ")
-            .unwrap();
-        let mut date = self.io_handler.read_line().unwrap();
-        date = date.trim().to_string();
+            .write(i18n::plural("reception.exported", report.exported(), &[]))?;
 
-        let appointment = Appointment::new(cpf.clone(), date);
+        for appointment in report.skipped() {
+            self.io_handler.write(i18n::text(
+                "reception.export_skipped",
+                &[("cpf", appointment.cpf()), ("date", &appointment.date())],
+            ))?;
+        }
 
-        self.appointment_schedule.update(&cpf, appointment).unwrap();
+        Ok(())
     }
 
-    fn delete_appointment(&mut self) {
-        self.io_handler.write("Desmarcando consulta...\n").unwrap();
+    fn import_appointments(&mut self) -> Result<()> {
+        self.io_handler
+            .write(i18n::message("reception.importing"))?;
 
-        self.io_handler.write("CPF do paciente: ").unwrap();
-        let cpf = self.io_handler.read_line().unwrap();
+        let path = self
+            .io_handler
+            .ask(&Prompt::text(i18n::message("reception.ask_ics_path")))?;
 
-        let _: Appointment = self.appointment_schedule.delete(cpf.trim()).unwrap();
-    }
+        let appointments: Vec<Appointment> = self.reception.appointment_schedule().query_all()?;
+        let report = IcsHandler::read_from_ics(&path, &appointments)?;
 
-    fn show_appointments(&mut self) {
-        self.io_handler.write("Consultas marcadas").unwrap();
+        for appointment in report.imported() {
+            self.reception
+                .appointment_schedule()
+                .insert(appointment.clone())?;
+        }
+
+        self.io_handler.write(i18n::plural(
+            "reception.imported",
+            report.imported().len(),
+            &[],
+        ))?;
+
+        for conflict in report.conflicts() {
+            let reason = i18n::message(match conflict.kind() {
+                ConflictKind::Duplicate => "reception.conflict_duplicate",
+                ConflictKind::AlreadyScheduled => "reception.conflict_already_scheduled",
+                ConflictKind::Invalid => "reception.conflict_invalid",
+            });
+
+            self.io_handler.write(i18n::text(
+                "reception.conflict",
+                &[("uid", &conflict.uid()), ("reason", &reason)],
+            ))?;
+        }
 
-        let appointments: Vec<Appointment> = self.appointment_schedule.query_all().unwrap();
-        appointments.into_iter().for_each(|appointment| {
-            self.io_handler.write(appointment).unwrap();
-            self.io_handler.write("\n").unwrap();
-        })
+        Ok(())
     }
 }
@@ -0,0 +1,611 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
+use common::cpf::Cpf;
+use common::i18n;
+use common::io_handler::Prompt;
+use common::pacient_account::Pacient;
+use common::payment::Procedure;
+use common::priority_queue::{Priority, PriorityQueueTicket};
+use common::service_sheet::ServiceSheet;
+use common::shutdown;
+use common::wait_estimator::describe_wait;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::reception::{Reception, WaitingTicket};
+
+// how long the screen waits for a key before reading the queues again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Cpf,
+    Name,
+    PhoneNumber,
+    DateOfBirth,
+    Street,
+    Neighborhood,
+    City,
+    Reason,
+    Dentist,
+    Procedure,
+}
+
+impl Field {
+    const ALL: [Field; 10] = [
+        Field::Cpf,
+        Field::Name,
+        Field::PhoneNumber,
+        Field::DateOfBirth,
+        Field::Street,
+        Field::Neighborhood,
+        Field::City,
+        Field::Reason,
+        Field::Dentist,
+        Field::Procedure,
+    ];
+
+    fn label(&self) -> &'static str {
+        i18n::message(match self {
+            Field::Cpf => "field.cpf",
+            Field::Name => "field.name",
+            Field::PhoneNumber => "field.phone_number",
+            Field::DateOfBirth => "field.date_of_birth",
+            Field::Street => "field.street",
+            Field::Neighborhood => "field.neighborhood",
+            Field::City => "field.city",
+            Field::Reason => "field.reason",
+            Field::Dentist => "field.dentist",
+            Field::Procedure => "field.procedure",
+        })
+    }
+
+    // the key of the pacient form field it stands for
+    fn key(&self) -> &'static str {
+        match self {
+            Field::Cpf => "cpf",
+            Field::Name => "name",
+            Field::PhoneNumber => "phone_number",
+            Field::DateOfBirth => "date_of_birth",
+            Field::Street => "street",
+            Field::Neighborhood => "neighborhood",
+            Field::City => "city",
+            Field::Reason => "reason",
+            Field::Dentist => "dentist",
+            Field::Procedure => "procedure",
+        }
+    }
+
+    // what the pacient form doesn't let be changed in an existing account
+    fn is_kept_by_account(&self) -> bool {
+        // which fields can be edited doesn't depend on the day
+        Pacient::form(NaiveDate::MAX)
+            .fields()
+            .iter()
+            .any(|field| field.key() == self.key() && !field.is_editable())
+    }
+}
+
+// the pacient account and service sheet being filled in, checked with the
+// same rules as the line-based questions
+#[derive(Default)]
+struct PacientForm {
+    values: [String; 10],
+    procedure: Option<Procedure>,
+    focus: usize,
+    account: Option<Pacient>,
+}
+
+impl PacientForm {
+    fn value(&self, field: Field) -> &str {
+        &self.values[field as usize]
+    }
+
+    fn focused(&self) -> Field {
+        Field::ALL[self.focus]
+    }
+
+    fn is_editable(&self, field: Field) -> bool {
+        field != Field::Procedure && !(self.account.is_some() && field.is_kept_by_account())
+    }
+
+    fn next(&mut self) {
+        self.focus = (self.focus + 1) % Field::ALL.len();
+    }
+
+    fn previous(&mut self) {
+        self.focus = (self.focus + Field::ALL.len() - 1) % Field::ALL.len();
+    }
+
+    fn type_char(&mut self, char: char) {
+        if self.is_editable(self.focused()) {
+            self.values[self.focus].push(char);
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.is_editable(self.focused()) {
+            self.values[self.focus].pop();
+        }
+    }
+
+    // goes through the procedures and "not decided yet"
+    fn cycle_procedure(&mut self, forward: bool) {
+        if self.focused() != Field::Procedure {
+            return;
+        }
+
+        let mut options = vec![None];
+        options.extend(Procedure::ALL.iter().copied().map(Some));
+        let idx = options
+            .iter()
+            .position(|procedure| *procedure == self.procedure)
+            .unwrap_or_default();
+        let idx = if forward {
+            (idx + 1) % options.len()
+        } else {
+            (idx + options.len() - 1) % options.len()
+        };
+        self.procedure = options[idx];
+    }
+
+    fn fill(&mut self, pacient: Pacient) {
+        self.values[Field::Cpf as usize] = pacient.cpf().to_string();
+        self.values[Field::Name as usize] = pacient.name().to_string();
+        self.values[Field::PhoneNumber as usize] = pacient.phone_number().to_string();
+        self.values[Field::DateOfBirth as usize] = pacient.date_of_birth().to_string();
+        self.values[Field::Street as usize] = pacient.street().to_string();
+        self.values[Field::Neighborhood as usize] = pacient.neighborhood().to_string();
+        self.values[Field::City as usize] = pacient.city().to_string();
+        self.account = Some(pacient);
+    }
+
+    fn display(&self, field: Field) -> String {
+        match field {
+            Field::Procedure => format!(
+                "< {} >",
+                self.procedure
+                    .map(|procedure| procedure.label())
+                    .unwrap_or(i18n::message("reception.procedure_undecided"))
+            ),
+            field => self.value(field).to_string(),
+        }
+    }
+
+    fn parse<T>(&self, field: Field, prompt: Prompt<T>) -> Result<T, String> {
+        prompt.parse(self.value(field)).map_err(|err| {
+            i18n::text(
+                "tui.invalid_field",
+                &[("field", &field.label()), ("error", &err)],
+            )
+        })
+    }
+
+    // the error names the first field that isn't valid
+    fn sheet(&self, now: DateTime<Local>) -> Result<ServiceSheet, String> {
+        let mut pacient = match &self.account {
+            Some(account) => account.clone(),
+            None => Pacient::empty(
+                self.parse(Field::Cpf, Prompt::cpf(Field::Cpf.label()))?,
+                now,
+            ),
+        };
+
+        for form_field in Pacient::form(now.date_naive()).fields() {
+            let field = match Field::ALL
+                .iter()
+                .find(|field| field.key() == form_field.key())
+            {
+                Some(field) if form_field.is_editable() => *field,
+                _ => continue,
+            };
+            let value = self.parse(field, form_field.prompt())?;
+            form_field.set(&mut pacient, value);
+        }
+
+        let reason = self.parse(Field::Reason, Prompt::text(Field::Reason.label()))?;
+        let dentist = self.parse(
+            Field::Dentist,
+            Prompt::optional_text(Field::Dentist.label()),
+        )?;
+
+        Ok(ServiceSheet::new(pacient, reason, now)
+            .with_dentist(dentist)
+            .with_procedure(self.procedure))
+    }
+}
+
+// full-screen attendance desk: the ticket queue, the dentist queues and the
+// day's appointments are kept up to date next to the form of the pacient
+// being attended
+pub struct ReceptionTui {
+    reception: Reception,
+    ticket: Option<PriorityQueueTicket>,
+    // the waiting tickets, as of the last refresh
+    queue: Vec<WaitingTicket>,
+    form: PacientForm,
+    agenda: Vec<(String, Option<String>)>,
+    status: String,
+    running: bool,
+}
+
+impl ReceptionTui {
+    pub fn new(reception: Reception) -> Self {
+        Self {
+            reception,
+            ticket: None,
+            queue: Vec::new(),
+            form: PacientForm::default(),
+            agenda: Vec::new(),
+            status: String::new(),
+            running: true,
+        }
+    }
+
+    // runs until Ctrl+Q, the terminal is given back even when it fails
+    pub fn start(mut self) -> Result<()> {
+        let legacy_cpfs = self.reception.legacy_cpfs()?;
+        if !legacy_cpfs.is_empty() {
+            let cpfs: Vec<String> = legacy_cpfs.iter().map(Cpf::to_string).collect();
+            self.status = i18n::plural(
+                "reception.legacy_cpfs",
+                cpfs.len(),
+                &[("cpfs", &cpfs.join(", "))],
+            )
+            .trim()
+            .to_string();
+        }
+
+        let mut terminal = ratatui::init();
+        let result = self.run(&mut terminal);
+        ratatui::restore();
+        result
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while self.running && !shutdown::interrupted() {
+            self.refresh();
+            terminal.draw(|frame| self.render(frame))?;
+
+            if event::poll(REFRESH_INTERVAL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        let result = self.handle_key(key);
+                        self.report(result);
+                    }
+                }
+            }
+        }
+
+        // the called ticket isn't lost when the station closes
+        match self.ticket.take() {
+            Some(ticket) => self.reception.return_to_queue(ticket),
+            None => Ok(()),
+        }
+    }
+
+    fn refresh(&mut self) {
+        let result = self.load();
+        self.report(result);
+    }
+
+    fn load(&mut self) -> Result<()> {
+        self.reception.refresh()?;
+        self.queue = self.reception.estimated_waits()?;
+
+        let accounts: Vec<Pacient> = self.reception.pacient_accounts().query_all()?;
+        self.agenda = self
+            .reception
+            .appointments_on(self.reception.now().date_naive())?
+            .iter()
+            .map(|appointment| {
+                let name = accounts
+                    .iter()
+                    .find(|pacient| pacient.cpf() == appointment.cpf())
+                    .map(|pacient| pacient.name().to_string());
+                (appointment.cpf().to_string(), name)
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    // errors are shown on the status line so the operator can try again
+    fn report(&mut self, result: Result<()>) {
+        if let Err(err) = result {
+            self.status = i18n::text("tui.failed", &[("error", &err)]);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('n') => self.call_next(),
+                KeyCode::Char('r') => self.call_again(),
+                KeyCode::Char('d') => self.return_to_queue(),
+                KeyCode::Char('l') => self.look_up_account(),
+                KeyCode::Char('s') => self.send_to_dentist(),
+                KeyCode::Char('q') | KeyCode::Char('c') => {
+                    self.running = false;
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+        }
+
+        match key.code {
+            KeyCode::Esc => self.form = PacientForm::default(),
+            KeyCode::Tab | KeyCode::Down | KeyCode::Enter => self.form.next(),
+            KeyCode::BackTab | KeyCode::Up => self.form.previous(),
+            KeyCode::Left => self.form.cycle_procedure(false),
+            KeyCode::Right => self.form.cycle_procedure(true),
+            KeyCode::Backspace => self.form.backspace(),
+            KeyCode::Char(char) => self.form.type_char(char),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn no_ticket(&mut self) -> Result<()> {
+        self.status = i18n::message("tui.no_ticket").to_string();
+        Ok(())
+    }
+
+    fn call_next(&mut self) -> Result<()> {
+        if let Some(ticket) = &self.ticket {
+            self.status = i18n::text("tui.ticket_pending", &[("code", &ticket.display_code())]);
+            return Ok(());
+        }
+
+        match self.reception.call_next()? {
+            Some(ticket) => {
+                self.status = i18n::text("tui.called", &[("code", &ticket.display_code())]);
+                self.ticket = Some(ticket);
+            }
+            None => self.status = i18n::message("reception.queue_empty").trim().to_string(),
+        }
+
+        Ok(())
+    }
+
+    fn call_again(&mut self) -> Result<()> {
+        let ticket = match self.ticket.as_mut() {
+            Some(ticket) => ticket,
+            None => return self.no_ticket(),
+        };
+
+        if self.reception.call_again(ticket)? {
+            self.status = i18n::text("tui.called_again", &[("code", &ticket.display_code())]);
+        } else {
+            self.status = i18n::plural(
+                "reception.no_show",
+                ticket.recalls() + 1,
+                &[("code", &ticket.display_code())],
+            )
+            .trim()
+            .to_string();
+            self.ticket = None;
+            self.form = PacientForm::default();
+        }
+
+        Ok(())
+    }
+
+    fn return_to_queue(&mut self) -> Result<()> {
+        let ticket = match &self.ticket {
+            Some(ticket) => ticket.clone(),
+            None => return self.no_ticket(),
+        };
+
+        let code = ticket.display_code();
+        self.reception.return_to_queue(ticket)?;
+        self.ticket = None;
+        self.status = i18n::text("reception.returned_to_queue", &[("code", &code)])
+            .trim()
+            .to_string();
+
+        Ok(())
+    }
+
+    // the pacient came forward once the operator starts filling in their data
+    fn look_up_account(&mut self) -> Result<()> {
+        if let Some(ticket) = self.ticket.as_mut() {
+            if ticket.lifecycle().started_at().is_none() {
+                ticket.lifecycle_mut().mark_started(self.reception.now());
+            }
+        }
+
+        let cpf = match self.form.parse(Field::Cpf, Prompt::cpf(Field::Cpf.label())) {
+            Ok(cpf) => cpf,
+            Err(err) => {
+                self.status = err;
+                return Ok(());
+            }
+        };
+
+        match self.reception.find_account(&cpf) {
+            Some(pacient) => {
+                self.status = i18n::text("tui.account_loaded", &[("name", &pacient.name())]);
+                self.form.fill(pacient);
+            }
+            None => self.status = i18n::message("tui.new_account").to_string(),
+        }
+
+        Ok(())
+    }
+
+    fn send_to_dentist(&mut self) -> Result<()> {
+        let mut ticket = match &self.ticket {
+            Some(ticket) => ticket.clone(),
+            None => return self.no_ticket(),
+        };
+
+        let sheet = match self.form.sheet(self.reception.now()) {
+            Ok(sheet) => sheet,
+            Err(err) => {
+                self.status = err;
+                return Ok(());
+            }
+        };
+
+        // an account is only updated after it was looked up
+        let pacient = sheet.pacient_account().clone();
+        if self.form.account.is_none() && self.reception.find_account(pacient.cpf()).is_some() {
+            self.status = i18n::message("reception.account_exists").to_string();
+            return Ok(());
+        }
+
+        if ticket.lifecycle().started_at().is_none() {
+            ticket.lifecycle_mut().mark_started(self.reception.now());
+        }
+        self.reception.save_account(pacient)?;
+        let queue = self.reception.send_to_dentist(&ticket, sheet)?;
+        self.reception.finish_ticket(&mut ticket)?;
+
+        self.ticket = None;
+        self.form = PacientForm::default();
+        self.status = i18n::text("reception.sent_to_queue", &[("queue", &queue)])
+            .trim()
+            .to_string();
+
+        Ok(())
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [panes, status, help] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(panes);
+        let [queue, dentist_queues] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(left);
+        let [form, agenda] = Layout::vertical([
+            Constraint::Length(Field::ALL.len() as u16 + 2),
+            Constraint::Min(0),
+        ])
+        .areas(right);
+
+        frame.render_widget(self.queue_pane(), queue);
+        frame.render_widget(self.dentist_queues_pane(), dentist_queues);
+        frame.render_widget(self.form_pane(), form);
+        frame.render_widget(self.agenda_pane(), agenda);
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+        frame.render_widget(
+            Paragraph::new(i18n::message("tui.help"))
+                .style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+    }
+
+    fn queue_pane(&self) -> Paragraph<'static> {
+        let title = i18n::text("tui.queue", &[("count", &self.queue.len())]);
+
+        let mut lines: Vec<Line> = self
+            .queue
+            .iter()
+            .map(|waiting| {
+                Line::from(format!(
+                    "{:<6} {:<24} {:<20} {}",
+                    waiting.ticket.display_code(),
+                    waiting.ticket.priority().label(),
+                    waiting.ticket.issued_at().unwrap_or_default(),
+                    describe_wait(waiting.wait_minutes)
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from(i18n::message("tui.empty")));
+        }
+
+        Paragraph::new(lines).block(Block::bordered().title(title))
+    }
+
+    fn dentist_queues_pane(&self) -> Paragraph<'static> {
+        let dentist_queues = self.reception.dentist_queues();
+
+        let mut lines = Vec::new();
+        for name in dentist_queues.names() {
+            let sheets = match dentist_queues.queue(name) {
+                Ok(queue) => queue.queue(),
+                Err(_) => continue,
+            };
+
+            lines.push(Line::styled(
+                format!("{} ({})", name, sheets.len()),
+                Style::new().add_modifier(Modifier::BOLD),
+            ));
+            for sheet in sheets {
+                lines.push(Line::from(format!(
+                    "  {} - {}",
+                    sheet.service_sheet().pacient(),
+                    sheet.service_sheet().reason()
+                )));
+            }
+        }
+
+        Paragraph::new(lines).block(Block::bordered().title(i18n::message("tui.dentist_queues")))
+    }
+
+    fn form_pane(&self) -> Paragraph<'static> {
+        let title = match &self.ticket {
+            Some(ticket) => i18n::text("tui.form_ticket", &[("code", &ticket.display_code())]),
+            None => i18n::message("tui.form").to_string(),
+        };
+
+        let lines: Vec<Line> = Field::ALL
+            .iter()
+            .map(|field| {
+                let line = format!(
+                    "{:<20} {}",
+                    format!("{}:", field.label()),
+                    self.form.display(*field)
+                );
+                let style = if *field == self.form.focused() {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else if self.form.is_editable(*field) || *field == Field::Procedure {
+                    Style::new()
+                } else {
+                    Style::new().add_modifier(Modifier::DIM)
+                };
+                Line::styled(line, style)
+            })
+            .collect();
+
+        Paragraph::new(lines).block(Block::bordered().title(title))
+    }
+
+    fn agenda_pane(&self) -> Paragraph<'static> {
+        let title = i18n::text(
+            "tui.agenda",
+            &[("date", &self.reception.now().format("%d-%m-%Y"))],
+        );
+
+        let mut lines: Vec<Line> = self
+            .agenda
+            .iter()
+            .map(|(cpf, name)| {
+                Line::from(format!(
+                    "{:<12} {}",
+                    cpf,
+                    name.as_deref().unwrap_or(i18n::message("tui.no_account"))
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from(i18n::message("tui.no_appointments")));
+        }
+
+        Paragraph::new(lines).block(Block::bordered().title(title))
+    }
+}
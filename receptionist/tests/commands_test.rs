@@ -0,0 +1,180 @@
+use std::fs;
+
+use anyhow::Result;
+use chrono::Local;
+use common::command::{CommandLine, CommandStatus};
+use common::cpf::Cpf;
+use common::database::Database;
+use common::json_handler::JsonHandler;
+use common::pacient_account::Pacient;
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority};
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::ticket_record::{TicketOutcome, TicketRecord};
+use receptionist::commands;
+use receptionist::reception::{Reception, DEFAULT_MAX_RECALLS};
+use serde_json::Value;
+
+fn sample_reception(dir: &str) -> Result<Reception> {
+    fs::create_dir_all(dir)?;
+    let queue_settings = QueueSettings::from_config(None, None)?;
+
+    Ok(Reception::new(
+        format!("{}/pacient_queue.json", dir),
+        QueueRouter::new(
+            vec![("geral".to_string(), format!("{}/dentist_queue.json", dir))],
+            &queue_settings,
+        )?,
+        queue_settings,
+        Database::new(format!("{}/pacient_accounts.json.db", dir))?,
+        Database::new(format!("{}/service_sheets.json.db", dir))?,
+        Database::new(format!("{}/appointment_schedule.json.db", dir))?,
+        Database::new(format!("{}/ticket_history.json.db", dir))?,
+        DEFAULT_MAX_RECALLS,
+    ))
+}
+
+// the status and what went to stdout
+fn run(reception: &mut Reception, args: &[&str]) -> Result<(CommandStatus, String)> {
+    let command = CommandLine::parse(args.iter().map(|arg| arg.to_string()));
+    let output_format = OutputFormat::from_args(args.iter().map(|arg| arg.to_string()))?;
+
+    let mut stdout = Vec::new();
+    let status = commands::run(&command, reception, output_format, &mut stdout)?;
+
+    Ok((status, String::from_utf8(stdout)?))
+}
+
+#[test]
+fn queue_commands_test() -> Result<()> {
+    let dir = "queue_commands_test";
+    let mut reception = sample_reception(dir)?;
+
+    let (status, stdout) = run(&mut reception, &["queue", "next", "--json"])?;
+    assert_eq!(status.code(), 3);
+    assert_eq!(stdout, "");
+
+    // the tickets the kiosk left in the queue file
+    let queue: PriorityQueue<PriorityQueueTicket> = vec![
+        PriorityQueueTicket::new(1, TicketPriority::Normal),
+        PriorityQueueTicket::new(2, TicketPriority::Emergency),
+    ]
+    .into();
+    JsonHandler::save_as_json(&format!("{}/pacient_queue.json", dir), &queue)?;
+
+    let (status, stdout) = run(&mut reception, &["queue", "list", "--json"])?;
+    assert_eq!(status.code(), 0);
+    let tickets: Value = serde_json::from_str(&stdout)?;
+    let codes: Vec<&Value> = tickets
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|ticket| &ticket["code"])
+        .collect();
+    assert_eq!(codes, vec!["E002", "N001"]);
+    // nobody was called yet, so each call is taken to last the default time
+    assert_eq!(tickets[0]["estimated_wait"], 0);
+    assert_eq!(tickets[1]["estimated_wait"], 10);
+
+    // only shown, the ticket stays in the queue
+    for _ in 0..2 {
+        let (status, stdout) = run(&mut reception, &["queue", "next", "--json"])?;
+        assert_eq!(status.code(), 0);
+        let ticket: Value = serde_json::from_str(&stdout)?;
+        assert_eq!(ticket["code"], "E002");
+    }
+
+    let (status, stdout) = run(&mut reception, &["queue", "clear"])?;
+    assert_eq!(status.code(), 2);
+    assert_eq!(stdout, "");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn pacient_and_appointment_commands_test() -> Result<()> {
+    let dir = "pacient_and_appointment_commands_test";
+    let mut reception = sample_reception(dir)?;
+
+    let cpf = Cpf::parse("529.982.247-25")?;
+    assert_eq!(
+        run(&mut reception, &["patient", "show", cpf.digits()])?
+            .0
+            .code(),
+        3
+    );
+    reception.save_account(Pacient::empty(cpf.clone(), Local::now()))?;
+
+    let (status, stdout) = run(&mut reception, &["patient", "show", cpf.digits(), "--json"])?;
+    assert_eq!(status.code(), 0);
+    let pacient: Value = serde_json::from_str(&stdout)?;
+    assert_eq!(pacient["cpf"], "529.982.247-25");
+
+    let (status, stdout) = run(&mut reception, &["appointments", "list", "--json"])?;
+    assert_eq!(status.code(), 0);
+    assert_eq!(
+        serde_json::from_str::<Value>(&stdout)?,
+        Value::Array(vec![])
+    );
+
+    let (status, stdout) = run(
+        &mut reception,
+        &["appointments", "list", "--date", "32-13-2024"],
+    )?;
+    assert_eq!(status.code(), 2);
+    assert_eq!(stdout, "");
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn legacy_cpfs_test() -> Result<()> {
+    let dir = "legacy_cpfs_test";
+    let reception = sample_reception(dir)?;
+    assert!(reception.legacy_cpfs()?.is_empty());
+
+    // an appointment saved before CPFs were checked
+    fs::write(
+        format!("{}/appointment_schedule.json.db", dir),
+        r#"[{ "cpf": "123", "date": "05-03-2024" }, { "cpf": "11144477735", "date": "06-03-2024" }]"#,
+    )?;
+    let legacy: Vec<String> = reception
+        .legacy_cpfs()?
+        .iter()
+        .map(|cpf| cpf.to_string())
+        .collect();
+    assert_eq!(legacy, vec!["123"]);
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn return_to_queue_test() -> Result<()> {
+    let dir = "return_to_queue_test";
+    let mut reception = sample_reception(dir)?;
+
+    let queue: PriorityQueue<PriorityQueueTicket> =
+        vec![PriorityQueueTicket::new(1, TicketPriority::Normal)].into();
+    JsonHandler::save_as_json(&format!("{}/pacient_queue.json", dir), &queue)?;
+
+    let ticket = reception.call_next()?.unwrap();
+    reception.return_to_queue(ticket)?;
+
+    // the call is kept in the history, and the ticket waits again
+    let history: Vec<TicketRecord> = reception.ticket_history().query_all()?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].outcome(), TicketOutcome::Requeued);
+    assert!(history[0].called_at().is_some());
+    reception.refresh()?;
+    assert_eq!(reception.waiting_tickets().len(), 1);
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::io::{self, BufRead, Cursor, Read};
+
+use anyhow::Result;
+use common::database::Database;
+use common::documents::DocumentPrinter;
+use common::io_handler::IOHandler;
+use common::json_handler::JsonHandler;
+use common::priority_queue::{PriorityQueue, PriorityQueueTicket, QueueSettings, TicketPriority};
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::shutdown;
+use receptionist::reception::{Reception, DEFAULT_MAX_RECALLS};
+use receptionist::service_manager::ServiceManager;
+
+// reads the given lines, then acts as if Ctrl-C was pressed while the
+// station waits for the next one
+struct InterruptedInput(Cursor<String>);
+
+impl Read for InterruptedInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.fill_buf()?.read(buf)?;
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for InterruptedInput {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let line = self.0.fill_buf()?;
+        if line.is_empty() {
+            shutdown::interrupt();
+        }
+        Ok(line)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.0.consume(amount);
+    }
+}
+
+// the only test of this file, since the interruption is seen by the whole
+// process
+#[test]
+fn interrupted_attendance_test() -> Result<()> {
+    let dir = "interrupted_attendance_test";
+    fs::create_dir_all(dir)?;
+    let queue_path = format!("{}/pacient_queue.json", dir);
+    let queue_settings = QueueSettings::from_config(None, None)?;
+
+    let queue: PriorityQueue<PriorityQueueTicket> =
+        vec![PriorityQueueTicket::new(1, TicketPriority::Normal)].into();
+    JsonHandler::save_as_json(&queue_path, &queue)?;
+
+    let reception = Reception::new(
+        queue_path.clone(),
+        QueueRouter::new(
+            vec![("geral".to_string(), format!("{}/dentist_queue.json", dir))],
+            &queue_settings,
+        )?,
+        queue_settings,
+        Database::new(format!("{}/pacient_accounts.json.db", dir))?,
+        Database::new(format!("{}/service_sheets.json.db", dir))?,
+        Database::new(format!("{}/appointment_schedule.json.db", dir))?,
+        Database::new(format!("{}/ticket_history.json.db", dir))?,
+        DEFAULT_MAX_RECALLS,
+    );
+
+    // interrupted while the pacient of N001 is asked whether they have an
+    // account
+    let mut manager = ServiceManager::new(
+        IOHandler::new(
+            InterruptedInput(Cursor::new("1\n1\n".to_string())),
+            Cursor::new(vec![]),
+        ),
+        reception,
+        Database::new(format!("{}/payments.json.db", dir))?,
+        OutputFormat::Text,
+        DocumentPrinter::new(format!("{}/documents", dir), None)?,
+        format!("{}/reports", dir),
+    );
+    manager.start()?;
+    assert!(shutdown::interrupted());
+
+    // the called ticket is back in the queue
+    let queue: PriorityQueue<PriorityQueueTicket> = JsonHandler::read_document(&queue_path)?;
+    assert_eq!(
+        queue.iter().map(|ticket| ticket.code()).collect::<Vec<_>>(),
+        vec![1]
+    );
+
+    fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
@@ -1 +1,59 @@
-// find a way to test the service manager
+use anyhow::Result;
+use common::database::Database;
+use common::dequeue_policy::parse_policy;
+use common::documents::DocumentPrinter;
+use common::priority_queue::QueueSettings;
+use common::queue_router::QueueRouter;
+use common::render::OutputFormat;
+use common::transcript::Session;
+use receptionist::reception::{Reception, DEFAULT_MAX_RECALLS};
+use receptionist::service_manager::ServiceManager;
+
+// a recorded attendance: the emergency ticket is called ahead of the two
+// waiting, the pacient gets an account and is sent to the dentist
+#[test]
+fn recorded_session_test() -> Result<()> {
+    let mut session = Session::Replay(format!(
+        "{}/tests/sessions/service_manager.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .start()?;
+
+    // the settings of the .env the session was recorded with
+    let queue_settings = QueueSettings::from_config(None, Some("30"))?
+        .with_policy(parse_policy(Some("ratio:2:1"))?)
+        .with_clock(session.clock());
+    let dentist_queues = QueueRouter::from_config(
+        "geral=dentist_queue.json,ortodontia=orthodontics_queue.json,\
+         endodontia=endodontics_queue.json,limpeza=cleaning_queue.json",
+        Some("reason:aparelho=ortodontia,reason:canal=endodontia,procedure:Cleaning=limpeza"),
+        &queue_settings,
+    )?
+    .with_files(|path| session.data_file(path))?;
+
+    let reception = Reception::new(
+        session.data_file("pacient_queue.json".to_string())?,
+        dentist_queues,
+        queue_settings,
+        Database::new(session.data_file("pacient_accounts.json.db".to_string())?)?,
+        Database::new(session.data_file("service_sheets.json.db".to_string())?)?,
+        Database::new(session.data_file("appointment_schedule.json.db".to_string())?)?,
+        Database::new(session.data_file("ticket_history.json.db".to_string())?)?,
+        DEFAULT_MAX_RECALLS,
+    )
+    .with_clock(session.clock());
+    let document_printer = DocumentPrinter::new(session.data_dir("documents".to_string())?, None)?
+        .with_clock(session.clock());
+
+    let mut manager = ServiceManager::new(
+        session.io_handler()?,
+        reception,
+        Database::new(session.data_file("payments.json.db".to_string())?)?,
+        OutputFormat::Text,
+        document_printer,
+        session.data_dir("reports".to_string())?,
+    );
+    manager.start()?;
+
+    session.finish(manager.io_handler())
+}